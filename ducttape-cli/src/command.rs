@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use clap::Subcommand;
+use ducttape_item_engine::text_renderer::ansi_renderer::AnsiRenderer;
+use ducttape_remote::{client::RemoteClient, error::RemoteResult};
+
+use crate::render;
+
+/// The commands of the console, the same ones are used as subcommands and in the REPL
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum Command {
+    /// Spawn an entity, e.g. `spawn player 10 20`
+    Spawn {
+        /// The entity kind, `player` and `dropped_item` name `Player` and `DroppedItem`
+        kind: String,
+        #[arg(allow_negative_numbers = true, default_value_t = 0.0)]
+        x: f32,
+        #[arg(allow_negative_numbers = true, default_value_t = 0.0)]
+        y: f32,
+    },
+    /// Entities of the current level
+    #[command(subcommand)]
+    Ent(EntityCommand),
+    /// Add items to the inventory, e.g. `give rope 3`
+    Give {
+        item: String,
+        #[arg(default_value_t = 1)]
+        amount: u32,
+    },
+    /// The player's inventory
+    #[command(subcommand)]
+    Inv(InventoryCommand),
+    /// The item registry
+    #[command(subcommand)]
+    Registry(RegistryCommand),
+    /// List the recipes
+    Recipes,
+    /// Craft a recipe from the inventory, e.g. `craft spear tip=rock shaft=rope`
+    Craft {
+        recipe: String,
+        /// `component=item`, the item is taken from the first slot holding it
+        #[arg(value_parser = parse_component)]
+        components: Vec<(String, String)>,
+    },
+    /// Show the state of the game
+    State,
+}
+
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum EntityCommand {
+    /// List the entities with their position
+    Ls,
+    /// Move an entity
+    Tp {
+        id: u64,
+        #[arg(allow_negative_numbers = true)]
+        x: f32,
+        #[arg(allow_negative_numbers = true)]
+        y: f32,
+    },
+    /// Remove an entity and its node
+    Rm { id: u64 },
+}
+
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum InventoryCommand {
+    /// List the inventory with the stats of every stack
+    Ls,
+    /// Take items out of a slot
+    Rm {
+        slot: u32,
+        #[arg(default_value_t = 1)]
+        count: u32,
+    },
+    /// Move a stack onto another slot, merging or swapping it
+    Mv { from: u32, to: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq, Subcommand)]
+pub enum RegistryCommand {
+    /// List every registered item
+    Ls,
+}
+
+fn parse_component(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((component, item)) if !component.is_empty() && !item.is_empty() => {
+            Ok((component.to_owned(), item.to_owned()))
+        }
+        _ => Err(format!("Expected component=item, got {}", arg)),
+    }
+}
+
+/// Entity kinds are PascalCase, so `player` and `dropped_item` become `Player` and `DroppedItem`
+pub fn entity_kind(kind: &str) -> String {
+    kind.split(['_', '-'])
+        .map(|word| {
+            let mut chars = word.chars();
+            chars
+                .next()
+                .map(|first| first.to_uppercase().chain(chars).collect::<String>())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+impl Command {
+    pub fn run(self, client: &impl RemoteClient) -> RemoteResult<()> {
+        match self {
+            Command::Spawn { kind, x, y } => {
+                let id = client.spawn_entity(&entity_kind(&kind), (x, y))?;
+                println!("Spawned {}", id);
+            }
+            Command::Ent(EntityCommand::Ls) => {
+                for entity in client.list_entities()? {
+                    println!("{}", render::entity_text(&entity).to_ansi_string());
+                }
+            }
+            Command::Ent(EntityCommand::Tp { id, x, y }) => client.set_position(id, (x, y))?,
+            Command::Ent(EntityCommand::Rm { id }) => client.remove_entity(id)?,
+            Command::Give { item, amount } => client.add_item(&item, amount)?,
+            Command::Inv(InventoryCommand::Ls) => {
+                for item in client.list_inventory()? {
+                    print!("{}", render::item_text(&item).to_ansi_string());
+                }
+            }
+            Command::Inv(InventoryCommand::Rm { slot, count }) => {
+                let taken = client.remove_item(slot, count)?;
+                println!("Took {} x{}", taken.name, taken.count);
+            }
+            Command::Inv(InventoryCommand::Mv { from, to }) => client.move_item(from, to)?,
+            Command::Registry(RegistryCommand::Ls) => {
+                for item in client.list_registry()? {
+                    println!("{}", render::registry_text(&item).to_ansi_string());
+                }
+            }
+            Command::Recipes => {
+                for recipe in client.list_recipes()? {
+                    println!("{}", recipe);
+                }
+            }
+            Command::Craft { recipe, components } => {
+                let components: HashMap<String, String> = components.into_iter().collect();
+                println!("Crafted {}", client.craft(&recipe, components)?);
+            }
+            Command::State => {
+                let state = client.get_game_state()?;
+                println!("{}", render::game_state_text(&state).to_ansi_string());
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kind_names() {
+        assert_eq!(entity_kind("player"), "Player");
+        assert_eq!(entity_kind("dropped_item"), "DroppedItem");
+        assert_eq!(entity_kind("ThrownItem"), "ThrownItem");
+    }
+
+    #[test]
+    fn components() {
+        assert_eq!(
+            parse_component("tip=rock"),
+            Ok(("tip".to_owned(), "rock".to_owned()))
+        );
+        assert!(parse_component("tip").is_err());
+        assert!(parse_component("=rock").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+//! A debug console for the running game, talks to the game over the JSON-RPC socket of
+//! `ducttape-remote`. Without a subcommand it starts a REPL with tab completion of item idents
+
+mod command;
+mod render;
+mod repl;
+
+use std::{path::PathBuf, process::ExitCode};
+
+use clap::Parser;
+use ducttape_remote::json_rpc::{self, JsonRpcClient};
+
+use crate::command::Command;
+
+/// Debug console of a running game
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// The game's socket, defaults to `$DUCTTAPE_REMOTE_SOCKET` or the one in the runtime
+    /// directory
+    #[arg(long)]
+    socket: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let path = cli.socket.unwrap_or_else(json_rpc::default_socket_path);
+    let client = match JsonRpcClient::connect(&path) {
+        Ok(client) => client,
+        Err(e) => {
+            eprintln!("Failed to connect to the game at {}: {}", path.display(), e);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let result = match cli.command {
+        Some(command) => command.run(&client).map_err(|e| e.to_string()),
+        None => repl::run(&client).map_err(|e| e.to_string()),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
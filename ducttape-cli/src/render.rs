@@ -0,0 +1,98 @@
+use ducttape_item_engine::attribute::{
+    schema::ATTRIBUTE_REGISTRY, AttributeModifier, AttributeType,
+};
+use ducttape_remote::info::{EntityInfo, GameStateInfo, ItemInfo, RegistryItemInfo};
+use valence_text::{color::NamedColor, Color, IntoText, Text};
+
+fn gray(text: String) -> Text {
+    text.into_text().color(Color::Named(NamedColor::Gray))
+}
+
+/// Aggregated stats of a stack, registered attributes first and in display order
+fn stats_text(item: &ItemInfo) -> Text {
+    let mut stats: Vec<(AttributeType, f64)> = item
+        .stats
+        .iter()
+        .map(|(id, value)| (AttributeType::new(id), *value))
+        .collect();
+
+    let registry = ATTRIBUTE_REGISTRY.read().unwrap();
+    stats.sort_by_key(|(at, _)| (registry.position(*at).unwrap_or(usize::MAX), *at));
+    drop(registry);
+
+    let mut txt = "".into_text();
+    for (at, value) in stats {
+        let value = at.get_schema().round(value);
+        txt += "    ".into_text() + at + AttributeModifier::Set(value) + "\n";
+    }
+
+    txt
+}
+
+/// A stack of the inventory with its slot and stats
+pub fn item_text(item: &ItemInfo) -> Text {
+    let mut txt = gray(format!("[{:>2}] ", item.slot))
+        + item.name.clone().into_text().bold()
+        + format!(" x{} ", item.count)
+        + gray(format!("({})", item.ident));
+
+    if !item.tags.is_empty() {
+        txt += gray(format!(" #{}", item.tags.join(" #")));
+    }
+
+    txt + "\n" + stats_text(item)
+}
+
+pub fn registry_text(item: &RegistryItemInfo) -> Text {
+    let mut txt = item.ident.clone().into_text().bold() + " " + gray(item.name.clone());
+
+    if item.compound {
+        txt += " compound"
+            .into_text()
+            .color(Color::Named(NamedColor::Aqua));
+    }
+    if !item.tags.is_empty() {
+        txt += gray(format!(" #{}", item.tags.join(" #")));
+    }
+
+    txt
+}
+
+pub fn entity_text(entity: &EntityInfo) -> Text {
+    let mut txt = gray(format!("{:>12} ", entity.id))
+        + entity.kind.clone().into_text().bold()
+        + format!(" at ({}, {})", entity.position.0, entity.position.1);
+
+    if entity.velocity != (0.0, 0.0) {
+        txt += gray(format!(
+            " moving ({}, {})",
+            entity.velocity.0, entity.velocity.1
+        ));
+    }
+    if !entity.tags.is_empty() {
+        txt += gray(format!(" #{}", entity.tags.join(" #")));
+    }
+
+    txt
+}
+
+pub fn game_state_text(state: &GameStateInfo) -> Text {
+    let level = if state.level.is_empty() {
+        "no level"
+    } else {
+        &state.level
+    };
+
+    state.state.clone().into_text().bold()
+        + " in "
+        + level.to_owned()
+        + "\n"
+        + gray(format!(
+            "player {} at ({}, {}), {} entities\n",
+            state.player, state.player_position.0, state.player_position.1, state.entities
+        ))
+        + gray(format!(
+            "inventory {}/{}, {}",
+            state.inventory_used, state.inventory_size, state.encumbrance
+        ))
+}
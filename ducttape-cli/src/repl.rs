@@ -0,0 +1,195 @@
+use clap::{CommandFactory, Parser};
+use ducttape_remote::client::RemoteClient;
+use rustyline::{
+    completion::{Completer, Pair},
+    error::ReadlineError,
+    highlight::Highlighter,
+    hint::Hinter,
+    history::DefaultHistory,
+    validate::Validator,
+    Context, Editor, Helper,
+};
+
+use crate::command::{Command, RegistryCommand};
+
+const PROMPT: &str = "ducttape> ";
+
+/// A line of the REPL, parsed like the subcommands
+#[derive(Debug, Parser)]
+#[command(no_binary_name = true, disable_version_flag = true)]
+struct ReplLine {
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Completes the command names, and item idents where a command takes an item
+struct ReplHelper {
+    commands: Vec<String>,
+    /// Idents of the item registry, fetched when the REPL starts and on `registry ls`
+    idents: Vec<String>,
+}
+
+/// Candidates for the word being typed, and how far into the word the candidates start
+fn complete_word(
+    words: &[&str],
+    word: &str,
+    commands: &[String],
+    idents: &[String],
+) -> (usize, Vec<String>) {
+    let matching = |candidates: &[String], prefix: &str| -> Vec<String> {
+        candidates
+            .iter()
+            .filter(|candidate| candidate.starts_with(prefix))
+            .cloned()
+            .collect()
+    };
+    let subcommands =
+        |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+    match words {
+        [] => (0, matching(commands, word)),
+        ["give"] => (0, matching(idents, word)),
+        ["inv"] => (0, matching(&subcommands(&["ls", "rm", "mv"]), word)),
+        ["ent"] => (0, matching(&subcommands(&["ls", "tp", "rm"]), word)),
+        ["registry"] => (0, matching(&subcommands(&["ls"]), word)),
+        ["craft", _, ..] => match word.split_once('=') {
+            Some((component, item)) => (component.len() + 1, matching(idents, item)),
+            None => (0, Vec::new()),
+        },
+        _ => (0, Vec::new()),
+    }
+}
+
+impl Completer for ReplHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let start = line.rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let words: Vec<&str> = line[..start].split_whitespace().collect();
+
+        let (offset, candidates) =
+            complete_word(&words, &line[start..], &self.commands, &self.idents);
+
+        Ok((
+            start + offset,
+            candidates
+                .into_iter()
+                .map(|candidate| Pair {
+                    display: candidate.clone(),
+                    replacement: candidate,
+                })
+                .collect(),
+        ))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+fn fetch_idents(client: &impl RemoteClient) -> Vec<String> {
+    match client.list_registry() {
+        Ok(items) => items.into_iter().map(|item| item.ident).collect(),
+        Err(e) => {
+            eprintln!("Failed to fetch the item registry: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Read and run commands until the input ends, failed commands are printed and skipped
+pub fn run(client: &impl RemoteClient) -> rustyline::Result<()> {
+    let commands = ReplLine::command()
+        .get_subcommands()
+        .map(|command| command.get_name().to_owned())
+        .chain(["exit".to_owned()])
+        .collect();
+
+    let mut editor: Editor<ReplHelper, DefaultHistory> = Editor::new()?;
+    editor.set_helper(Some(ReplHelper {
+        commands,
+        idents: fetch_idents(client),
+    }));
+
+    loop {
+        let line = match editor.readline(PROMPT) {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => return Ok(()),
+            Err(e) => return Err(e),
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line.as_str())?;
+
+        let Some(words) = shlex::split(&line) else {
+            eprintln!("Unbalanced quotes");
+            continue;
+        };
+        // A line that is only a `#` comment splits into no words
+        match words.first().map(String::as_str) {
+            None => continue,
+            Some("exit" | "quit") => return Ok(()),
+            Some(_) => {}
+        }
+
+        let command = match ReplLine::try_parse_from(words) {
+            Ok(line) => line.command,
+            Err(e) => {
+                let _ = e.print();
+                continue;
+            }
+        };
+
+        let refresh = command == Command::Registry(RegistryCommand::Ls);
+        if let Err(e) = command.run(client) {
+            eprintln!("{}", e);
+        } else if refresh {
+            if let Some(helper) = editor.helper_mut() {
+                helper.idents = fetch_idents(client);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn completion() {
+        let commands = vec!["give".to_owned(), "inv".to_owned()];
+        let idents = vec!["rock".to_owned(), "rope".to_owned(), "stick".to_owned()];
+
+        assert_eq!(
+            complete_word(&[], "gi", &commands, &idents),
+            (0, vec!["give".to_owned()])
+        );
+        assert_eq!(
+            complete_word(&["give"], "ro", &commands, &idents),
+            (0, vec!["rock".to_owned(), "rope".to_owned()])
+        );
+        assert_eq!(
+            complete_word(&["craft", "spear"], "shaft=st", &commands, &idents),
+            (6, vec!["stick".to_owned()])
+        );
+        assert_eq!(
+            complete_word(&["give", "rock"], "", &commands, &idents)
+                .1
+                .len(),
+            0
+        );
+    }
+}
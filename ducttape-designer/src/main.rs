@@ -0,0 +1,238 @@
+//! Checks and previews item templates without launching the game: validates the template
+//! assets, prints the stats of a template item and renders its texture to a PNG
+
+use std::{collections::HashMap, error::Error, path::PathBuf, process::ExitCode, sync::Arc};
+
+use clap::{Parser, Subcommand};
+use ducttape_item_engine::{
+    attribute::{schema::ATTRIBUTE_REGISTRY, AttributeParser},
+    item::{Item, ItemRegistry, ItemTexture},
+    template::{
+        loader::ItemTemplate,
+        source::{AssetSource, FsAssetSource},
+        validate::{validate_template, validate_templates, TemplateIssue},
+    },
+    text_renderer::ansi_renderer::AnsiRenderer,
+};
+use valence_text::{color::NamedColor, Color, IntoText, Text};
+
+/// Folder of the templates, relative to the asset folder
+const TEMPLATES_FOLDER: &str = "item/generated";
+/// Folder of the data item definitions, relative to the asset folder
+const ITEMS_FOLDER: &str = "items";
+const ATTRIBUTES_FILE: &str = "attributes.toml";
+
+/// Inspect, validate and render item templates
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Cli {
+    /// The game's asset folder, items and attributes are loaded from here
+    #[arg(long, default_value = "ducttape-godot/assets")]
+    assets: PathBuf,
+    /// The template folder, defaults to `item/generated` in the asset folder
+    #[arg(long)]
+    templates: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check every template, or only the given one
+    Validate { template: Option<String> },
+    /// Print the aggregated stats of a template item, e.g. `stats spear tip=rock shaft=rope`
+    Stats {
+        template: String,
+        /// `component=item`, the item has to be a registered data item
+        #[arg(value_parser = parse_component)]
+        components: Vec<(String, String)>,
+    },
+    /// Render the texture of a template item to a PNG, unassigned components use their fallback
+    Render {
+        template: String,
+        /// `component=item`, the image `{component}-{item}.png` of the template is used
+        #[arg(value_parser = parse_component)]
+        components: Vec<(String, String)>,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+}
+
+fn parse_component(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((component, item)) if !component.is_empty() && !item.is_empty() => {
+            Ok((component.to_owned(), item.to_owned()))
+        }
+        _ => Err(format!("Expected component=item, got {}", arg)),
+    }
+}
+
+fn colored(text: impl Into<String>, color: NamedColor) -> Text {
+    let text: String = text.into();
+    text.into_text().color(Color::Named(color))
+}
+
+/// Load a template and check that every assigned component exists
+fn load_template(
+    source: Arc<dyn AssetSource>,
+    name: &str,
+    components: &[(String, String)],
+) -> Result<ItemTemplate, Box<dyn Error>> {
+    let template = ItemTemplate::load_template(source, name)?;
+
+    let names = template.get_component_names();
+    for (component, _) in components {
+        if !names.contains(component) {
+            return Err(format!(
+                "{} has no component {}, its components are {}",
+                name,
+                component,
+                names.join(", ")
+            )
+            .into());
+        }
+    }
+
+    Ok(template)
+}
+
+fn print_issues(name: &str, issues: &[TemplateIssue]) {
+    let status = if issues.is_empty() {
+        colored(" ok", NamedColor::Green)
+    } else {
+        colored(format!(" {} issues", issues.len()), NamedColor::Red)
+    };
+    println!(
+        "{}",
+        (name.to_owned().into_text().bold() + status).to_ansi_string()
+    );
+
+    for issue in issues {
+        println!(
+            "  {}",
+            colored(issue.to_string(), NamedColor::Red).to_ansi_string()
+        );
+    }
+}
+
+/// Validate the templates, returns false if any template has issues
+fn validate(
+    source: Arc<dyn AssetSource>,
+    template: Option<String>,
+) -> Result<bool, Box<dyn Error>> {
+    let issues = match template {
+        Some(name) => {
+            let template = ItemTemplate::load_template(source, &name)?;
+            [(name, validate_template(&template))].into_iter().collect()
+        }
+        None => validate_templates(source)?,
+    };
+
+    for (name, issues) in issues.iter() {
+        print_issues(name, issues);
+    }
+
+    Ok(issues.values().all(Vec::is_empty))
+}
+
+fn stats(
+    assets: &FsAssetSource,
+    source: Arc<dyn AssetSource>,
+    name: &str,
+    components: Vec<(String, String)>,
+) -> Result<(), Box<dyn Error>> {
+    let template = load_template(source, name, &components)?;
+
+    let mut registry: ItemRegistry = ItemRegistry::new();
+    for e in registry.load_data_items(assets, ITEMS_FOLDER)?.errors {
+        eprintln!("{}", colored(e.to_string(), NamedColor::Yellow).to_ansi_string());
+    }
+
+    let components = components
+        .into_iter()
+        .map(|(component, ident)| match registry.get(&ident) {
+            Some(item) => Ok((component, item.clone())),
+            None => Err(format!("{} is not a data item", ident)),
+        })
+        .collect::<Result<HashMap<_, _>, _>>()?;
+
+    let item = template.populate_template(components);
+
+    let mut header = item.get_name().into_text().bold();
+    if !item.get_tags().is_empty() {
+        header += colored(
+            format!(" #{}", item.get_tags().join(" #")),
+            NamedColor::Gray,
+        );
+    }
+    let mut components: Vec<_> = item.get_components().into_iter().collect();
+    components.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (component, component_item) in components {
+        header +=
+            colored(format!("\n  {}: ", component), NamedColor::Gray) + component_item.get_name();
+    }
+
+    let stats = AttributeParser::from(item.get_stats().get_all_attributes()).into_text();
+    println!("{}\n\n{}", header.to_ansi_string(), stats.to_ansi_string());
+
+    Ok(())
+}
+
+fn render(
+    source: Arc<dyn AssetSource>,
+    name: &str,
+    components: Vec<(String, String)>,
+    output: PathBuf,
+) -> Result<(), Box<dyn Error>> {
+    let template = load_template(source, name, &components)?;
+
+    match template.render(&components.into_iter().collect())? {
+        ItemTexture::Image(image) => image.save(&output)?,
+        _ => return Err("The template didn't render to an image".into()),
+    }
+    println!("Rendered {} to {}", name, output.display());
+
+    Ok(())
+}
+
+fn run(cli: Cli) -> Result<bool, Box<dyn Error>> {
+    let assets = FsAssetSource::new(&cli.assets);
+    let templates: Arc<dyn AssetSource> = Arc::new(FsAssetSource::new(
+        cli.templates
+            .unwrap_or_else(|| cli.assets.join(TEMPLATES_FOLDER)),
+    ));
+
+    // Templates and items may use the extra attributes, a missing file only leaves them out
+    if assets.exists(ATTRIBUTES_FILE) {
+        ATTRIBUTE_REGISTRY
+            .write()
+            .unwrap()
+            .load(&assets, ATTRIBUTES_FILE)?;
+    }
+
+    match cli.command {
+        Command::Validate { template } => return validate(templates, template),
+        Command::Stats {
+            template,
+            components,
+        } => stats(&assets, templates, &template, components)?,
+        Command::Render {
+            template,
+            components,
+            output,
+        } => render(templates, &template, components, output)?,
+    }
+
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("{}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
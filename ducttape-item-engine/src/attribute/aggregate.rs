@@ -0,0 +1,332 @@
+use std::cmp::Ordering;
+
+use serde::{Deserialize, Serialize};
+use valence_text::{color::NamedColor, Color, IntoText, Text};
+
+use super::{
+    expression::Expression, schema::AttributeSchema, Attribute, AttributeModifier, AttributeType,
+};
+
+/// The stages of aggregating an attribute, every modifier of a layer is applied before the next layer
+///
+/// 1. `Base`: the schema default or formula, replaced by the highest priority `Set`
+/// 2. `Additive`: every `Add` is summed onto the base
+/// 3. `Multiplicative`: every `AddPercent` is summed and applied once, then each `Multiply` compounds
+/// 4. `Final`: the highest priority `Override` replaces the value, then every `Clamp` and the schema range
+///
+/// Within a layer modifiers are ordered by priority, then by uuid so equal priorities stay deterministic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum AttributeLayer {
+    Base,
+    Additive,
+    Multiplicative,
+    Final,
+}
+
+/// Order attributes the way they are aggregated
+pub fn compare_attributes(a: &Attribute, b: &Attribute) -> Ordering {
+    (a.modifier.get_layer(), a.priority, a.uuid).cmp(&(b.modifier.get_layer(), b.priority, b.uuid))
+}
+
+/// What changed the value in an aggregation step
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AggregationSource {
+    /// The default value of the attribute schema
+    Default,
+    /// The value of the formula of a derived attribute
+    Formula(Expression),
+    Modifier(Attribute),
+    /// The min and max of the attribute schema
+    Range { min: Option<f64>, max: Option<f64> },
+}
+
+/// A single step of the aggregation and the value after it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregationStep {
+    pub layer: AttributeLayer,
+    pub source: AggregationSource,
+    pub value: f64,
+}
+
+/// Every intermediate value of an aggregation, used to explain where a stat comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AggregationTrace {
+    at: AttributeType,
+    steps: Vec<AggregationStep>,
+    value: f64,
+    precision: u8,
+}
+
+impl AggregationTrace {
+    pub fn get_attribute_type(&self) -> AttributeType {
+        self.at
+    }
+
+    pub fn get_steps(&self) -> &[AggregationStep] {
+        &self.steps
+    }
+
+    /// Get the final value of the attribute
+    pub fn get_value(&self) -> f64 {
+        self.value
+    }
+
+    fn round(&self, value: f64) -> f64 {
+        let factor = 10f64.powi(self.precision as i32);
+        (value * factor).round() / factor
+    }
+}
+
+impl<'a> IntoText<'a> for AggregationTrace {
+    fn into_cow_text(self) -> std::borrow::Cow<'a, Text> {
+        let mut txt = "".into_text();
+
+        for step in self.steps.iter() {
+            let line = match &step.source {
+                AggregationSource::Default if step.value == 0.0 => continue,
+                AggregationSource::Default => {
+                    AttributeModifier::Set(step.value).into_text() + " (default)"
+                }
+                AggregationSource::Formula(formula) => {
+                    AttributeModifier::Set(step.value).into_text() + format!(" ({})", formula)
+                }
+                AggregationSource::Modifier(attribute) => attribute.clone().into_text(),
+                AggregationSource::Range { min, max } => {
+                    AttributeModifier::Clamp {
+                        min: *min,
+                        max: *max,
+                    }
+                    .into_text()
+                        + " (range)"
+                }
+            };
+
+            let value = format!(" → {}", self.round(step.value))
+                .into_text()
+                .color(Color::Named(NamedColor::DarkGray));
+
+            txt = txt + line + value + "\n";
+        }
+
+        let value = self.round(self.value);
+        (txt + AttributeModifier::Set(value)).into_cow_text()
+    }
+}
+
+fn push_step(steps: &mut Vec<AggregationStep>, attribute: &Attribute, value: f64) {
+    steps.push(AggregationStep {
+        layer: attribute.modifier.get_layer(),
+        source: AggregationSource::Modifier(attribute.clone()),
+        value,
+    });
+}
+
+/// Run the aggregation pipeline over the modifiers of a single attribute, see `AttributeLayer` for the order
+pub fn aggregate(schema: &AttributeSchema, attributes: &[Attribute]) -> AggregationTrace {
+    aggregate_from(schema, AggregationSource::Default, schema.default, attributes)
+}
+
+/// Run the aggregation pipeline starting from a different value than the schema default
+pub fn aggregate_from(
+    schema: &AttributeSchema,
+    source: AggregationSource,
+    base: f64,
+    attributes: &[Attribute],
+) -> AggregationTrace {
+    let mut attributes = attributes.to_vec();
+    attributes.sort_by(compare_attributes);
+
+    let mut steps = Vec::new();
+    let mut value = base;
+
+    steps.push(AggregationStep {
+        layer: AttributeLayer::Base,
+        source,
+        value,
+    });
+
+    // Sets are sorted by priority, so the last one wins
+    for attribute in attributes.iter() {
+        if let AttributeModifier::Set(s) = attribute.modifier {
+            value = s;
+            push_step(&mut steps, attribute, value);
+        }
+    }
+
+    for attribute in attributes.iter() {
+        if let AttributeModifier::Add(a) = attribute.modifier {
+            value += a;
+            push_step(&mut steps, attribute, value);
+        }
+    }
+
+    // Percentages all apply to the value before any of them, so +10% and +10% is +20%
+    let before_percent = value;
+    let mut percent = 0.0;
+    for attribute in attributes.iter() {
+        if let AttributeModifier::AddPercent(p) = attribute.modifier {
+            percent += p;
+            value = before_percent * (1.0 + percent);
+            push_step(&mut steps, attribute, value);
+        }
+    }
+
+    for attribute in attributes.iter() {
+        if let AttributeModifier::Multiply(m) = attribute.modifier {
+            value *= m;
+            push_step(&mut steps, attribute, value);
+        }
+    }
+
+    for attribute in attributes.iter() {
+        if let AttributeModifier::Override(o) = attribute.modifier {
+            value = o;
+            push_step(&mut steps, attribute, value);
+        }
+    }
+
+    for attribute in attributes.iter() {
+        if let AttributeModifier::Clamp { min, max } = attribute.modifier {
+            value = min.map_or(value, |min| value.max(min));
+            value = max.map_or(value, |max| value.min(max));
+            push_step(&mut steps, attribute, value);
+        }
+    }
+
+    let clamped = schema.clamp(value);
+    if clamped != value {
+        value = clamped;
+        steps.push(AggregationStep {
+            layer: AttributeLayer::Final,
+            source: AggregationSource::Range {
+                min: schema.min,
+                max: schema.max,
+            },
+            value,
+        });
+    }
+
+    AggregationTrace {
+        at: schema.id,
+        steps,
+        value,
+        precision: schema.precision,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use uuid::Uuid;
+
+    use crate::attribute::AttributeReason;
+
+    use super::*;
+
+    fn attribute(priority: u8, modifier: AttributeModifier) -> Attribute {
+        Attribute {
+            uuid: Uuid::new_v4(),
+            reason: AttributeReason::Hidden,
+            priority,
+            modifier,
+            condition: None,
+            lifetime: None,
+        }
+    }
+
+    fn schema() -> AttributeSchema {
+        AttributeSchema::new(AttributeType::new("Stamina"), "")
+    }
+
+    #[test]
+    fn layers_ignore_priority() {
+        // The multiplier has the lowest priority but still applies after the set and the add
+        let trace = aggregate(
+            &schema(),
+            &[
+                attribute(0, AttributeModifier::Multiply(2.0)),
+                attribute(5, AttributeModifier::Add(5.0)),
+                attribute(9, AttributeModifier::Set(10.0)),
+            ],
+        );
+
+        assert_eq!(trace.get_value(), 30.0);
+
+        let values: Vec<f64> = trace.get_steps().iter().map(|step| step.value).collect();
+        assert_eq!(values, [0.0, 10.0, 15.0, 30.0]);
+    }
+
+    #[test]
+    fn percent_and_compounding() {
+        let percent = aggregate(
+            &schema(),
+            &[
+                attribute(0, AttributeModifier::Set(100.0)),
+                attribute(1, AttributeModifier::AddPercent(0.5)),
+                attribute(2, AttributeModifier::AddPercent(0.5)),
+            ],
+        );
+        assert_eq!(percent.get_value(), 200.0);
+
+        let compounding = aggregate(
+            &schema(),
+            &[
+                attribute(0, AttributeModifier::Set(100.0)),
+                attribute(1, AttributeModifier::Multiply(1.5)),
+                attribute(2, AttributeModifier::Multiply(1.5)),
+            ],
+        );
+        assert_eq!(compounding.get_value(), 225.0);
+    }
+
+    #[test]
+    fn equal_priorities_are_deterministic() {
+        let low = Attribute {
+            uuid: Uuid::from_u128(1),
+            ..attribute(3, AttributeModifier::Set(1.0))
+        };
+        let high = Attribute {
+            uuid: Uuid::from_u128(2),
+            ..attribute(3, AttributeModifier::Set(2.0))
+        };
+
+        let forward = aggregate(&schema(), &[low.clone(), high.clone()]);
+        let backward = aggregate(&schema(), &[high, low]);
+
+        assert_eq!(forward, backward);
+        assert_eq!(forward.get_value(), 2.0);
+    }
+
+    #[test]
+    fn final_layer() {
+        let schema = schema().with_default(1.0).with_range(Some(0.0), Some(50.0));
+
+        let trace = aggregate(
+            &schema,
+            &[
+                attribute(0, AttributeModifier::Add(99.0)),
+                attribute(
+                    0,
+                    AttributeModifier::Clamp {
+                        min: None,
+                        max: Some(80.0),
+                    },
+                ),
+            ],
+        );
+        assert_eq!(trace.get_value(), 50.0);
+        assert!(matches!(
+            trace.get_steps().last().unwrap().source,
+            AggregationSource::Range { .. }
+        ));
+
+        let trace = aggregate(
+            &schema,
+            &[
+                attribute(0, AttributeModifier::Multiply(0.0)),
+                attribute(1, AttributeModifier::Override(7.0)),
+                attribute(0, AttributeModifier::Override(3.0)),
+            ],
+        );
+        assert_eq!(trace.get_value(), 7.0);
+    }
+}
@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+/// When a modifier applies, checked against the `AttributeContext` of the parser
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttributeCondition {
+    /// The item is in an equipment slot
+    Equipped,
+    /// The holder is in water, or the item got wet
+    Wet,
+    Sprinting,
+    /// The holder is in the biome with this name
+    InBiome(String),
+    /// A game specific flag is set, e.g. `"on_fire"`
+    Flag(String),
+    Not(Box<AttributeCondition>),
+    All(Vec<AttributeCondition>),
+    Any(Vec<AttributeCondition>),
+}
+
+impl AttributeCondition {
+    pub fn is_met(&self, context: &AttributeContext) -> bool {
+        match self {
+            AttributeCondition::Equipped => context.equipped,
+            AttributeCondition::Wet => context.wet,
+            AttributeCondition::Sprinting => context.sprinting,
+            AttributeCondition::InBiome(biome) => context.biome.as_ref() == Some(biome),
+            AttributeCondition::Flag(flag) => context.flags.contains(flag),
+            AttributeCondition::Not(condition) => !condition.is_met(context),
+            AttributeCondition::All(conditions) => conditions.iter().all(|c| c.is_met(context)),
+            AttributeCondition::Any(conditions) => conditions.iter().any(|c| c.is_met(context)),
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |conditions: &[AttributeCondition], separator: &str| {
+            conditions
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(separator)
+        };
+
+        match self {
+            AttributeCondition::Equipped => write!(f, "equipped"),
+            AttributeCondition::Wet => write!(f, "wet"),
+            AttributeCondition::Sprinting => write!(f, "sprinting"),
+            AttributeCondition::InBiome(biome) => write!(f, "in {}", biome),
+            AttributeCondition::Flag(flag) => write!(f, "{}", flag),
+            AttributeCondition::Not(condition) => write!(f, "not {}", condition),
+            AttributeCondition::All(conditions) => write!(f, "({})", join(conditions, " and ")),
+            AttributeCondition::Any(conditions) => write!(f, "({})", join(conditions, " or ")),
+        }
+    }
+}
+
+/// How long a modifier lasts, expired modifiers are ignored and can be removed with `Stats::remove_expired`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AttributeLifetime {
+    /// Expires once the context time reaches this many seconds
+    Until(f64),
+    /// Expires after the item has been used this many more times
+    Uses(u32),
+}
+
+impl AttributeLifetime {
+    /// A lifetime that ends `seconds` after `now`
+    pub fn seconds(now: f64, seconds: f64) -> Self {
+        AttributeLifetime::Until(now + seconds)
+    }
+
+    pub fn is_expired(&self, context: &AttributeContext) -> bool {
+        match self {
+            AttributeLifetime::Until(time) => context.time >= *time,
+            AttributeLifetime::Uses(uses) => *uses == 0,
+        }
+    }
+
+    /// Count a use of the item, returns true if the lifetime is over afterwards
+    pub fn consume_use(&mut self) -> bool {
+        if let AttributeLifetime::Uses(uses) = self {
+            *uses = uses.saturating_sub(1);
+            return *uses == 0;
+        }
+
+        false
+    }
+}
+
+impl std::fmt::Display for AttributeLifetime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeLifetime::Until(time) => write!(f, "until {}s", time),
+            AttributeLifetime::Uses(1) => write!(f, "1 use"),
+            AttributeLifetime::Uses(uses) => write!(f, "{} uses", uses),
+        }
+    }
+}
+
+/// The state of the world an item is in, used to decide which modifiers apply
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttributeContext {
+    pub equipped: bool,
+    pub wet: bool,
+    pub sprinting: bool,
+    pub biome: Option<String>,
+    pub flags: HashSet<String>,
+    /// Game time in seconds, compared against `AttributeLifetime::Until`
+    pub time: f64,
+}
+
+impl AttributeContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_equipped(mut self, equipped: bool) -> Self {
+        self.equipped = equipped;
+        self
+    }
+
+    pub fn with_wet(mut self, wet: bool) -> Self {
+        self.wet = wet;
+        self
+    }
+
+    pub fn with_sprinting(mut self, sprinting: bool) -> Self {
+        self.sprinting = sprinting;
+        self
+    }
+
+    pub fn with_biome(mut self, biome: &str) -> Self {
+        self.biome = Some(biome.to_owned());
+        self
+    }
+
+    pub fn with_flag(mut self, flag: &str) -> Self {
+        self.flags.insert(flag.to_owned());
+        self
+    }
+
+    pub fn with_time(mut self, time: f64) -> Self {
+        self.time = time;
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        attribute::{Attribute, AttributeModifier, AttributeParser, AttributeReason, AttributeType},
+        item::Stats,
+        prelude_items::stats::BasicStats,
+    };
+
+    use super::*;
+
+    fn boost(lifetime: AttributeLifetime) -> Attribute {
+        Attribute {
+            uuid: uuid::Uuid::new_v4(),
+            reason: AttributeReason::Display("🧪".into()),
+            priority: 1,
+            modifier: AttributeModifier::Add(5.0),
+            condition: None,
+            lifetime: Some(lifetime),
+        }
+    }
+
+    #[test]
+    fn conditions() {
+        let context = AttributeContext::new()
+            .with_sprinting(true)
+            .with_biome("caves")
+            .with_flag("on_fire");
+
+        assert!(AttributeCondition::Sprinting.is_met(&context));
+        assert!(AttributeCondition::Flag("on_fire".into()).is_met(&context));
+        assert!(!AttributeCondition::InBiome("surface".into()).is_met(&context));
+        assert!(AttributeCondition::Any(vec![
+            AttributeCondition::Wet,
+            AttributeCondition::InBiome("caves".into())
+        ])
+        .is_met(&context));
+        assert!(!AttributeCondition::All(vec![
+            AttributeCondition::Equipped,
+            AttributeCondition::Sprinting
+        ])
+        .is_met(&context));
+        assert!(AttributeCondition::Not(Box::new(AttributeCondition::Wet)).is_met(&context));
+    }
+
+    #[test]
+    fn lifetimes() {
+        let mut stats = BasicStats::new();
+        stats.push_attribute(AttributeType::Sharpness, boost(AttributeLifetime::Uses(2)));
+        stats.push_attribute(
+            AttributeType::Agility,
+            boost(AttributeLifetime::seconds(10.0, 30.0)),
+        );
+
+        let sharpness = |stats: &BasicStats, context: AttributeContext| {
+            AttributeParser::from(stats.get_all_attributes())
+                .with_context(context)
+                .aggregate_to_value(AttributeType::Sharpness)
+        };
+
+        stats.consume_use();
+        assert_eq!(sharpness(&stats, AttributeContext::new()), 5.0);
+
+        stats.consume_use();
+        assert_eq!(sharpness(&stats, AttributeContext::new()), 0.0);
+        assert!(stats.get_attributes(AttributeType::Sharpness).is_empty());
+
+        let agility = AttributeParser::from(stats.get_all_attributes())
+            .with_context(AttributeContext::new().with_time(50.0))
+            .aggregate_to_value(AttributeType::Agility);
+        assert_eq!(agility, 0.0);
+
+        stats.remove_expired(&AttributeContext::new().with_time(20.0));
+        assert_eq!(stats.get_attributes(AttributeType::Agility).len(), 1);
+
+        stats.remove_expired(&AttributeContext::new().with_time(40.0));
+        assert!(stats.get_attributes(AttributeType::Agility).is_empty());
+    }
+}
@@ -0,0 +1,630 @@
+use std::{collections::BTreeSet, iter::Peekable, str::CharIndices};
+
+use super::AttributeType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    /// Dividing by zero gives zero, so a missing attribute doesn't break every stat derived from it
+    Divide,
+}
+
+impl BinaryOperator {
+    fn precedence(&self) -> u8 {
+        match self {
+            BinaryOperator::Add | BinaryOperator::Subtract => 1,
+            BinaryOperator::Multiply | BinaryOperator::Divide => 2,
+        }
+    }
+
+    fn symbol(&self) -> char {
+        match self {
+            BinaryOperator::Add => '+',
+            BinaryOperator::Subtract => '-',
+            BinaryOperator::Multiply => '*',
+            BinaryOperator::Divide => '/',
+        }
+    }
+
+    fn apply(&self, left: f64, right: f64) -> f64 {
+        match self {
+            BinaryOperator::Add => left + right,
+            BinaryOperator::Subtract => left - right,
+            BinaryOperator::Multiply => left * right,
+            BinaryOperator::Divide if right == 0.0 => 0.0,
+            BinaryOperator::Divide => left / right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Function {
+    Min,
+    Max,
+    Abs,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "abs" => Some(Function::Abs),
+            _ => None,
+        }
+    }
+
+    fn get_name(&self) -> &'static str {
+        match self {
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Abs => "abs",
+        }
+    }
+
+    fn arity(&self) -> usize {
+        match self {
+            Function::Min | Function::Max => 2,
+            Function::Abs => 1,
+        }
+    }
+
+    fn apply(&self, args: &[f64]) -> f64 {
+        match self {
+            Function::Min => args[0].min(args[1]),
+            Function::Max => args[0].max(args[1]),
+            Function::Abs => args[0].abs(),
+        }
+    }
+}
+
+/// A formula over other attributes, e.g. `Reach - Weight / 10`
+///
+/// In TOML expressions are written as strings, in Rust they can be parsed or built with operators:
+/// `Expression::from(AttributeType::Sharpness) * AttributeType::Agility.into()`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expression {
+    Number(f64),
+    /// The aggregated value of an attribute
+    Attribute(AttributeType),
+    Negate(Box<Expression>),
+    Binary(BinaryOperator, Box<Expression>, Box<Expression>),
+    Call(Function, Vec<Expression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExpressionError {
+    /// The expression could not be parsed, `position` is the byte offset of the problem.
+    Syntax {
+        expression: String,
+        position: usize,
+        message: String,
+    },
+    /// The expression calls a function that doesn't exist.
+    UnknownFunction(String),
+    /// A function was called with the wrong number of arguments.
+    Arity {
+        function: String,
+        expected: usize,
+        found: usize,
+    },
+    /// Attributes are derived from each other, the first and last attribute are the same.
+    Cycle(Vec<AttributeType>),
+}
+
+impl std::fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExpressionError::Syntax {
+                expression,
+                position,
+                message,
+            } => write!(f, "{} at {} in \"{}\"", message, position, expression),
+            ExpressionError::UnknownFunction(function) => {
+                write!(f, "Unknown function: {}", function)
+            }
+            ExpressionError::Arity {
+                function,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} takes {} arguments but got {}",
+                function, expected, found
+            ),
+            ExpressionError::Cycle(cycle) => {
+                let ids: Vec<&str> = cycle.iter().map(|at| at.get_id()).collect();
+                write!(f, "Derived attributes depend on each other: {}", ids.join(" -> "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ExpressionError {}
+
+pub type ExpressionResult<T> = Result<T, ExpressionError>;
+
+impl Expression {
+    pub fn number(value: f64) -> Self {
+        Expression::Number(value)
+    }
+
+    pub fn attribute(at: AttributeType) -> Self {
+        Expression::Attribute(at)
+    }
+
+    pub fn call(function: Function, args: Vec<Expression>) -> ExpressionResult<Self> {
+        if args.len() != function.arity() {
+            return Err(ExpressionError::Arity {
+                function: function.get_name().to_owned(),
+                expected: function.arity(),
+                found: args.len(),
+            });
+        }
+
+        Ok(Expression::Call(function, args))
+    }
+
+    pub fn parse(expression: &str) -> ExpressionResult<Self> {
+        let mut parser = Parser {
+            expression,
+            chars: expression.char_indices().peekable(),
+        };
+
+        let parsed = parser.parse_sum()?;
+        parser.skip_whitespace();
+
+        match parser.chars.peek().copied() {
+            None => Ok(parsed),
+            Some((position, c)) => Err(parser.error(position, &format!("Unexpected '{}'", c))),
+        }
+    }
+
+    /// Get every attribute the expression reads
+    pub fn get_dependencies(&self) -> BTreeSet<AttributeType> {
+        let mut dependencies = BTreeSet::new();
+        self.collect_dependencies(&mut dependencies);
+        dependencies
+    }
+
+    fn collect_dependencies(&self, dependencies: &mut BTreeSet<AttributeType>) {
+        match self {
+            Expression::Number(_) => {}
+            Expression::Attribute(at) => {
+                dependencies.insert(*at);
+            }
+            Expression::Negate(expression) => expression.collect_dependencies(dependencies),
+            Expression::Binary(_, left, right) => {
+                left.collect_dependencies(dependencies);
+                right.collect_dependencies(dependencies);
+            }
+            Expression::Call(_, args) => {
+                for arg in args {
+                    arg.collect_dependencies(dependencies);
+                }
+            }
+        }
+    }
+
+    /// Compute the expression, `lookup` gets the value of each attribute the expression reads
+    pub fn evaluate(
+        &self,
+        lookup: &mut dyn FnMut(AttributeType) -> ExpressionResult<f64>,
+    ) -> ExpressionResult<f64> {
+        Ok(match self {
+            Expression::Number(value) => *value,
+            Expression::Attribute(at) => lookup(*at)?,
+            Expression::Negate(expression) => -expression.evaluate(lookup)?,
+            Expression::Binary(operator, left, right) => {
+                operator.apply(left.evaluate(lookup)?, right.evaluate(lookup)?)
+            }
+            Expression::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| arg.evaluate(lookup))
+                    .collect::<ExpressionResult<Vec<f64>>>()?;
+
+                function.apply(&args)
+            }
+        })
+    }
+
+    fn binary(operator: BinaryOperator, left: Expression, right: Expression) -> Self {
+        Expression::Binary(operator, Box::new(left), Box::new(right))
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Binary(operator, _, _) => operator.precedence(),
+            _ => u8::MAX,
+        }
+    }
+}
+
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expression::Number(value) => write!(f, "{}", value),
+            Expression::Attribute(at) => write!(f, "{}", at),
+            Expression::Negate(expression) if expression.precedence() < u8::MAX => {
+                write!(f, "-({})", expression)
+            }
+            Expression::Negate(expression) => write!(f, "-{}", expression),
+            Expression::Binary(operator, left, right) => {
+                // Operators are left associative, so the right side needs parentheses on equal precedence
+                if left.precedence() < operator.precedence() {
+                    write!(f, "({})", left)?;
+                } else {
+                    write!(f, "{}", left)?;
+                }
+
+                write!(f, " {} ", operator.symbol())?;
+
+                if right.precedence() <= operator.precedence() {
+                    write!(f, "({})", right)
+                } else {
+                    write!(f, "{}", right)
+                }
+            }
+            Expression::Call(function, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", function.get_name(), args.join(", "))
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for Expression {
+    type Err = ExpressionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Expression::parse(s)
+    }
+}
+
+impl From<f64> for Expression {
+    fn from(value: f64) -> Self {
+        Expression::Number(value)
+    }
+}
+
+impl From<AttributeType> for Expression {
+    fn from(at: AttributeType) -> Self {
+        Expression::Attribute(at)
+    }
+}
+
+impl std::ops::Add for Expression {
+    type Output = Expression;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        Expression::binary(BinaryOperator::Add, self, rhs)
+    }
+}
+
+impl std::ops::Sub for Expression {
+    type Output = Expression;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        Expression::binary(BinaryOperator::Subtract, self, rhs)
+    }
+}
+
+impl std::ops::Mul for Expression {
+    type Output = Expression;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        Expression::binary(BinaryOperator::Multiply, self, rhs)
+    }
+}
+
+impl std::ops::Div for Expression {
+    type Output = Expression;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        Expression::binary(BinaryOperator::Divide, self, rhs)
+    }
+}
+
+impl std::ops::Neg for Expression {
+    type Output = Expression;
+
+    fn neg(self) -> Self::Output {
+        Expression::Negate(Box::new(self))
+    }
+}
+
+impl serde::Serialize for Expression {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for Expression {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let expression = String::deserialize(deserializer)?;
+        Expression::parse(&expression).map_err(serde::de::Error::custom)
+    }
+}
+
+/// Recursive descent parser, one function per precedence level
+struct Parser<'a> {
+    expression: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn error(&self, position: usize, message: &str) -> ExpressionError {
+        ExpressionError::Syntax {
+            expression: self.expression.to_owned(),
+            position,
+            message: message.to_owned(),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|(_, c)| c.is_whitespace()).is_some() {}
+    }
+
+    /// Consume the next character if it is `expected`
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if(|(_, c)| *c == expected).is_some()
+    }
+
+    fn position(&mut self) -> usize {
+        self.chars
+            .peek()
+            .map_or(self.expression.len(), |(position, _)| *position)
+    }
+
+    fn parse_sum(&mut self) -> ExpressionResult<Expression> {
+        let mut left = self.parse_product()?;
+
+        loop {
+            let operator = if self.eat('+') {
+                BinaryOperator::Add
+            } else if self.eat('-') {
+                BinaryOperator::Subtract
+            } else {
+                return Ok(left);
+            };
+
+            left = Expression::binary(operator, left, self.parse_product()?);
+        }
+    }
+
+    fn parse_product(&mut self) -> ExpressionResult<Expression> {
+        let mut left = self.parse_unary()?;
+
+        loop {
+            let operator = if self.eat('*') {
+                BinaryOperator::Multiply
+            } else if self.eat('/') {
+                BinaryOperator::Divide
+            } else {
+                return Ok(left);
+            };
+
+            left = Expression::binary(operator, left, self.parse_unary()?);
+        }
+    }
+
+    fn parse_unary(&mut self) -> ExpressionResult<Expression> {
+        if self.eat('-') {
+            return Ok(-self.parse_unary()?);
+        }
+
+        self.parse_atom()
+    }
+
+    fn parse_atom(&mut self) -> ExpressionResult<Expression> {
+        self.skip_whitespace();
+        let start = self.position();
+
+        match self.chars.peek().map(|(_, c)| *c) {
+            Some('(') => {
+                self.chars.next();
+                let expression = self.parse_sum()?;
+
+                if !self.eat(')') {
+                    let position = self.position();
+                    return Err(self.error(position, "Expected ')'"));
+                }
+
+                Ok(expression)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let number = self.take_while(|c| c.is_ascii_digit() || c == '.');
+
+                number
+                    .parse()
+                    .map(Expression::Number)
+                    .map_err(|_| self.error(start, &format!("Invalid number '{}'", number)))
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let name = self.take_while(|c| c.is_alphanumeric() || c == '_');
+
+                if !self.eat('(') {
+                    return Ok(Expression::Attribute(AttributeType::new(name)));
+                }
+
+                let function = Function::from_name(name)
+                    .ok_or_else(|| ExpressionError::UnknownFunction(name.to_owned()))?;
+
+                let mut args = Vec::new();
+                if !self.eat(')') {
+                    loop {
+                        args.push(self.parse_sum()?);
+
+                        if self.eat(')') {
+                            break;
+                        }
+                        if !self.eat(',') {
+                            let position = self.position();
+                            return Err(self.error(position, "Expected ',' or ')'"));
+                        }
+                    }
+                }
+
+                Expression::call(function, args)
+            }
+            Some(c) => Err(self.error(start, &format!("Unexpected '{}'", c))),
+            None => Err(self.error(start, "Unexpected end of expression")),
+        }
+    }
+
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> &'a str {
+        let start = self.position();
+        while self.chars.next_if(|(_, c)| predicate(*c)).is_some() {}
+        let end = self.position();
+
+        &self.expression[start..end]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::attribute::{
+        aggregate::AggregationSource,
+        schema::{AttributeRegistry, AttributeSchema},
+        Attribute, AttributeModifier, AttributeParser, AttributeReason,
+    };
+
+    use super::*;
+
+    fn modifier(modifier: AttributeModifier) -> Vec<Attribute> {
+        vec![Attribute {
+            uuid: uuid::Uuid::new_v4(),
+            reason: AttributeReason::Hidden,
+            priority: 0,
+            modifier,
+            condition: None,
+            lifetime: None,
+        }]
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let expression = Expression::parse("Reach - Weight / 10").unwrap();
+        assert_eq!(
+            expression,
+            Expression::from(AttributeType::Reach)
+                - Expression::from(AttributeType::Weight) / Expression::from(10.0)
+        );
+        assert_eq!(expression.to_string(), "Reach - Weight / 10");
+
+        let expression = Expression::parse("-(a - (b - c)) * max(Sharpness, 2.5)").unwrap();
+        assert_eq!(expression.to_string(), "-(a - (b - c)) * max(Sharpness, 2.5)");
+        assert_eq!(
+            expression.get_dependencies(),
+            BTreeSet::from([
+                AttributeType::new("a"),
+                AttributeType::new("b"),
+                AttributeType::new("c"),
+                AttributeType::Sharpness
+            ])
+        );
+    }
+
+    #[test]
+    fn evaluate() {
+        let expression = Expression::parse("Sharpness * (Agility + 1) / 0 + abs(-2)").unwrap();
+        let value = expression
+            .evaluate(&mut |at| Ok(if at == AttributeType::Sharpness { 3.0 } else { 4.0 }))
+            .unwrap();
+
+        assert_eq!(value, 2.0);
+    }
+
+    #[test]
+    fn errors() {
+        assert!(matches!(
+            Expression::parse("Sharpness *"),
+            Err(ExpressionError::Syntax { position: 11, .. })
+        ));
+        assert!(matches!(
+            Expression::parse("(Sharpness"),
+            Err(ExpressionError::Syntax { .. })
+        ));
+        assert!(matches!(
+            Expression::parse("sqrt(Sharpness)"),
+            Err(ExpressionError::UnknownFunction(_))
+        ));
+        assert!(matches!(
+            Expression::parse("min(Sharpness)"),
+            Err(ExpressionError::Arity { expected: 2, .. })
+        ));
+    }
+
+    #[test]
+    fn derived_attributes() {
+        let mass = AttributeType::new("Mass");
+        let speed = AttributeType::new("Speed");
+        let momentum = AttributeType::new("Momentum");
+        let impact = AttributeType::new("Impact");
+
+        let mut registry = AttributeRegistry::with_builtins();
+        registry.register(
+            AttributeSchema::new(momentum, "")
+                .with_formula(Expression::from(mass) * Expression::from(speed)),
+        );
+        registry.register(
+            AttributeSchema::new(impact, "").with_formula("Momentum / 2 + 1".parse().unwrap()),
+        );
+
+        let parser = AttributeParser::from(HashMap::from([
+            (mass, modifier(AttributeModifier::Set(4.0))),
+            (speed, modifier(AttributeModifier::Set(3.0))),
+            (impact, modifier(AttributeModifier::Multiply(2.0))),
+        ]));
+        let values = parser.aggregate_to_values_in(&registry);
+
+        assert_eq!(values[&momentum], 12.0);
+        // Modifiers of a derived attribute apply on top of its formula
+        assert_eq!(values[&impact], 14.0);
+        assert!(matches!(
+            parser.explain_in(&registry, impact).get_steps()[0].source,
+            AggregationSource::Formula(_)
+        ));
+        // The global registry doesn't know the attributes, so nothing is derived
+        assert_eq!(parser.aggregate_to_value(momentum), 0.0);
+    }
+
+    #[test]
+    fn derived_cycles() {
+        let egg = AttributeType::new("Egg");
+        let chicken = AttributeType::new("Chicken");
+
+        let mut registry = AttributeRegistry::new();
+        registry
+            .load_toml(
+                r#"
+                [[attribute]]
+                id = "Egg"
+                formula = "Chicken + 1"
+
+                [[attribute]]
+                id = "Chicken"
+                formula = "max(Egg, 0)"
+                "#,
+            )
+            .unwrap();
+
+        assert_eq!(registry.find_cycle(), Some(vec![egg, chicken, egg]));
+        assert!(AttributeRegistry::with_builtins().find_cycle().is_none());
+
+        let parser =
+            AttributeParser::from(HashMap::from([(egg, modifier(AttributeModifier::Set(5.0)))]));
+        assert!(matches!(
+            parser.try_explain_in(&registry, egg),
+            Err(ExpressionError::Cycle(_))
+        ));
+        // The formula is ignored, so only the modifiers are left
+        assert_eq!(parser.aggregate_to_value_in(&registry, egg), 5.0);
+    }
+}
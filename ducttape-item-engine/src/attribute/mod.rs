@@ -0,0 +1,505 @@
+pub mod aggregate;
+pub mod condition;
+pub mod expression;
+pub mod schema;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Mutex,
+};
+
+use aggregate::{AggregationSource, AggregationTrace, AttributeLayer};
+use condition::{AttributeCondition, AttributeContext, AttributeLifetime};
+use expression::{ExpressionError, ExpressionResult};
+use schema::{get_attribute_schema, AttributeRegistry, AttributeSchema, ATTRIBUTE_REGISTRY};
+use valence_text::{color::NamedColor, Color, IntoText, Text};
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(untagged)]
+pub enum AttributeReason {
+    Hidden,
+    Display(String),
+}
+
+/// Identifies an attribute, the registered `AttributeSchema` says how it is displayed and clamped.
+/// Ids are interned so attribute types stay `Copy`
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub struct AttributeType(&'static str);
+
+#[allow(non_upper_case_globals)]
+impl AttributeType {
+    /// How much damage the item can deal
+    pub const Sharpness: AttributeType = AttributeType("Sharpness");
+    /// How much damage the item can take
+    pub const Durability: AttributeType = AttributeType("Durability");
+    /// How much the item weighs
+    pub const Weight: AttributeType = AttributeType("Weight");
+    /// How much weight you can be supported
+    pub const Strength: AttributeType = AttributeType("Strength");
+    /// How fast you can attack
+    pub const Agility: AttributeType = AttributeType("Agility");
+    /// How far you can reach
+    pub const Reach: AttributeType = AttributeType("Reach");
+}
+
+lazy_static::lazy_static! {
+    static ref INTERNED_IDS: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
+impl AttributeType {
+    /// Get the attribute type for an id, the attribute doesn't have to be registered
+    pub fn new(id: &str) -> Self {
+        let mut interned = INTERNED_IDS.lock().unwrap();
+
+        match interned.get(id) {
+            Some(id) => AttributeType(id),
+            None => {
+                let id: &'static str = Box::leak(id.to_owned().into_boxed_str());
+                interned.insert(id);
+                AttributeType(id)
+            }
+        }
+    }
+
+    pub fn get_id(&self) -> &'static str {
+        self.0
+    }
+
+    /// Get the registered schema of this attribute
+    pub fn get_schema(&self) -> AttributeSchema {
+        get_attribute_schema(*self)
+    }
+}
+
+impl std::fmt::Debug for AttributeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Display for AttributeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl serde::Serialize for AttributeType {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for AttributeType {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let id = String::deserialize(deserializer)?;
+        Ok(AttributeType::new(&id))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Attribute {
+    pub uuid: uuid::Uuid,
+    pub reason: AttributeReason,
+    pub priority: u8,
+    pub modifier: AttributeModifier,
+    /// Only applies while the condition is met, always applies without a condition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub condition: Option<AttributeCondition>,
+    /// Stops applying once the lifetime is over, lasts forever without a lifetime
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub lifetime: Option<AttributeLifetime>,
+}
+
+/// Namespace of the uuids generated for attributes by `Attribute::stable_uuid`
+const ATTRIBUTE_NAMESPACE: uuid::Uuid =
+    uuid::Uuid::from_u128(0x6d1c_2f0e_83a4_4b8e_9f55_0c3e_a1d7_42b9);
+
+impl Attribute {
+    /// Get a uuid that is the same every time for the same path, for attributes generated at
+    /// runtime that have to keep their uuid across calls and loads, e.g. `["spear", "Weight"]`
+    pub fn stable_uuid(path: &[&str]) -> uuid::Uuid {
+        uuid::Uuid::new_v5(&ATTRIBUTE_NAMESPACE, path.join("/").as_bytes())
+    }
+
+    /// Check if the attribute applies in the context
+    pub fn is_active(&self, context: &AttributeContext) -> bool {
+        self.condition
+            .iter()
+            .all(|condition| condition.is_met(context))
+            && !self.is_expired(context)
+    }
+
+    pub fn is_expired(&self, context: &AttributeContext) -> bool {
+        self.lifetime
+            .as_ref()
+            .is_some_and(|lifetime| lifetime.is_expired(context))
+    }
+}
+
+/// How an attribute changes a value, see `aggregate` for the order modifiers are applied in
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum AttributeModifier {
+    /// Multiplies the value, compounding with other multipliers
+    Multiply(f64),
+    Add(f64),
+    /// Sets the base value, the highest priority set wins
+    Set(f64),
+    /// Adds a percentage of the value, percentages are summed before they are applied (0.1 = +10%)
+    AddPercent(f64),
+    /// Replaces the value after every other modifier, the highest priority override wins
+    Override(f64),
+    /// Keeps the final value within a range
+    Clamp { min: Option<f64>, max: Option<f64> },
+}
+
+impl AttributeModifier {
+    /// Get the aggregation layer this modifier is applied in
+    pub fn get_layer(&self) -> AttributeLayer {
+        match self {
+            AttributeModifier::Set(_) => AttributeLayer::Base,
+            AttributeModifier::Add(_) => AttributeLayer::Additive,
+            AttributeModifier::AddPercent(_) | AttributeModifier::Multiply(_) => {
+                AttributeLayer::Multiplicative
+            }
+            AttributeModifier::Override(_) | AttributeModifier::Clamp { .. } => {
+                AttributeLayer::Final
+            }
+        }
+    }
+
+    pub fn is_buff(&self) -> bool {
+        match self {
+            AttributeModifier::Multiply(m) => *m > 1.0,
+            AttributeModifier::Add(a) | AttributeModifier::AddPercent(a) => *a > 0.0,
+            AttributeModifier::Set(_)
+            | AttributeModifier::Override(_)
+            | AttributeModifier::Clamp { .. } => false,
+        }
+    }
+
+    pub fn is_neutral(&self) -> bool {
+        match self {
+            AttributeModifier::Multiply(m) => *m == 1.0,
+            AttributeModifier::Add(a) | AttributeModifier::AddPercent(a) => *a == 0.0,
+            AttributeModifier::Set(_)
+            | AttributeModifier::Override(_)
+            | AttributeModifier::Clamp { .. } => true,
+        }
+    }
+
+    pub fn is_debuff(&self) -> bool {
+        match self {
+            AttributeModifier::Multiply(m) => *m < 1.0,
+            AttributeModifier::Add(a) | AttributeModifier::AddPercent(a) => *a < 0.0,
+            AttributeModifier::Set(_)
+            | AttributeModifier::Override(_)
+            | AttributeModifier::Clamp { .. } => false,
+        }
+    }
+
+    pub fn stat_color(&self) -> NamedColor {
+        if self.is_buff() {
+            NamedColor::Green
+        } else if self.is_neutral() {
+            NamedColor::Yellow
+        } else if self.is_debuff() {
+            NamedColor::Red
+        } else {
+            NamedColor::White
+        }
+    }
+}
+
+impl std::fmt::Display for AttributeModifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AttributeModifier::Multiply(m) => {
+                write!(f, " x {}", m)
+            }
+            AttributeModifier::Add(a) => {
+                if *a > 0.0 {
+                    write!(f, " + {}", a)
+                } else {
+                    write!(f, " - {}", a.abs())
+                }
+            }
+            AttributeModifier::Set(s) => {
+                write!(f, " = {}", s)
+            }
+            AttributeModifier::AddPercent(p) => {
+                if *p > 0.0 {
+                    write!(f, " + {}%", p * 100.0)
+                } else {
+                    write!(f, " - {}%", p.abs() * 100.0)
+                }
+            }
+            AttributeModifier::Override(o) => {
+                write!(f, " := {}", o)
+            }
+            AttributeModifier::Clamp { min, max } => {
+                let bound = |bound: &Option<f64>| bound.map_or("..".to_owned(), |b| b.to_string());
+                write!(f, " in [{}, {}]", bound(min), bound(max))
+            }
+        }
+    }
+}
+
+impl<'a> IntoText<'a> for AttributeModifier {
+    fn into_cow_text(self) -> std::borrow::Cow<'a, Text> {
+        format!("{}", self)
+            .into_text()
+            .color(Color::Named(self.stat_color()))
+            .into_cow_text()
+    }
+}
+
+impl<'a> IntoText<'a> for AttributeType {
+    fn into_cow_text(self) -> std::borrow::Cow<'a, Text> {
+        self.get_schema().get_icon().to_owned().into_cow_text()
+    }
+}
+
+impl<'a> IntoText<'a> for Attribute {
+    fn into_cow_text(self) -> std::borrow::Cow<'a, Text> {
+        let mut txt = self.modifier.into_text();
+        if let AttributeReason::Display(name) = self.reason {
+            txt = txt + " (" + name + ")";
+        }
+
+        let mut notes = Vec::new();
+        if let Some(condition) = self.condition {
+            notes.push(format!("while {}", condition));
+        }
+        if let Some(lifetime) = self.lifetime {
+            notes.push(lifetime.to_string());
+        }
+        if !notes.is_empty() {
+            txt += format!(" [{}]", notes.join(", "))
+                .into_text()
+                .color(Color::Named(NamedColor::Gray));
+        }
+
+        txt.into_cow_text()
+    }
+}
+
+/// Helper struct for aggregatijng attributes
+#[derive(Clone)]
+pub struct AttributeParser {
+    attributes: HashMap<AttributeType, Vec<Attribute>>,
+    context: AttributeContext,
+}
+
+impl From<HashMap<AttributeType, Vec<Attribute>>> for AttributeParser {
+    fn from(attributes: HashMap<AttributeType, Vec<Attribute>>) -> Self {
+        AttributeParser {
+            attributes,
+            context: AttributeContext::default(),
+        }
+    }
+}
+
+impl Default for AttributeParser {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AttributeParser {
+    pub fn new() -> Self {
+        AttributeParser {
+            attributes: HashMap::new(),
+            context: AttributeContext::default(),
+        }
+    }
+
+    /// Evaluate conditional and temporary attributes against a context, by default
+    /// nothing is equipped, wet or sprinting and the time is 0
+    pub fn with_context(mut self, context: AttributeContext) -> Self {
+        self.context = context;
+        self
+    }
+
+    pub fn set_context(&mut self, context: AttributeContext) {
+        self.context = context;
+    }
+
+    pub fn get_context(&self) -> &AttributeContext {
+        &self.context
+    }
+
+    pub fn push(&mut self, at: AttributeType, attribute: Attribute) {
+        let vec = self.attributes.entry(at).or_default();
+        vec.push(attribute);
+        self.sort();
+    }
+
+    fn sort(&mut self) {
+        for (_, vec) in self.attributes.iter_mut() {
+            vec.sort_by(aggregate::compare_attributes);
+        }
+    }
+
+    fn active_attributes(&self, at: AttributeType) -> Vec<Attribute> {
+        self.attributes
+            .get(&at)
+            .into_iter()
+            .flatten()
+            .filter(|attribute| attribute.is_active(&self.context))
+            .cloned()
+            .collect()
+    }
+
+    /// Run the aggregation pipeline for an attribute, keeping every intermediate value.
+    /// Attributes that don't apply in the context are left out, derived attributes start from their formula
+    pub fn try_explain(&self, at: AttributeType) -> ExpressionResult<AggregationTrace> {
+        self.try_explain_in(&ATTRIBUTE_REGISTRY.read().unwrap(), at)
+    }
+
+    /// Like `try_explain`, with the schemas and formulas of the given registry
+    pub fn try_explain_in(
+        &self,
+        registry: &AttributeRegistry,
+        at: AttributeType,
+    ) -> ExpressionResult<AggregationTrace> {
+        self.explain_derived(registry, at, &mut Vec::new())
+    }
+
+    fn explain_derived(
+        &self,
+        registry: &AttributeRegistry,
+        at: AttributeType,
+        path: &mut Vec<AttributeType>,
+    ) -> ExpressionResult<AggregationTrace> {
+        if path.contains(&at) {
+            let mut cycle = path.clone();
+            cycle.push(at);
+            return Err(ExpressionError::Cycle(cycle));
+        }
+
+        let schema = registry.get_schema(at);
+        let attributes = self.active_attributes(at);
+
+        let Some(formula) = schema.formula.clone() else {
+            return Ok(aggregate::aggregate(&schema, &attributes));
+        };
+
+        path.push(at);
+        let base = formula.evaluate(&mut |dependency| {
+            Ok(self.explain_derived(registry, dependency, path)?.get_value())
+        })?;
+        path.pop();
+
+        Ok(aggregate::aggregate_from(
+            &schema,
+            AggregationSource::Formula(formula),
+            base,
+            &attributes,
+        ))
+    }
+
+    /// Like `try_explain`, but a formula that depends on itself is ignored
+    pub fn explain(&self, at: AttributeType) -> AggregationTrace {
+        self.explain_in(&ATTRIBUTE_REGISTRY.read().unwrap(), at)
+    }
+
+    pub fn explain_in(&self, registry: &AttributeRegistry, at: AttributeType) -> AggregationTrace {
+        self.try_explain_in(registry, at).unwrap_or_else(|_| {
+            aggregate::aggregate(&registry.get_schema(at), &self.active_attributes(at))
+        })
+    }
+
+    /// Aggregate every modifier of an attribute into its final value
+    pub fn aggregate_to_value(&self, at: AttributeType) -> f64 {
+        self.explain(at).get_value()
+    }
+
+    pub fn aggregate_to_value_in(&self, registry: &AttributeRegistry, at: AttributeType) -> f64 {
+        self.explain_in(registry, at).get_value()
+    }
+
+    /// The attributes the parser has modifiers for, and every registered derived attribute that reads any of them
+    pub fn get_attribute_types(&self) -> Vec<AttributeType> {
+        self.get_attribute_types_in(&ATTRIBUTE_REGISTRY.read().unwrap())
+    }
+
+    pub fn get_attribute_types_in(&self, registry: &AttributeRegistry) -> Vec<AttributeType> {
+        let mut types: Vec<AttributeType> = self.attributes.keys().copied().collect();
+
+        for schema in registry.iter().filter(|schema| schema.is_derived()) {
+            if !types.contains(&schema.id)
+                && registry
+                    .get_dependencies(schema.id)
+                    .iter()
+                    .any(|dependency| self.attributes.contains_key(dependency))
+            {
+                types.push(schema.id);
+            }
+        }
+
+        types
+    }
+
+    pub fn aggregate_to_values(&self) -> HashMap<AttributeType, f64> {
+        self.aggregate_to_values_in(&ATTRIBUTE_REGISTRY.read().unwrap())
+    }
+
+    pub fn aggregate_to_values_in(
+        &self,
+        registry: &AttributeRegistry,
+    ) -> HashMap<AttributeType, f64> {
+        self.get_attribute_types_in(registry)
+            .into_iter()
+            .map(|at| (at, self.aggregate_to_value_in(registry, at)))
+            .collect()
+    }
+
+    pub fn aggregate_to_fixed_attribute(&self, at: AttributeType) -> Attribute {
+        Attribute {
+            uuid: uuid::Uuid::new_v4(),
+            reason: AttributeReason::Hidden,
+            priority: 0,
+            modifier: AttributeModifier::Set(self.aggregate_to_value(at)),
+            condition: None,
+            lifetime: None,
+        }
+    }
+
+    pub fn aggregate_to_fixed_attributes(&self) -> HashMap<AttributeType, Attribute> {
+        self.attributes
+            .keys()
+            .map(|at| (*at, self.aggregate_to_fixed_attribute(*at)))
+            .collect()
+    }
+
+    /// Render the step by step breakdown of an attribute
+    pub fn aggregate_to_component(&self, at: AttributeType) -> Text {
+        self.explain(at).into_text()
+    }
+
+    pub fn aggregate_to_components(&self) -> HashMap<AttributeType, Text> {
+        self.get_attribute_types()
+            .into_iter()
+            .map(|at| (at, self.aggregate_to_component(at)))
+            .collect()
+    }
+}
+
+impl<'a> IntoText<'a> for AttributeParser {
+    fn into_cow_text(self) -> std::borrow::Cow<'a, Text> {
+        let mut txt = "".into_text();
+
+        let mut components: Vec<_> = self.aggregate_to_components().into_iter().collect();
+
+        // Registered attributes in display order, then any unregistered ones by id
+        let registry = ATTRIBUTE_REGISTRY.read().unwrap();
+        components.sort_by_key(|(at, _)| (registry.position(*at).unwrap_or(usize::MAX), *at));
+        drop(registry);
+
+        for (at, c) in components {
+            txt = txt + at + ":\n" + c + "\n";
+        }
+
+        txt.into_cow_text()
+    }
+}
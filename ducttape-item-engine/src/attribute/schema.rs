@@ -0,0 +1,390 @@
+use std::{collections::BTreeSet, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::template::source::AssetSource;
+
+use super::{
+    expression::{Expression, ExpressionError},
+    AttributeType,
+};
+
+fn default_precision() -> u8 {
+    2
+}
+
+/// How an attribute is displayed and which values it can take
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttributeSchema {
+    pub id: AttributeType,
+    /// Shown in place of the id in stat breakdowns, usually an emoji
+    #[serde(default)]
+    pub icon: String,
+    #[serde(default)]
+    pub description: String,
+    /// The value of the attribute before any modifier is applied
+    #[serde(default)]
+    pub default: f64,
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+    /// How many decimals are shown
+    #[serde(default = "default_precision")]
+    pub precision: u8,
+    /// Derives the attribute from other attributes, the formula replaces the default value
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formula: Option<Expression>,
+    /// Equipped items add their value of the attribute to the character wearing them, see
+    /// `EquipmentCollection::get_effective_stats`. Attributes that describe the item itself,
+    /// like its durability, stay with the item
+    #[serde(default)]
+    pub wearer: bool,
+}
+
+impl AttributeSchema {
+    pub fn new(id: AttributeType, icon: &str) -> Self {
+        Self {
+            id,
+            icon: icon.to_owned(),
+            description: String::new(),
+            default: 0.0,
+            min: None,
+            max: None,
+            precision: default_precision(),
+            formula: None,
+            wearer: false,
+        }
+    }
+
+    pub fn with_description(mut self, description: &str) -> Self {
+        self.description = description.to_owned();
+        self
+    }
+
+    pub fn with_default(mut self, default: f64) -> Self {
+        self.default = default;
+        self
+    }
+
+    pub fn with_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn with_precision(mut self, precision: u8) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    pub fn with_formula(mut self, formula: Expression) -> Self {
+        self.formula = Some(formula);
+        self
+    }
+
+    /// Make equipped items add the attribute to their wearer
+    pub fn for_wearer(mut self) -> Self {
+        self.wearer = true;
+        self
+    }
+
+    pub fn is_derived(&self) -> bool {
+        self.formula.is_some()
+    }
+
+    /// Get the icon, or the id if the schema has no icon
+    pub fn get_icon(&self) -> &str {
+        if self.icon.is_empty() {
+            self.id.get_id()
+        } else {
+            &self.icon
+        }
+    }
+
+    /// Keep a value within the attribute's min and max
+    pub fn clamp(&self, value: f64) -> f64 {
+        let value = self.min.map_or(value, |min| value.max(min));
+        self.max.map_or(value, |max| value.min(max))
+    }
+
+    /// Round a value to the attribute's display precision
+    pub fn round(&self, value: f64) -> f64 {
+        let factor = 10f64.powi(self.precision as i32);
+        (value * factor).round() / factor
+    }
+}
+
+/// The contents of an attribute schema file, one `[[attribute]]` table per attribute
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AttributeSchemaFile {
+    #[serde(default)]
+    pub attribute: Vec<AttributeSchema>,
+}
+
+/// Every known attribute, in the order they are displayed
+#[derive(Debug, Clone, Default)]
+pub struct AttributeRegistry {
+    schemas: Vec<AttributeSchema>,
+}
+
+impl AttributeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding the attributes every item engine game has
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+
+        registry.register(
+            AttributeSchema::new(AttributeType::Sharpness, "🗡️")
+                .with_description("How much damage the item can deal"),
+        );
+        registry.register(
+            AttributeSchema::new(AttributeType::Durability, "⚡")
+                .with_description("How much damage the item can take"),
+        );
+        registry.register(
+            AttributeSchema::new(AttributeType::Weight, "🏋️")
+                .with_description("How much the item weighs")
+                .for_wearer(),
+        );
+        registry.register(
+            AttributeSchema::new(AttributeType::Strength, "💪")
+                .with_description("How much weight can be supported"),
+        );
+        registry.register(
+            AttributeSchema::new(AttributeType::Agility, "🏃")
+                .with_description("How fast you can attack"),
+        );
+        registry.register(
+            AttributeSchema::new(AttributeType::Reach, "🏹")
+                .with_description("How far you can reach")
+                .for_wearer(),
+        );
+
+        registry
+    }
+
+    /// Add an attribute, registering an id again replaces its schema but keeps its display position
+    pub fn register(&mut self, schema: AttributeSchema) {
+        match self.position(schema.id) {
+            Some(index) => self.schemas[index] = schema,
+            None => self.schemas.push(schema),
+        }
+    }
+
+    pub fn get(&self, at: AttributeType) -> Option<&AttributeSchema> {
+        self.schemas.iter().find(|schema| schema.id == at)
+    }
+
+    /// Get the schema of an attribute, unregistered attributes get a default schema named after their id
+    pub fn get_schema(&self, at: AttributeType) -> AttributeSchema {
+        self.get(at)
+            .cloned()
+            .unwrap_or_else(|| AttributeSchema::new(at, ""))
+    }
+
+    pub fn contains(&self, at: AttributeType) -> bool {
+        self.position(at).is_some()
+    }
+
+    /// Get where an attribute is displayed relative to the others
+    pub fn position(&self, at: AttributeType) -> Option<usize> {
+        self.schemas.iter().position(|schema| schema.id == at)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, AttributeSchema> {
+        self.schemas.iter()
+    }
+
+    pub fn get_types(&self) -> Vec<AttributeType> {
+        self.schemas.iter().map(|schema| schema.id).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.schemas.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.schemas.is_empty()
+    }
+
+    /// Get every attribute a derived attribute reads, including the ones read by derived attributes it reads
+    pub fn get_dependencies(&self, at: AttributeType) -> BTreeSet<AttributeType> {
+        let mut dependencies = BTreeSet::new();
+        let mut pending = vec![at];
+
+        while let Some(at) = pending.pop() {
+            let Some(formula) = self.get(at).and_then(|schema| schema.formula.as_ref()) else {
+                continue;
+            };
+
+            for dependency in formula.get_dependencies() {
+                if dependencies.insert(dependency) {
+                    pending.push(dependency);
+                }
+            }
+        }
+
+        dependencies
+    }
+
+    /// Find derived attributes that depend on themselves, returns the attributes in the cycle
+    pub fn find_cycle(&self) -> Option<Vec<AttributeType>> {
+        fn visit(
+            registry: &AttributeRegistry,
+            at: AttributeType,
+            path: &mut Vec<AttributeType>,
+            done: &mut BTreeSet<AttributeType>,
+        ) -> Option<Vec<AttributeType>> {
+            if let Some(start) = path.iter().position(|visited| *visited == at) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(at);
+                return Some(cycle);
+            }
+
+            if done.contains(&at) {
+                return None;
+            }
+
+            let formula = registry.get(at).and_then(|schema| schema.formula.as_ref())?;
+
+            path.push(at);
+            for dependency in formula.get_dependencies() {
+                if let Some(cycle) = visit(registry, dependency, path, done) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            done.insert(at);
+
+            None
+        }
+
+        let mut done = BTreeSet::new();
+
+        self.schemas
+            .iter()
+            .filter(|schema| schema.is_derived())
+            .find_map(|schema| visit(self, schema.id, &mut Vec::new(), &mut done))
+    }
+
+    /// Register every attribute of a schema file, returns the registered ids
+    pub fn load_toml(&mut self, toml: &str) -> Result<Vec<AttributeType>, toml::de::Error> {
+        let file: AttributeSchemaFile = toml::from_str(toml)?;
+
+        Ok(file
+            .attribute
+            .into_iter()
+            .map(|schema| {
+                let id = schema.id;
+                self.register(schema);
+                id
+            })
+            .collect())
+    }
+
+    /// Load a schema file, fails if the file makes derived attributes depend on themselves.
+    /// The attributes are registered either way
+    pub fn load(
+        &mut self,
+        source: &dyn AssetSource,
+        path: &str,
+    ) -> Result<Vec<AttributeType>, Box<dyn std::error::Error>> {
+        let loaded = self.load_toml(&source.read_to_string(path)?)?;
+
+        if let Some(cycle) = self.find_cycle() {
+            return Err(Box::new(ExpressionError::Cycle(cycle)));
+        }
+
+        Ok(loaded)
+    }
+}
+
+lazy_static::lazy_static! {
+    /// The attributes known to the game, starts out with the built-ins
+    pub static ref ATTRIBUTE_REGISTRY: RwLock<AttributeRegistry> =
+        RwLock::new(AttributeRegistry::with_builtins());
+}
+
+/// Add an attribute to the global registry
+pub fn register_attribute(schema: AttributeSchema) {
+    ATTRIBUTE_REGISTRY.write().unwrap().register(schema);
+}
+
+/// Get the schema of an attribute from the global registry, see `AttributeRegistry::get_schema`
+pub fn get_attribute_schema(at: AttributeType) -> AttributeSchema {
+    ATTRIBUTE_REGISTRY.read().unwrap().get_schema(at)
+}
+
+/// Every registered attribute, in display order
+pub fn all_attribute_types() -> Vec<AttributeType> {
+    ATTRIBUTE_REGISTRY.read().unwrap().get_types()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CAVE_ATTRIBUTES: &str = r#"
+        [[attribute]]
+        id = "Flammability"
+        icon = "🔥"
+        description = "How easily the item catches fire"
+        min = 0.0
+        max = 1.0
+
+        [[attribute]]
+        id = "Buoyancy"
+        default = 1.0
+        precision = 0
+
+        [[attribute]]
+        id = "Weight"
+        icon = "⚖️"
+        min = 0.0
+    "#;
+
+    #[test]
+    fn load_schema() {
+        let mut registry = AttributeRegistry::with_builtins();
+        let loaded = registry.load_toml(CAVE_ATTRIBUTES).unwrap();
+
+        assert_eq!(
+            loaded,
+            [
+                AttributeType::new("Flammability"),
+                AttributeType::new("Buoyancy"),
+                AttributeType::Weight
+            ]
+        );
+        assert_eq!(registry.len(), 8);
+
+        // Overriding a built-in keeps its place
+        assert_eq!(registry.position(AttributeType::Weight), Some(2));
+        assert_eq!(registry.get(AttributeType::Weight).unwrap().get_icon(), "⚖️");
+
+        let flammability = registry.get(AttributeType::new("Flammability")).unwrap();
+        assert_eq!(flammability.clamp(2.5), 1.0);
+        assert_eq!(flammability.clamp(-1.0), 0.0);
+        assert_eq!(flammability.round(0.123), 0.12);
+
+        let buoyancy = registry.get(AttributeType::new("Buoyancy")).unwrap();
+        assert_eq!(buoyancy.get_icon(), "Buoyancy");
+        assert_eq!(buoyancy.default, 1.0);
+        assert_eq!(buoyancy.round(2.6), 3.0);
+    }
+
+    #[test]
+    fn ids_serialize_as_strings() {
+        let at = AttributeType::new("Buoyancy");
+
+        assert_eq!(serde_json::to_string(&at).unwrap(), "\"Buoyancy\"");
+        assert_eq!(
+            serde_json::from_str::<AttributeType>("\"Sharpness\"").unwrap(),
+            AttributeType::Sharpness
+        );
+    }
+}
@@ -0,0 +1,581 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::Arc,
+};
+
+use serde::Deserialize;
+
+use crate::{
+    item::{
+        AddResult, EngineHook, Item, ItemCollection, ItemCollectionError, ItemRegistry, ItemStack,
+    },
+    template::{loader::ItemTemplate, source::AssetSource},
+};
+
+fn default_count() -> u32 {
+    1
+}
+
+/// Which items may fill a single component of a template
+#[derive(Debug, Clone, Deserialize)]
+pub struct ComponentRequirement {
+    /// Item idents that are accepted for this component
+    #[serde(default)]
+    pub items: Vec<String>,
+    /// Any item with one of these tags is accepted for this component
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// How many items are consumed from the stack when crafting
+    #[serde(default = "default_count")]
+    pub count: u32,
+}
+
+impl ComponentRequirement {
+    pub fn accepts<THook: EngineHook>(&self, item: &dyn Item<THook>) -> bool {
+        self.items.contains(&item.get_ident())
+            || item.get_tags().iter().any(|tag| self.tags.contains(tag))
+    }
+}
+
+/// The contents of a `recipe.toml` file
+#[derive(Debug, Clone, Deserialize)]
+pub struct RecipeData {
+    pub component: BTreeMap<String, ComponentRequirement>,
+}
+
+#[derive(Debug)]
+pub enum CraftingError {
+    /// A component of the recipe has no input assigned.
+    MissingComponent(String),
+    /// An input was assigned to a component the recipe doesn't have.
+    UnknownComponent(String),
+    /// The assigned item is not accepted for the component.
+    InvalidComponent { component: String, item: String },
+    /// The input slot doesn't hold enough items for every component assigned to it.
+    NotEnoughItems(usize),
+    /// No slot of the input collection holds the item.
+    ItemNotFound(String),
+    /// The crafted item doesn't fit into the collection, even with the inputs used up.
+    NoRoom,
+    /// The input collection could not provide or take the items.
+    Collection(ItemCollectionError),
+}
+
+impl std::fmt::Display for CraftingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CraftingError::MissingComponent(component) => {
+                write!(f, "Missing item for component: {}", component)
+            }
+            CraftingError::UnknownComponent(component) => {
+                write!(f, "Recipe has no component: {}", component)
+            }
+            CraftingError::InvalidComponent { component, item } => {
+                write!(f, "Item {} can't be used as component {}", item, component)
+            }
+            CraftingError::NotEnoughItems(index) => {
+                write!(f, "Not enough items in slot {}", index)
+            }
+            CraftingError::ItemNotFound(item) => write!(f, "No {} to craft with", item),
+            CraftingError::NoRoom => write!(f, "No room for the crafted item"),
+            CraftingError::Collection(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CraftingError {}
+
+impl From<ItemCollectionError> for CraftingError {
+    fn from(e: ItemCollectionError) -> Self {
+        CraftingError::Collection(e)
+    }
+}
+
+pub type CraftingResult<T> = Result<T, CraftingError>;
+
+/// A recipe for a template item, loaded from the `recipe.toml` next to the template's `template.toml`
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    template: ItemTemplate,
+    components: BTreeMap<String, ComponentRequirement>,
+}
+
+impl Recipe {
+    pub fn new(template: ItemTemplate, components: BTreeMap<String, ComponentRequirement>) -> Self {
+        Self {
+            template,
+            components,
+        }
+    }
+
+    pub fn load_recipe(
+        source: Arc<dyn AssetSource>,
+        name: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let data: RecipeData =
+            toml::from_str(&source.read_to_string(&format!("{}/recipe.toml", name))?)?;
+        let template = ItemTemplate::load_template(source, name)?;
+
+        Ok(Self::new(template, data.component))
+    }
+
+    pub fn get_name(&self) -> &str {
+        self.template.get_data_name()
+    }
+
+    pub fn get_template(&self) -> &ItemTemplate {
+        &self.template
+    }
+
+    pub fn get_components(&self) -> &BTreeMap<String, ComponentRequirement> {
+        &self.components
+    }
+
+    pub fn get_component(&self, component: &str) -> Option<&ComponentRequirement> {
+        self.components.get(component)
+    }
+
+    /// The registry ident of the item crafted from the given component item idents,
+    /// e.g. `spear[shaft=stick,tip=rock]`
+    pub fn output_ident(&self, components: &BTreeMap<String, String>) -> String {
+        self.template.derived_ident(components)
+    }
+
+    /// Find the collection slots to craft from, given the ident of the item for each component.
+    /// The first slot holding an item is used for it
+    pub fn find_inputs<'a, THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+        &self,
+        collection: &C,
+        components: impl IntoIterator<Item = (&'a String, &'a String)>,
+    ) -> CraftingResult<BTreeMap<String, usize>> {
+        components
+            .into_iter()
+            .map(|(component, ident)| {
+                let index = collection
+                    .iter()
+                    .position(|stack| stack.get_ident() == *ident)
+                    .ok_or_else(|| CraftingError::ItemNotFound(ident.clone()))?;
+
+                Ok((component.clone(), index))
+            })
+            .collect()
+    }
+
+    /// Check that the items at the given collection slots can fill every component of the recipe
+    pub fn validate<THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+        &self,
+        collection: &C,
+        inputs: &BTreeMap<String, usize>,
+    ) -> CraftingResult<()> {
+        if let Some(component) = inputs.keys().find(|c| !self.components.contains_key(*c)) {
+            return Err(CraftingError::UnknownComponent(component.clone()));
+        }
+
+        let mut needed: HashMap<usize, u32> = HashMap::new();
+
+        for (component, requirement) in self.components.iter() {
+            let index = *inputs
+                .get(component)
+                .ok_or_else(|| CraftingError::MissingComponent(component.clone()))?;
+            let stack = collection.get_item(index)?;
+
+            if !requirement.accepts(stack) {
+                return Err(CraftingError::InvalidComponent {
+                    component: component.clone(),
+                    item: stack.get_ident(),
+                });
+            }
+
+            *needed.entry(index).or_default() += requirement.count;
+        }
+
+        for (index, count) in needed {
+            if collection.get_item(index)?.get_count() < count {
+                return Err(CraftingError::NotEnoughItems(index));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Build the output item for the given inputs without consuming them or registering
+    /// anything, an output that was crafted before is taken from the registry.
+    ///
+    /// Instance data of the inputs, like their damage, stays with them as components of the
+    /// output, so such an output is its own item instead of the registered one
+    pub fn preview<THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+        &self,
+        collection: &C,
+        registry: &ItemRegistry<THook>,
+        inputs: &BTreeMap<String, usize>,
+    ) -> CraftingResult<Arc<dyn Item<THook>>> {
+        self.build_output(collection, registry, inputs)
+            .map(|output| output.item)
+    }
+
+    /// Build the output and the item made from the input prototypes, which is registered
+    /// under its `output_ident` once the output is actually crafted
+    fn build_output<THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+        &self,
+        collection: &C,
+        registry: &ItemRegistry<THook>,
+        inputs: &BTreeMap<String, usize>,
+    ) -> CraftingResult<CraftedOutput<THook>> {
+        self.validate(collection, inputs)?;
+
+        let mut prototypes = HashMap::new();
+        let mut components: HashMap<String, Arc<dyn Item<THook>>> = HashMap::new();
+        let mut has_instance_data = false;
+
+        for (component, index) in inputs {
+            let stack = collection.get_item(*index)?;
+            let prototype = stack.get_prototype();
+
+            if stack.get_item().get_data().is_empty() {
+                components.insert(component.clone(), prototype.clone());
+            } else {
+                components.insert(component.clone(), Arc::new(stack.get_item().clone()));
+                has_instance_data = true;
+            }
+            prototypes.insert(component.clone(), prototype);
+        }
+
+        let output = self
+            .template
+            .populate_template(prototypes)
+            .with_derived_ident();
+
+        let registered = match registry.get(&output.get_ident()) {
+            Some(item) => item.clone(),
+            None => Arc::new(output),
+        };
+
+        if !has_instance_data {
+            return Ok(CraftedOutput {
+                item: registered.clone(),
+                registered,
+            });
+        }
+
+        let output = self
+            .template
+            .populate_template(components)
+            .with_derived_ident();
+
+        Ok(CraftedOutput {
+            item: Arc::new(output),
+            registered,
+        })
+    }
+
+    /// Consume the inputs from the collection and return the crafted item, the output is
+    /// registered the first time it is crafted
+    pub fn craft<THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+        &self,
+        collection: &mut C,
+        registry: &mut ItemRegistry<THook>,
+        inputs: &BTreeMap<String, usize>,
+    ) -> CraftingResult<ItemStack<THook>> {
+        let output = self.build_output(collection, registry, inputs)?;
+        self.take_inputs(collection, inputs)?;
+        register_output(registry, output.registered);
+
+        Ok(ItemStack::new(output.item, 1))
+    }
+
+    fn take_inputs<THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+        &self,
+        collection: &mut C,
+        inputs: &BTreeMap<String, usize>,
+    ) -> CraftingResult<()> {
+        for (component, index) in inputs {
+            let count = self.components[component].count;
+            collection.take_item(*index, count)?;
+        }
+
+        Ok(())
+    }
+
+    /// Craft and put the output back into the same collection, returns the crafted stack.
+    /// If the output doesn't fit, even with the inputs used up, the inputs are put back and
+    /// nothing is crafted or registered
+    pub fn craft_into<THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+        &self,
+        collection: &mut C,
+        registry: &mut ItemRegistry<THook>,
+        inputs: &BTreeMap<String, usize>,
+    ) -> CraftingResult<ItemStack<THook>> {
+        let CraftedOutput { item, registered } = self.build_output(collection, registry, inputs)?;
+
+        let slots: BTreeSet<usize> = inputs.values().copied().collect();
+        let saved = slots
+            .into_iter()
+            .map(|index| Ok((index, collection.get_item(index)?.clone())))
+            .collect::<CraftingResult<Vec<_>>>()?;
+
+        self.take_inputs(collection, inputs)?;
+        let output = ItemStack::new(item, 1);
+
+        match collection.add_item_partial(output.clone()) {
+            AddResult::Complete => {
+                register_output(registry, registered);
+                Ok(output)
+            }
+            AddResult::Partial(_) => {
+                // A single item either fits or doesn't, so nothing was added
+                let items = collection.get_items_mut();
+                for (index, stack) in saved {
+                    items[index] = stack;
+                }
+                collection.refresh();
+
+                Err(CraftingError::NoRoom)
+            }
+        }
+    }
+}
+
+/// What a recipe makes from a set of inputs, see `Recipe::build_output`
+struct CraftedOutput<THook: EngineHook> {
+    /// The item put into the collection
+    item: Arc<dyn Item<THook>>,
+    /// The item made from the input prototypes, registered once the output is crafted
+    registered: Arc<dyn Item<THook>>,
+}
+
+/// Register a crafted output under its ident, unless it was crafted before
+fn register_output<THook: EngineHook>(
+    registry: &mut ItemRegistry<THook>,
+    output: Arc<dyn Item<THook>>,
+) {
+    let ident = output.get_ident();
+    if !registry.contains(&ident) {
+        registry.register(ident, output);
+    }
+}
+
+/// Every recipe found in an asset source
+#[derive(Debug, Clone, Default)]
+pub struct RecipeBook {
+    recipes: BTreeMap<String, Recipe>,
+}
+
+impl RecipeBook {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load every folder in the root of the source that contains a `recipe.toml`
+    pub fn load_all(source: Arc<dyn AssetSource>) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut book = Self::new();
+
+        for name in source.list("")? {
+            if source.exists(&format!("{}/recipe.toml", name)) {
+                book.register(Recipe::load_recipe(source.clone(), &name)?);
+            }
+        }
+
+        Ok(book)
+    }
+
+    pub fn register(&mut self, recipe: Recipe) {
+        self.recipes.insert(recipe.get_name().to_owned(), recipe);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Recipe> {
+        self.recipes.get(name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &Recipe)> {
+        self.recipes.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.recipes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.recipes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        item::{DummyHook, ItemCollectionSized},
+        prelude_items::{dev_tablet, rock},
+        template::source::FsAssetSource,
+    };
+
+    use super::*;
+
+    fn recipes() -> RecipeBook {
+        let source = FsAssetSource::new(format!(
+            "{}/../ducttape-godot/assets/item/generated",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+
+        RecipeBook::load_all(Arc::new(source)).unwrap()
+    }
+
+    #[test]
+    fn craft_spear() {
+        let book = recipes();
+        let spear = book.get("spear").unwrap();
+
+        let mut registry: ItemRegistry<DummyHook> = ItemRegistry::new();
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(4);
+        inventory
+            .add_item(ItemStack::new(Arc::new(rock()), 3))
+            .unwrap();
+
+        let inputs = BTreeMap::from([("tip".to_owned(), 0), ("shaft".to_owned(), 0)]);
+
+        let crafted = spear
+            .craft_into(&mut inventory, &mut registry, &inputs)
+            .unwrap();
+
+        assert_eq!(crafted.get_ident(), "spear[shaft=rock,tip=rock]");
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 1);
+        // The crafted item is registered under the ident it reports
+        assert_eq!(
+            inventory.get_item(1).unwrap().get_ident(),
+            "spear[shaft=rock,tip=rock]"
+        );
+        assert!(registry.contains("spear[shaft=rock,tip=rock]"));
+    }
+
+    #[test]
+    fn craft_keeps_instance_data() {
+        let book = recipes();
+        let spear = book.get("spear").unwrap();
+
+        let mut registry: ItemRegistry<DummyHook> = ItemRegistry::new();
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(4);
+        inventory
+            .add_item(ItemStack::new(Arc::new(rock()), 2))
+            .unwrap();
+        let damaged = inventory.split_stack(0, 1).unwrap();
+        inventory
+            .get_item_mut(damaged)
+            .unwrap()
+            .get_item_mut()
+            .damage(20.0);
+
+        let inputs = BTreeMap::from([("tip".to_owned(), damaged), ("shaft".to_owned(), 0)]);
+        let preview = spear.preview(&inventory, &registry, &inputs).unwrap();
+        // Previewing doesn't register anything
+        assert!(registry.is_empty());
+
+        let crafted = spear
+            .craft_into(&mut inventory, &mut registry, &inputs)
+            .unwrap();
+        assert_eq!(crafted.get_ident(), preview.get_ident());
+
+        let tip = &crafted.get_components()["tip"];
+        assert_eq!(tip.as_instance().unwrap().get_damage(), 20.0);
+        assert!(crafted.get_components()["shaft"].as_instance().is_none());
+
+        // The registered item is made of the undamaged prototypes
+        let registered = registry.get("spear[shaft=rock,tip=rock]").unwrap();
+        assert!(registered.get_components()["tip"].as_instance().is_none());
+    }
+
+    #[test]
+    fn reject_invalid_inputs() {
+        let book = recipes();
+        let spear = book.get("spear").unwrap();
+
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(4);
+        inventory
+            .add_item(ItemStack::new(Arc::new(rock()), 1))
+            .unwrap();
+        inventory
+            .add_item(ItemStack::new(Arc::new(dev_tablet()), 1))
+            .unwrap();
+
+        let not_enough = BTreeMap::from([("tip".to_owned(), 0), ("shaft".to_owned(), 0)]);
+        assert!(matches!(
+            spear.validate(&inventory, &not_enough),
+            Err(CraftingError::NotEnoughItems(0))
+        ));
+
+        let tablet = BTreeMap::from([("tip".to_owned(), 0), ("shaft".to_owned(), 1)]);
+        assert!(matches!(
+            spear.validate(&inventory, &tablet),
+            Err(CraftingError::InvalidComponent { .. })
+        ));
+
+        let missing = BTreeMap::from([("tip".to_owned(), 0)]);
+        assert!(matches!(
+            spear.validate(&inventory, &missing),
+            Err(CraftingError::MissingComponent(_))
+        ));
+    }
+
+    #[test]
+    fn find_inputs_by_ident() {
+        let book = recipes();
+        let spear = book.get("spear").unwrap();
+
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(4);
+        inventory
+            .add_item(ItemStack::new(Arc::new(dev_tablet()), 1))
+            .unwrap();
+        inventory
+            .add_item(ItemStack::new(Arc::new(rock()), 2))
+            .unwrap();
+
+        let components = HashMap::from([
+            ("tip".to_owned(), "rock".to_owned()),
+            ("shaft".to_owned(), "rock".to_owned()),
+        ]);
+        assert_eq!(
+            spear.find_inputs(&inventory, &components).unwrap(),
+            BTreeMap::from([("tip".to_owned(), 1), ("shaft".to_owned(), 1)])
+        );
+
+        let components = HashMap::from([("tip".to_owned(), "stick".to_owned())]);
+        assert!(matches!(
+            spear.find_inputs(&inventory, &components),
+            Err(CraftingError::ItemNotFound(ident)) if ident == "stick"
+        ));
+    }
+
+    #[test]
+    fn craft_into_full_collection() {
+        let book = recipes();
+        let spear = book.get("spear").unwrap();
+        let mut registry: ItemRegistry<DummyHook> = ItemRegistry::new();
+        let inputs = BTreeMap::from([("tip".to_owned(), 0), ("shaft".to_owned(), 0)]);
+
+        // A rock is left over, so the spear has nowhere to go and the rocks stay
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(1);
+        inventory
+            .add_item(ItemStack::new(Arc::new(rock()), 3))
+            .unwrap();
+
+        assert!(matches!(
+            spear.craft_into(&mut inventory, &mut registry, &inputs),
+            Err(CraftingError::NoRoom)
+        ));
+        assert_eq!(inventory.get_item(0).unwrap().get_ident(), "rock");
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 3);
+        // Nothing was crafted, so nothing is registered
+        assert!(!registry.contains("spear[shaft=rock,tip=rock]"));
+
+        // Using up every rock frees the slot for the spear
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(1);
+        inventory
+            .add_item(ItemStack::new(Arc::new(rock()), 2))
+            .unwrap();
+
+        spear
+            .craft_into(&mut inventory, &mut registry, &inputs)
+            .unwrap();
+        assert_eq!(
+            inventory.get_item(0).unwrap().get_ident(),
+            "spear[shaft=rock,tip=rock]"
+        );
+    }
+}
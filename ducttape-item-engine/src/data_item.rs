@@ -0,0 +1,434 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribute::{
+        condition::{AttributeCondition, AttributeLifetime},
+        Attribute, AttributeModifier, AttributeReason, AttributeType,
+    },
+    item::{
+        AnimationType, DummyHook, EngineHook, FrameProperties, Item, ItemMut, ItemRegistry,
+        ItemTexture, SpecialAbility, Stats, DEFAULT_MAX_STACK_SIZE,
+    },
+    prelude_items::stats::BasicStats,
+    template::source::AssetSource,
+};
+
+fn default_max_stack_size() -> u32 {
+    DEFAULT_MAX_STACK_SIZE
+}
+
+fn default_modifier_priority() -> u8 {
+    1
+}
+
+/// The texture of a data item, paths are relative to the asset source the item is loaded from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DataTexture {
+    /// `texture = "item/rock/rock.png"`
+    Image(String),
+    /// `texture = { atlas = "item/tablet/tablet.png", width = 32, height = 32, duration = 0.5 }`
+    Animated {
+        atlas: String,
+        #[serde(flatten)]
+        frames: FrameProperties,
+    },
+}
+
+/// A modifier applied on top of the base attributes of a data item
+///
+/// ```toml
+/// [[modifier]]
+/// attribute = "Strength"
+/// modifier = { Multiply = 0.5 }
+/// condition = "Wet"
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataModifier {
+    pub attribute: AttributeType,
+    pub modifier: AttributeModifier,
+    #[serde(default = "default_modifier_priority")]
+    pub priority: u8,
+    #[serde(default)]
+    pub condition: Option<AttributeCondition>,
+    #[serde(default)]
+    pub lifetime: Option<AttributeLifetime>,
+}
+
+/// The contents of an `items/*.toml` file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DataItemData {
+    pub ident: String,
+    pub name: String,
+    #[serde(default)]
+    pub texture: Option<DataTexture>,
+    /// Base value of each attribute, added as a `Set` attribute at priority 0
+    #[serde(default)]
+    pub attributes: BTreeMap<AttributeType, f64>,
+    #[serde(default, rename = "modifier")]
+    pub modifiers: Vec<DataModifier>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_max_stack_size")]
+    pub max_stack_size: u32,
+}
+
+#[derive(Debug)]
+pub enum DataItemError {
+    /// The item file or its texture could not be read.
+    Io { path: String, error: std::io::Error },
+    /// The item file is not a valid item definition.
+    Parse { path: String, error: toml::de::Error },
+    /// The texture is not a valid image.
+    Image { path: String, error: image::ImageError },
+}
+
+impl std::fmt::Display for DataItemError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DataItemError::Io { path, error } => write!(f, "Failed to read {}: {}", path, error),
+            DataItemError::Parse { path, error } => {
+                write!(f, "Invalid item definition {}: {}", path, error)
+            }
+            DataItemError::Image { path, error } => {
+                write!(f, "Invalid item texture {}: {}", path, error)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DataItemError {}
+
+pub type DataItemResult<T> = Result<T, DataItemError>;
+
+/// The items registered by `ItemRegistry::load_data_items`, and the files that failed to load
+#[derive(Debug, Default)]
+pub struct LoadedDataItems {
+    pub idents: Vec<String>,
+    pub errors: Vec<DataItemError>,
+}
+
+fn read_image(source: &dyn AssetSource, path: &str) -> DataItemResult<image::DynamicImage> {
+    let data = source.read(path).map_err(|error| DataItemError::Io {
+        path: path.to_owned(),
+        error,
+    })?;
+
+    image::load_from_memory(&data).map_err(|error| DataItemError::Image {
+        path: path.to_owned(),
+        error,
+    })
+}
+
+/// An item defined entirely by data, for items that don't need any custom behaviour
+#[derive(Debug, Clone)]
+pub struct DataItem<THook: EngineHook = DummyHook> {
+    data: DataItemData,
+    texture: ItemTexture,
+    stats: BasicStats,
+    phantom: std::marker::PhantomData<THook>,
+}
+
+impl<THook: EngineHook> DataItem<THook> {
+    /// Build an item from its definition, loading the texture from the source
+    pub fn from_data(source: &dyn AssetSource, data: DataItemData) -> DataItemResult<Self> {
+        let texture = match &data.texture {
+            None => ItemTexture::None,
+            Some(DataTexture::Image(path)) => read_image(source, path)?.into(),
+            Some(DataTexture::Animated { atlas, frames }) => {
+                (read_image(source, atlas)?, frames.clone(), AnimationType::Loop).into()
+            }
+        };
+
+        let mut stats = BasicStats::new();
+
+        // The uuids are derived from the ident, so they stay the same every time the item loads
+        for (at, value) in data.attributes.iter() {
+            stats.push_attribute(
+                *at,
+                Attribute {
+                    uuid: Attribute::stable_uuid(&[&data.ident, at.get_id()]),
+                    reason: AttributeReason::Display(data.name.clone()),
+                    priority: 0,
+                    modifier: AttributeModifier::Set(*value),
+                    condition: None,
+                    lifetime: None,
+                },
+            );
+        }
+
+        for (index, modifier) in data.modifiers.iter().enumerate() {
+            stats.push_attribute(
+                modifier.attribute,
+                Attribute {
+                    uuid: Attribute::stable_uuid(&[
+                        &data.ident,
+                        "modifier",
+                        &index.to_string(),
+                    ]),
+                    reason: AttributeReason::Display(data.name.clone()),
+                    priority: modifier.priority,
+                    modifier: modifier.modifier.clone(),
+                    condition: modifier.condition.clone(),
+                    lifetime: modifier.lifetime.clone(),
+                },
+            );
+        }
+
+        Ok(Self {
+            data,
+            texture,
+            stats,
+            phantom: std::marker::PhantomData,
+        })
+    }
+
+    /// Parse an item definition, `path` is only used for error messages
+    pub fn from_toml(source: &dyn AssetSource, path: &str, toml: &str) -> DataItemResult<Self> {
+        let data = toml::from_str(toml).map_err(|error| DataItemError::Parse {
+            path: path.to_owned(),
+            error,
+        })?;
+
+        Self::from_data(source, data)
+    }
+
+    /// Load an item definition from the source
+    pub fn load(source: &dyn AssetSource, path: &str) -> DataItemResult<Self> {
+        let toml = source
+            .read_to_string(path)
+            .map_err(|error| DataItemError::Io {
+                path: path.to_owned(),
+                error,
+            })?;
+
+        Self::from_toml(source, path, &toml)
+    }
+
+    pub fn get_data(&self) -> &DataItemData {
+        &self.data
+    }
+}
+
+impl<THook: EngineHook> Item<THook> for DataItem<THook> {
+    fn get_name(&self) -> String {
+        self.data.name.clone()
+    }
+
+    fn get_ident(&self) -> String {
+        self.data.ident.clone()
+    }
+
+    fn get_stats(&self) -> Box<dyn Stats> {
+        Box::new(self.stats.clone())
+    }
+
+    fn special_abilities(&self) -> Vec<Box<dyn SpecialAbility<THook>>> {
+        Vec::new()
+    }
+
+    fn get_texture(&self) -> ItemTexture {
+        self.texture.clone()
+    }
+
+    fn get_max_stack_size(&self) -> u32 {
+        self.data.max_stack_size
+    }
+
+    fn get_tags(&self) -> Vec<String> {
+        self.data.tags.clone()
+    }
+}
+
+impl<THook: EngineHook> ItemMut for DataItem<THook> {
+    fn get_stats_mut(&mut self) -> &mut dyn Stats {
+        &mut self.stats
+    }
+}
+
+impl<THook: EngineHook> ItemRegistry<THook> {
+    /// Load every `*.toml` file in a folder of the source and register each item under its ident.
+    /// A file that fails to load is skipped, only the folder itself failing is an error
+    pub fn load_data_items(
+        &mut self,
+        source: &dyn AssetSource,
+        folder: &str,
+    ) -> DataItemResult<LoadedDataItems> {
+        let names = source.list(folder).map_err(|error| DataItemError::Io {
+            path: folder.to_owned(),
+            error,
+        })?;
+
+        let mut loaded = LoadedDataItems::default();
+
+        for name in names.iter().filter(|name| name.ends_with(".toml")) {
+            let path = if folder.is_empty() {
+                name.clone()
+            } else {
+                format!("{}/{}", folder, name)
+            };
+
+            let item: DataItem<THook> = match DataItem::load(source, &path) {
+                Ok(item) => item,
+                Err(e) => {
+                    loaded.errors.push(e);
+                    continue;
+                }
+            };
+            let ident = item.get_ident();
+
+            self.register(ident.clone(), std::sync::Arc::new(item));
+            loaded.idents.push(ident);
+        }
+
+        Ok(loaded)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, ImageFormat};
+
+    use crate::{
+        attribute::{condition::AttributeContext, AttributeParser},
+        template::source::MemoryAssetSource,
+    };
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut buf = Cursor::new(Vec::new());
+        DynamicImage::new_rgba8(width, height)
+            .write_to(&mut buf, ImageFormat::Png)
+            .unwrap();
+        buf.into_inner()
+    }
+
+    fn source() -> MemoryAssetSource {
+        MemoryAssetSource::new()
+            .with_file(
+                "items/pebble.toml",
+                r#"
+                ident = "pebble"
+                name = "Pebble"
+                texture = "item/pebble.png"
+                tags = ["stone"]
+                max_stack_size = 16
+
+                [attributes]
+                Sharpness = 1.0
+                Weight = 2.5
+                "#,
+            )
+            .with_file(
+                "items/screen.toml",
+                r#"
+                ident = "screen"
+                name = "Screen"
+                texture = { atlas = "item/screen.png", width = 16, height = 16, duration = 0.25 }
+                "#,
+            )
+            .with_file(
+                "items/vine.toml",
+                r#"
+                ident = "vine"
+                name = "Vine"
+
+                [attributes]
+                Strength = 40.0
+
+                [[modifier]]
+                attribute = "Strength"
+                modifier = { Multiply = 0.5 }
+                condition = "Wet"
+
+                [[modifier]]
+                attribute = "Weight"
+                modifier = { Add = 1.0 }
+                condition = { All = ["Wet", { InBiome = "caves" }] }
+                "#,
+            )
+            .with_file("items/README.md", "not an item")
+            .with_file("item/pebble.png", png(16, 16))
+            .with_file("item/screen.png", png(64, 16))
+    }
+
+    #[test]
+    fn load_items() {
+        let mut registry: ItemRegistry = ItemRegistry::new();
+        let loaded = registry.load_data_items(&source(), "items").unwrap();
+
+        assert_eq!(loaded.idents, ["pebble", "screen", "vine"]);
+        assert!(loaded.errors.is_empty());
+
+        let pebble = registry.get("pebble").unwrap();
+        assert_eq!(pebble.get_name(), "Pebble");
+        assert_eq!(pebble.get_tags(), ["stone"]);
+        assert_eq!(pebble.get_max_stack_size(), 16);
+        assert!(matches!(pebble.get_texture(), ItemTexture::Image(_)));
+
+        let parser = AttributeParser::from(pebble.get_stats().get_all_attributes());
+        assert_eq!(parser.aggregate_to_value(AttributeType::Weight), 2.5);
+
+        // Loading the items again gives their attributes the same uuids
+        let mut reloaded: ItemRegistry = ItemRegistry::new();
+        reloaded.load_data_items(&source(), "items").unwrap();
+        for ident in ["pebble", "vine"] {
+            assert_eq!(
+                registry.get(ident).unwrap().get_stats().get_all_attributes(),
+                reloaded.get(ident).unwrap().get_stats().get_all_attributes()
+            );
+        }
+
+        let screen = registry.get("screen").unwrap();
+        assert_eq!(screen.get_max_stack_size(), DEFAULT_MAX_STACK_SIZE);
+        assert!(matches!(
+            screen.get_texture(),
+            ItemTexture::Animated(_, FrameProperties { width: 16, .. }, _)
+        ));
+    }
+
+    #[test]
+    fn conditional_modifiers() {
+        let mut registry: ItemRegistry = ItemRegistry::new();
+        registry.load_data_items(&source(), "items").unwrap();
+
+        let vine = registry.get("vine").unwrap();
+        let parser = AttributeParser::from(vine.get_stats().get_all_attributes());
+        assert_eq!(parser.aggregate_to_value(AttributeType::Strength), 40.0);
+        assert_eq!(parser.aggregate_to_value(AttributeType::Weight), 0.0);
+
+        let parser = parser.with_context(AttributeContext::new().with_wet(true));
+        assert_eq!(parser.aggregate_to_value(AttributeType::Strength), 20.0);
+        assert_eq!(parser.aggregate_to_value(AttributeType::Weight), 0.0);
+
+        let parser =
+            parser.with_context(AttributeContext::new().with_wet(true).with_biome("caves"));
+        assert_eq!(parser.aggregate_to_value(AttributeType::Weight), 1.0);
+    }
+
+    #[test]
+    fn broken_items() {
+        let source = source()
+            .with_file(
+                "items/ghost.toml",
+                "ident = \"ghost\"\nname = \"Ghost\"\ntexture = \"item/ghost.png\"",
+            )
+            .with_file("items/husk.toml", "ident = \"husk\"\nname = ");
+
+        let mut registry: ItemRegistry = ItemRegistry::new();
+        let loaded = registry.load_data_items(&source, "items").unwrap();
+
+        // The broken files are reported, the other items still load
+        assert_eq!(loaded.idents, ["pebble", "screen", "vine"]);
+        assert!(matches!(
+            loaded.errors.as_slice(),
+            [DataItemError::Io { .. }, DataItemError::Parse { .. }]
+        ));
+        assert!(registry.get("ghost").is_none());
+    }
+}
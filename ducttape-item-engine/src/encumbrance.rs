@@ -0,0 +1,135 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribute::{condition::AttributeContext, AttributeParser, AttributeType},
+    item::{EngineHook, Item, ItemCollection, ItemStack},
+};
+
+/// How burdened a character is by what it carries, from the carried weight relative to its strength
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum Encumbrance {
+    /// Up to half of the strength, no penalty
+    Unburdened,
+    /// Up to the strength
+    Burdened,
+    /// Up to one and a half times the strength
+    Strained,
+    /// More than one and a half times the strength
+    Overloaded,
+}
+
+impl Encumbrance {
+    pub fn from_load(weight: f64, strength: f64) -> Self {
+        if strength <= 0.0 {
+            return if weight > 0.0 {
+                Encumbrance::Overloaded
+            } else {
+                Encumbrance::Unburdened
+            };
+        }
+
+        let load = weight / strength;
+
+        if load <= 0.5 {
+            Encumbrance::Unburdened
+        } else if load <= 1.0 {
+            Encumbrance::Burdened
+        } else if load <= 1.5 {
+            Encumbrance::Strained
+        } else {
+            Encumbrance::Overloaded
+        }
+    }
+
+    /// Factor applied to horizontal acceleration
+    pub fn get_acceleration_factor(&self) -> f32 {
+        match self {
+            Encumbrance::Unburdened => 1.0,
+            Encumbrance::Burdened => 0.8,
+            Encumbrance::Strained => 0.5,
+            Encumbrance::Overloaded => 0.25,
+        }
+    }
+
+    /// Factor applied to the jump force
+    pub fn get_jump_factor(&self) -> f32 {
+        match self {
+            Encumbrance::Unburdened => 1.0,
+            Encumbrance::Burdened => 0.9,
+            Encumbrance::Strained => 0.7,
+            Encumbrance::Overloaded => 0.5,
+        }
+    }
+
+    pub fn can_wall_jump(&self) -> bool {
+        *self != Encumbrance::Overloaded
+    }
+}
+
+impl std::fmt::Display for Encumbrance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Encumbrance::Unburdened => write!(f, "Unburdened"),
+            Encumbrance::Burdened => write!(f, "Burdened"),
+            Encumbrance::Strained => write!(f, "Strained"),
+            Encumbrance::Overloaded => write!(f, "Overloaded"),
+        }
+    }
+}
+
+/// Get the weight of a whole stack
+pub fn get_stack_weight<THook: EngineHook>(
+    stack: &ItemStack<THook>,
+    context: &AttributeContext,
+) -> f64 {
+    AttributeParser::from(stack.get_stats().get_all_attributes())
+        .with_context(context.clone())
+        .aggregate_to_value(AttributeType::Weight)
+        * stack.get_count() as f64
+}
+
+/// Get the weight of every stack in a collection
+pub fn get_carried_weight<THook: EngineHook>(
+    collection: &impl ItemCollection<THook>,
+    context: &AttributeContext,
+) -> f64 {
+    collection
+        .iter()
+        .map(|stack| get_stack_weight(stack, context))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{item::ItemCollectionSized, prelude_items::rock};
+
+    use super::*;
+
+    #[test]
+    fn tiers() {
+        assert_eq!(Encumbrance::from_load(0.0, 20.0), Encumbrance::Unburdened);
+        assert_eq!(Encumbrance::from_load(10.0, 20.0), Encumbrance::Unburdened);
+        assert_eq!(Encumbrance::from_load(15.0, 20.0), Encumbrance::Burdened);
+        assert_eq!(Encumbrance::from_load(25.0, 20.0), Encumbrance::Strained);
+        assert_eq!(Encumbrance::from_load(31.0, 20.0), Encumbrance::Overloaded);
+        assert_eq!(Encumbrance::from_load(1.0, 0.0), Encumbrance::Overloaded);
+
+        assert!(Encumbrance::Strained.can_wall_jump());
+        assert!(!Encumbrance::Overloaded.can_wall_jump());
+    }
+
+    #[test]
+    fn carried_weight() {
+        let rock = ItemStack::new(Arc::new(rock()), 3);
+        let context = AttributeContext::new();
+        let rock_weight = get_stack_weight(&rock, &context) / 3.0;
+
+        let mut inventory = ItemCollectionSized::new(4);
+        inventory.add_item(rock).unwrap();
+
+        assert!(rock_weight > 0.0);
+        assert_eq!(get_carried_weight(&inventory, &context), rock_weight * 3.0);
+    }
+}
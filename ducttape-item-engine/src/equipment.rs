@@ -0,0 +1,465 @@
+use std::{collections::HashMap, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribute::{
+        condition::AttributeContext, schema::ATTRIBUTE_REGISTRY, Attribute, AttributeModifier,
+        AttributeParser, AttributeReason, AttributeType,
+    },
+    item::{
+        is_air, merge_into_stacks, space_in_stacks, AddResult, DummyHook, EngineHook, Item,
+        ItemCollection, ItemCollectionError, ItemCollectionEvent, ItemCollectionResult, ItemStack,
+    },
+    prelude_items::air::Air,
+};
+
+pub const MAIN_HAND: &str = "main_hand";
+pub const OFF_HAND: &str = "off_hand";
+pub const BACK: &str = "back";
+pub const BELT: &str = "belt";
+
+/// How much agility every unit of equipped weight costs the wearer
+pub const AGILITY_PER_WEIGHT: f64 = 0.5;
+/// The slowest a wearer can get, no matter how heavy the equipment
+pub const MIN_MOBILITY: f64 = 0.25;
+
+/// A named equipment slot, only items with one of the slot's tags can be equipped in it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EquipmentSlot {
+    pub name: String,
+    /// A slot without tags accepts every item
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+impl EquipmentSlot {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            tags: Vec::new(),
+        }
+    }
+
+    pub fn with_tags(mut self, tags: &[&str]) -> Self {
+        self.tags = tags.iter().map(|tag| (*tag).to_owned()).collect();
+        self
+    }
+
+    pub fn accepts<THook: EngineHook>(&self, item: &dyn Item<THook>) -> bool {
+        self.tags.is_empty() || item.get_tags().iter().any(|tag| self.tags.contains(tag))
+    }
+}
+
+/// The items a character holds and wears, one stack per named slot
+pub struct EquipmentCollection<THook: EngineHook = DummyHook> {
+    slots: Vec<EquipmentSlot>,
+    items: Vec<ItemStack<THook>>,
+    listeners: Vec<Box<dyn Fn(ItemCollectionEvent<THook>) + Send + Sync>>,
+}
+
+impl<THook: EngineHook> EquipmentCollection<THook> {
+    pub fn new(slots: Vec<EquipmentSlot>) -> Self {
+        let items = slots.iter().map(|_| Air::new_itemstack()).collect();
+
+        Self {
+            slots,
+            items,
+            listeners: Vec::new(),
+        }
+    }
+
+    /// Hands that hold anything, a back for long items and a belt for small tools and bindings
+    pub fn player() -> Self {
+        Self::new(vec![
+            EquipmentSlot::new(MAIN_HAND),
+            EquipmentSlot::new(OFF_HAND),
+            EquipmentSlot::new(BACK).with_tags(&["long", "back"]),
+            EquipmentSlot::new(BELT).with_tags(&["tool", "binding", "belt"]),
+        ])
+    }
+
+    pub fn get_slots(&self) -> &[EquipmentSlot] {
+        &self.slots
+    }
+
+    pub fn get_slot_index(&self, slot: &str) -> Option<usize> {
+        self.slots.iter().position(|s| s.name == slot)
+    }
+
+    /// Get the stack equipped in a slot, `None` if the slot is empty or doesn't exist
+    pub fn get_equipped(&self, slot: &str) -> Option<&ItemStack<THook>> {
+        self.get_slot_index(slot)
+            .and_then(|index| self.get_item(index).ok())
+    }
+
+    /// Check if the slot at `index` would accept the stack
+    pub fn accepts(&self, index: usize, item: &ItemStack<THook>) -> bool {
+        self.slots.get(index).is_some_and(|slot| slot.accepts(item))
+    }
+
+    /// Put a stack into a slot, returns the stack that was equipped there before
+    pub fn equip(
+        &mut self,
+        slot: &str,
+        item: ItemStack<THook>,
+    ) -> ItemCollectionResult<Option<ItemStack<THook>>> {
+        let index = self
+            .get_slot_index(slot)
+            .ok_or(ItemCollectionError::NotFound)?;
+
+        if !self.accepts(index, &item) {
+            return Err(ItemCollectionError::NotAccepted);
+        }
+
+        let previous = self.remove_item(index).ok();
+
+        self.notify(ItemCollectionEvent::Add {
+            index,
+            item: Arc::new(item.clone()),
+        });
+        self.items[index] = item;
+
+        Ok(previous)
+    }
+
+    pub fn unequip(&mut self, slot: &str) -> ItemCollectionResult<ItemStack<THook>> {
+        let index = self
+            .get_slot_index(slot)
+            .ok_or(ItemCollectionError::NotFound)?;
+
+        self.remove_item(index)
+    }
+
+    /// The first empty slot that accepts the stack
+    fn free_slot_for(&self, item: &ItemStack<THook>) -> Option<usize> {
+        (0..self.items.len())
+            .find(|index| is_air(&self.items[*index]) && self.accepts(*index, item))
+    }
+
+    /// Aggregate the stats of a character wearing this equipment.
+    ///
+    /// Every equipped item is aggregated on its own with `equipped` set in the context, then
+    /// each of its values of a `wearer` attribute is added to the base attributes of the character
+    pub fn get_effective_stats(
+        &self,
+        base: HashMap<AttributeType, Vec<Attribute>>,
+        context: &AttributeContext,
+    ) -> AttributeParser {
+        let mut parser = AttributeParser::from(base).with_context(context.clone());
+        let item_context = context.clone().with_equipped(true);
+        let registry = ATTRIBUTE_REGISTRY.read().unwrap();
+
+        for (slot, stack) in self
+            .slots
+            .iter()
+            .zip(self.items.iter())
+            .filter(|(_, stack)| !is_air(stack))
+        {
+            let values = AttributeParser::from(stack.get_stats().get_all_attributes())
+                .with_context(item_context.clone())
+                .aggregate_to_values_in(&registry);
+
+            for (at, value) in values {
+                if !registry.get_schema(at).wearer {
+                    continue;
+                }
+
+                parser.push(
+                    at,
+                    Attribute {
+                        uuid: Attribute::stable_uuid(&["equipment", &slot.name, at.get_id()]),
+                        reason: AttributeReason::Display(stack.get_name()),
+                        priority: 1,
+                        modifier: AttributeModifier::Add(value * stack.get_count() as f64),
+                        condition: None,
+                        lifetime: None,
+                    },
+                );
+            }
+        }
+
+        parser
+    }
+
+    /// How fast a character with `base_agility` moves wearing this equipment, compared to
+    /// moving without it. Every unit of equipped weight costs `AGILITY_PER_WEIGHT`, so a
+    /// heavier loadout never moves faster
+    pub fn get_mobility(&self, base_agility: f64, context: &AttributeContext) -> f64 {
+        if base_agility <= 0.0 {
+            return MIN_MOBILITY;
+        }
+
+        let weight = self
+            .get_effective_stats(HashMap::new(), context)
+            .aggregate_to_value(AttributeType::Weight)
+            .max(0.0);
+
+        ((base_agility - weight * AGILITY_PER_WEIGHT) / base_agility).clamp(MIN_MOBILITY, 1.0)
+    }
+}
+
+impl<THook: EngineHook> ItemCollection<THook> for EquipmentCollection<THook> {
+    fn add_item(&mut self, item: ItemStack<THook>) -> ItemCollectionResult<()> {
+        if self.get_space_for(&item) < item.get_count() {
+            return Err(ItemCollectionError::Full);
+        }
+
+        self.add_item_partial(item);
+        Ok(())
+    }
+
+    fn add_item_partial(&mut self, mut item: ItemStack<THook>) -> AddResult<THook> {
+        for index in merge_into_stacks(&mut self.items, &mut item) {
+            self.notify(ItemCollectionEvent::Update {
+                index,
+                item: Arc::new(self.items[index].clone()),
+            });
+        }
+
+        while item.get_count() > 0 {
+            let Some(index) = self.free_slot_for(&item) else {
+                return AddResult::Partial(item);
+            };
+
+            let stack = item.take(item.get_max_stack_size());
+            self.notify(ItemCollectionEvent::Add {
+                index,
+                item: Arc::new(stack.clone()),
+            });
+            self.items[index] = stack;
+        }
+
+        AddResult::Complete
+    }
+
+    fn get_space_for(&self, item: &ItemStack<THook>) -> u32 {
+        let free_slots = (0..self.items.len())
+            .filter(|index| is_air(&self.items[*index]) && self.accepts(*index, item))
+            .count() as u32;
+
+        space_in_stacks(&self.items, item)
+            .saturating_add(free_slots.saturating_mul(item.get_max_stack_size()))
+    }
+
+    fn split_stack(&mut self, index: usize, count: u32) -> ItemCollectionResult<usize> {
+        let item = self.get_item(index)?.clone();
+        let target = self.free_slot_for(&item).ok_or(ItemCollectionError::Full)?;
+
+        let stack = self.get_item_mut(index)?.split(count)?;
+        self.notify(ItemCollectionEvent::Update {
+            index,
+            item: Arc::new(self.items[index].clone()),
+        });
+
+        self.notify(ItemCollectionEvent::Add {
+            index: target,
+            item: Arc::new(stack.clone()),
+        });
+        self.items[target] = stack;
+
+        Ok(target)
+    }
+
+    fn notify(&self, event: ItemCollectionEvent<THook>) {
+        self.listeners.iter().for_each(|f| f(event.clone()));
+    }
+
+    fn get_item(&self, index: usize) -> ItemCollectionResult<&ItemStack<THook>> {
+        match self.items.get(index) {
+            Some(item) if !is_air(item) => Ok(item),
+            _ => Err(ItemCollectionError::NotFound),
+        }
+    }
+
+    fn get_item_mut(&mut self, index: usize) -> ItemCollectionResult<&mut ItemStack<THook>> {
+        match self.items.get_mut(index) {
+            Some(item) if !is_air(&*item) => Ok(item),
+            _ => Err(ItemCollectionError::NotFound),
+        }
+    }
+
+    fn remove_item(&mut self, index: usize) -> ItemCollectionResult<ItemStack<THook>> {
+        let item = self.get_item_mut(index)?;
+        let item = std::mem::replace(item, Air::new_itemstack());
+
+        self.notify(ItemCollectionEvent::Remove {
+            index,
+            item: Arc::new(item.clone()),
+        });
+
+        Ok(item)
+    }
+
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, ItemStack<THook>> {
+        self.items.iter()
+    }
+
+    fn iter_mut(&mut self) -> std::slice::IterMut<'_, ItemStack<THook>> {
+        self.items.iter_mut()
+    }
+
+    fn into_iter(self) -> std::vec::IntoIter<ItemStack<THook>> {
+        self.items.into_iter()
+    }
+
+    fn refresh(&mut self) {
+        self.notify(ItemCollectionEvent::ManualRefresh);
+    }
+
+    /// Empty every slot, the slots themselves are kept
+    fn clear(&mut self) {
+        self.items.iter_mut().for_each(|item| *item = Air::new_itemstack());
+        self.notify(ItemCollectionEvent::Clear);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.items.iter().all(is_air)
+    }
+
+    fn get_items(&self) -> &Vec<ItemStack<THook>> {
+        &self.items
+    }
+
+    fn get_items_mut(&mut self) -> &mut Vec<ItemStack<THook>> {
+        &mut self.items
+    }
+
+    fn listen(&mut self, f: Box<dyn Fn(ItemCollectionEvent<THook>) + Send + Sync>) {
+        self.listeners.push(f);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        add_base_attribute,
+        data_item::DataItem,
+        item::ItemMut,
+        prelude_items::{dev_tablet, rock},
+        template::source::MemoryAssetSource,
+    };
+
+    use super::*;
+
+    fn rope() -> ItemStack {
+        let mut rope: DataItem = DataItem::from_toml(
+            &MemoryAssetSource::new(),
+            "rope.toml",
+            "ident = \"rope\"\nname = \"Rope\"\ntags = [\"binding\"]",
+        )
+        .unwrap();
+        add_base_attribute!(rope, AttributeType::Weight, 5.0);
+        add_base_attribute!(rope, AttributeType::Strength, 10.0);
+
+        ItemStack::new(Arc::new(rope), 1)
+    }
+
+    #[test]
+    fn slots_filter_by_tag() {
+        let mut equipment: EquipmentCollection = EquipmentCollection::player();
+
+        assert!(matches!(
+            equipment.equip(BELT, ItemStack::new(Arc::new(rock()), 1)),
+            Err(ItemCollectionError::NotAccepted)
+        ));
+        assert!(equipment.equip(BELT, rope()).unwrap().is_none());
+
+        let previous = equipment
+            .equip(MAIN_HAND, ItemStack::new(Arc::new(rock()), 1))
+            .unwrap();
+        assert!(previous.is_none());
+
+        let previous = equipment
+            .equip(MAIN_HAND, ItemStack::new(Arc::new(dev_tablet()), 1))
+            .unwrap();
+        assert_eq!(previous.unwrap().get_ident(), "rock");
+
+        // The tablet has no tags, so it only fits into the free off hand
+        equipment.add_item(ItemStack::new(Arc::new(dev_tablet()), 1)).unwrap();
+        assert_eq!(equipment.get_equipped(OFF_HAND).unwrap().get_ident(), "dev_tablet");
+        assert!(matches!(
+            equipment.add_item(ItemStack::new(Arc::new(dev_tablet()), 1)),
+            Err(ItemCollectionError::Full)
+        ));
+
+        assert_eq!(equipment.unequip(BELT).unwrap().get_ident(), "rope");
+        assert!(equipment.get_equipped(BELT).is_none());
+    }
+
+    #[test]
+    fn effective_stats() {
+        let mut equipment: EquipmentCollection = EquipmentCollection::player();
+        equipment.equip(BELT, rope()).unwrap();
+        equipment
+            .equip(MAIN_HAND, ItemStack::new(Arc::new(rock()), 1))
+            .unwrap();
+
+        let base = HashMap::from([(
+            AttributeType::Strength,
+            vec![Attribute {
+                uuid: uuid::Uuid::new_v4(),
+                reason: AttributeReason::Hidden,
+                priority: 0,
+                modifier: AttributeModifier::Set(20.0),
+                condition: None,
+                lifetime: None,
+            }],
+        )]);
+
+        let stats = equipment.get_effective_stats(base, &AttributeContext::new());
+        let rock_weight = AttributeParser::from(rock().get_stats().get_all_attributes())
+            .aggregate_to_value(AttributeType::Weight);
+
+        // The strength of the rope and the rock is what they can hold, not the wearer
+        assert_eq!(stats.aggregate_to_value(AttributeType::Strength), 20.0);
+        assert_eq!(stats.aggregate_to_value(AttributeType::Sharpness), 0.0);
+        assert_eq!(stats.aggregate_to_value(AttributeType::Durability), 0.0);
+        assert_eq!(
+            stats.aggregate_to_value(AttributeType::Weight),
+            5.0 + rock_weight
+        );
+    }
+
+    #[test]
+    fn heavier_equipment_is_slower() {
+        let context = AttributeContext::new();
+        let mut equipment: EquipmentCollection = EquipmentCollection::player();
+        let mut mobility = equipment.get_mobility(100.0, &context);
+        assert_eq!(mobility, 1.0);
+
+        let loadouts = [
+            (MAIN_HAND, ItemStack::new(Arc::new(rock()), 1)),
+            (MAIN_HAND, ItemStack::new(Arc::new(rock()), 3)),
+            (BELT, rope()),
+            (OFF_HAND, ItemStack::new(Arc::new(rock()), 10)),
+        ];
+
+        // Every step only adds weight, the rock's agility must not speed the wearer up
+        for (slot, stack) in loadouts {
+            equipment.equip(slot, stack).unwrap();
+            let heavier = equipment.get_mobility(100.0, &context);
+
+            assert!(heavier < mobility);
+            mobility = heavier;
+        }
+
+        assert_eq!(equipment.get_mobility(10.0, &context), MIN_MOBILITY);
+        assert_eq!(equipment.get_mobility(0.0, &context), MIN_MOBILITY);
+    }
+
+    #[test]
+    fn agility_stays_with_the_item() {
+        let mut equipment: EquipmentCollection = EquipmentCollection::player();
+        equipment
+            .equip(MAIN_HAND, ItemStack::new(Arc::new(rock()), 3))
+            .unwrap();
+
+        let stats = equipment.get_effective_stats(HashMap::new(), &AttributeContext::new());
+        assert_eq!(stats.aggregate_to_value(AttributeType::Agility), 0.0);
+    }
+}
@@ -0,0 +1,288 @@
+use std::sync::Arc;
+
+use crate::item::{
+    is_air, EngineHook, Item, ItemCollection, ItemCollectionEvent, ItemCollectionResult,
+    ItemStack,
+};
+
+/// What a special ability gets to work with while it handles a hook
+pub struct HookContext<'a, THook: EngineHook> {
+    stack: &'a mut ItemStack<THook>,
+    commands: Vec<THook::Command>,
+    cancelled: bool,
+}
+
+impl<'a, THook: EngineHook> HookContext<'a, THook> {
+    pub fn new(stack: &'a mut ItemStack<THook>) -> Self {
+        Self {
+            stack,
+            commands: Vec::new(),
+            cancelled: false,
+        }
+    }
+
+    /// Get the stack the hook was dispatched to
+    pub fn get_stack(&self) -> &ItemStack<THook> {
+        self.stack
+    }
+
+    /// Get the stack the hook was dispatched to, changes are kept after the dispatch
+    pub fn get_stack_mut(&mut self) -> &mut ItemStack<THook> {
+        self.stack
+    }
+
+    /// Ask the host game to do something once the dispatch is done
+    pub fn emit(&mut self, command: THook::Command) {
+        self.commands.push(command);
+    }
+
+    /// Skip the remaining abilities and tell the host game not to run its default behaviour for the hook
+    pub fn cancel(&mut self) {
+        self.cancelled = true;
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+}
+
+/// The result of dispatching a hook to a stack
+#[derive(Debug)]
+pub struct HookOutcome<THook: EngineHook> {
+    commands: Vec<THook::Command>,
+    cancelled: bool,
+    triggered: Vec<String>,
+}
+
+impl<THook: EngineHook> HookOutcome<THook> {
+    /// Get the commands emitted by the abilities, in the order they were emitted
+    pub fn get_commands(&self) -> &[THook::Command] {
+        &self.commands
+    }
+
+    pub fn into_commands(self) -> Vec<THook::Command> {
+        self.commands
+    }
+
+    /// Check if an ability cancelled the hook
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled
+    }
+
+    /// Get the names of the abilities that ran
+    pub fn get_triggered(&self) -> &[String] {
+        &self.triggered
+    }
+
+    /// Check if any ability subscribed to the hook
+    pub fn is_handled(&self) -> bool {
+        !self.triggered.is_empty()
+    }
+}
+
+impl<THook: EngineHook> ItemStack<THook> {
+    /// Run every special ability of the stack that subscribed to the hook, in the order the item lists them
+    pub fn dispatch_hook(&mut self, hook: &THook) -> HookOutcome<THook> {
+        let abilities = self.special_abilities();
+        let mut context = HookContext::new(self);
+        let mut triggered = Vec::new();
+
+        for ability in abilities {
+            if context.is_cancelled() {
+                break;
+            }
+
+            if ability.get_hooks().iter().any(|subscribed| subscribed.matches(hook)) {
+                ability.on_hook(hook, &mut context);
+                triggered.push(ability.get_name());
+            }
+        }
+
+        HookOutcome {
+            commands: context.commands,
+            cancelled: context.cancelled,
+            triggered,
+        }
+    }
+}
+
+/// Dispatch a hook to the stack at `index` of a collection, listeners are told if an ability changed the stack
+pub fn dispatch_hook_at<THook: EngineHook, C: ItemCollection<THook> + ?Sized>(
+    collection: &mut C,
+    index: usize,
+    hook: &THook,
+) -> ItemCollectionResult<HookOutcome<THook>> {
+    let stack = collection.get_item_mut(index)?;
+    let before = stack.clone();
+    let outcome = stack.dispatch_hook(hook);
+    let after = stack.clone();
+
+    if is_air(&after) && !is_air(&before) {
+        collection.notify(ItemCollectionEvent::Remove {
+            index,
+            item: Arc::new(before),
+        });
+    } else if !after.can_stack_with(&before) || after.get_count() != before.get_count() {
+        collection.notify(ItemCollectionEvent::Update {
+            index,
+            item: Arc::new(after),
+        });
+    }
+
+    Ok(outcome)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use crate::{
+        add_base_attribute,
+        attribute::AttributeType,
+        item::{ItemCollectionSized, ItemMut, ItemTexture, SpecialAbility, Stats},
+        prelude_items::stats::BasicStats,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Clone)]
+    enum TestHook {
+        Use,
+        Hit(f64),
+        Tick,
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum TestCommand {
+        Say(String),
+    }
+
+    impl EngineHook for TestHook {
+        type Command = TestCommand;
+    }
+
+    /// Wears the item down a little and says so whenever it is used
+    #[derive(Debug, Clone)]
+    struct Sharpen;
+
+    impl SpecialAbility<TestHook> for Sharpen {
+        fn get_name(&self) -> String {
+            "sharpen".into()
+        }
+
+        fn get_hooks(&self) -> Vec<TestHook> {
+            vec![TestHook::Use]
+        }
+
+        fn on_hook(&self, _hook: &TestHook, context: &mut HookContext<TestHook>) {
+            context.get_stack_mut().get_item_mut().damage(5.0);
+            context.emit(TestCommand::Say("sharpened".into()));
+        }
+    }
+
+    /// Breaks one item off the stack on hard hits
+    #[derive(Debug, Clone)]
+    struct Fragile;
+
+    impl SpecialAbility<TestHook> for Fragile {
+        fn get_name(&self) -> String {
+            "fragile".into()
+        }
+
+        fn get_hooks(&self) -> Vec<TestHook> {
+            vec![TestHook::Hit(0.0)]
+        }
+
+        fn on_hook(&self, hook: &TestHook, context: &mut HookContext<TestHook>) {
+            if let TestHook::Hit(force) = hook {
+                if *force > 10.0 {
+                    context.get_stack_mut().decrement_count(1);
+                    context.cancel();
+                }
+            }
+        }
+    }
+
+    #[derive(Debug, Clone)]
+    struct Whetstone {
+        stats: BasicStats,
+    }
+
+    impl Item<TestHook> for Whetstone {
+        fn get_name(&self) -> String {
+            "Whetstone".into()
+        }
+
+        fn get_ident(&self) -> String {
+            "whetstone".into()
+        }
+
+        fn get_stats(&self) -> Box<dyn Stats> {
+            Box::new(self.stats.clone())
+        }
+
+        fn special_abilities(&self) -> Vec<Box<dyn SpecialAbility<TestHook>>> {
+            vec![Box::new(Fragile), Box::new(Sharpen)]
+        }
+
+        fn get_texture(&self) -> ItemTexture {
+            ItemTexture::None
+        }
+    }
+
+    impl ItemMut for Whetstone {
+        fn get_stats_mut(&mut self) -> &mut dyn Stats {
+            &mut self.stats
+        }
+    }
+
+    fn whetstones(count: u32) -> ItemStack<TestHook> {
+        let mut whetstone = Whetstone {
+            stats: BasicStats::new(),
+        };
+        add_base_attribute!(whetstone, AttributeType::Durability, 20.0);
+
+        ItemStack::new(Arc::new(whetstone), count)
+    }
+
+    #[test]
+    fn dispatch_to_subscribers() {
+        let mut stack = whetstones(1);
+
+        let outcome = stack.dispatch_hook(&TestHook::Use);
+        assert_eq!(outcome.get_triggered(), ["sharpen"]);
+        assert_eq!(
+            outcome.get_commands(),
+            [TestCommand::Say("sharpened".into())]
+        );
+        assert_eq!(stack.get_item().get_damage(), 5.0);
+
+        let outcome = stack.dispatch_hook(&TestHook::Tick);
+        assert!(!outcome.is_handled());
+        assert!(outcome.into_commands().is_empty());
+    }
+
+    #[test]
+    fn cancel_and_notify() {
+        let mut inventory: ItemCollectionSized<TestHook> = ItemCollectionSized::new(2);
+        inventory.add_item(whetstones(2)).unwrap();
+
+        let events = Arc::new(Mutex::new(Vec::new()));
+        inventory.listen(Box::new({
+            let events = events.clone();
+            move |event| events.lock().unwrap().push(event)
+        }));
+
+        let outcome = dispatch_hook_at(&mut inventory, 0, &TestHook::Hit(2.0)).unwrap();
+        assert!(outcome.is_handled() && !outcome.is_cancelled());
+        assert!(events.lock().unwrap().is_empty());
+
+        let outcome = dispatch_hook_at(&mut inventory, 0, &TestHook::Hit(20.0)).unwrap();
+        assert!(outcome.is_cancelled());
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 1);
+        assert!(matches!(
+            events.lock().unwrap().as_slice(),
+            [ItemCollectionEvent::Update { index: 0, .. }]
+        ));
+    }
+}
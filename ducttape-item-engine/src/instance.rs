@@ -0,0 +1,256 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribute::{Attribute, AttributeModifier, AttributeParser, AttributeReason, AttributeType},
+    item::{DummyHook, EngineHook, Item, ItemMut, ItemTexture, SpecialAbility, Stats},
+    prelude_items::stats::{BasicStats, BasicStatsBuilder},
+};
+
+/// Id of the attribute used to apply an instance's damage to its durability
+pub const DAMAGE_ATTRIBUTE_ID: uuid::Uuid = uuid::uuid!("6d1f2c3e-8b0a-4f57-9d4e-2a7c5b1e0f93");
+
+fn is_zero(value: &f64) -> bool {
+    *value == 0.0
+}
+
+/// The data that makes an item instance distinct from its prototype
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ItemInstanceData {
+    /// Attributes of this instance, applied on top of the prototype's attributes
+    #[serde(default, skip_serializing_if = "BasicStats::is_empty")]
+    pub stats: BasicStats,
+    /// How much durability this instance has lost
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub damage: f64,
+    /// A name that replaces the prototype's name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custom_name: Option<String>,
+    /// Free-form data attached to this instance by the game
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+}
+
+impl ItemInstanceData {
+    pub fn is_empty(&self) -> bool {
+        self.stats.is_empty()
+            && self.damage == 0.0
+            && self.custom_name.is_none()
+            && self.metadata.is_empty()
+    }
+}
+
+/// A single instance of a registered item, the prototype is shared between instances
+/// while everything in the instance data is local to this instance
+#[derive(Debug, Clone)]
+pub struct ItemInstance<THook: EngineHook = DummyHook> {
+    prototype: Arc<dyn Item<THook>>,
+    data: ItemInstanceData,
+}
+
+impl<THook: EngineHook> ItemInstance<THook> {
+    pub fn new(prototype: Arc<dyn Item<THook>>) -> Self {
+        Self::with_data(prototype, ItemInstanceData::default())
+    }
+
+    pub fn with_data(prototype: Arc<dyn Item<THook>>, data: ItemInstanceData) -> Self {
+        Self { prototype, data }
+    }
+
+    pub fn get_prototype(&self) -> Arc<dyn Item<THook>> {
+        self.prototype.clone()
+    }
+
+    pub fn get_data(&self) -> &ItemInstanceData {
+        &self.data
+    }
+
+    pub fn get_data_mut(&mut self) -> &mut ItemInstanceData {
+        &mut self.data
+    }
+
+    pub fn get_custom_name(&self) -> Option<&String> {
+        self.data.custom_name.as_ref()
+    }
+
+    pub fn set_custom_name(&mut self, name: Option<String>) {
+        self.data.custom_name = name;
+    }
+
+    pub fn get_metadata(&self, key: &str) -> Option<&serde_json::Value> {
+        self.data.metadata.get(key)
+    }
+
+    pub fn set_metadata(&mut self, key: String, value: serde_json::Value) {
+        self.data.metadata.insert(key, value);
+    }
+
+    pub fn remove_metadata(&mut self, key: &str) -> Option<serde_json::Value> {
+        self.data.metadata.remove(key)
+    }
+
+    /// The durability of this instance before any damage
+    pub fn get_max_durability(&self) -> f64 {
+        let mut attributes = self.prototype.get_stats().get_all_attributes();
+        for (at, instance_attributes) in self.data.stats.get_all_attributes() {
+            attributes.entry(at).or_default().extend(instance_attributes);
+        }
+
+        AttributeParser::from(attributes).aggregate_to_value(AttributeType::Durability)
+    }
+
+    pub fn get_durability(&self) -> f64 {
+        (self.get_max_durability() - self.data.damage).max(0.0)
+    }
+
+    pub fn get_damage(&self) -> f64 {
+        self.data.damage
+    }
+
+    /// Damage this instance, returns true if the instance broke
+    pub fn damage(&mut self, amount: f64) -> bool {
+        self.data.damage = (self.data.damage + amount.max(0.0)).min(self.get_max_durability());
+        self.is_broken()
+    }
+
+    pub fn repair(&mut self, amount: f64) {
+        self.data.damage = (self.data.damage - amount.max(0.0)).max(0.0);
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.data.damage > 0.0 && self.get_durability() <= 0.0
+    }
+}
+
+impl<THook: EngineHook> From<Arc<dyn Item<THook>>> for ItemInstance<THook> {
+    fn from(prototype: Arc<dyn Item<THook>>) -> Self {
+        Self::new(prototype)
+    }
+}
+
+impl<THook: EngineHook> Item<THook> for ItemInstance<THook> {
+    fn get_name(&self) -> String {
+        self.data
+            .custom_name
+            .clone()
+            .unwrap_or_else(|| self.prototype.get_name())
+    }
+
+    fn get_ident(&self) -> String {
+        self.prototype.get_ident()
+    }
+
+    fn get_stats(&self) -> Box<dyn Stats> {
+        let mut stats = BasicStatsBuilder::new()
+            .with_attribute_vecs(self.prototype.get_stats().get_all_attributes())
+            .with_attribute_vecs(self.data.stats.get_all_attributes());
+
+        if self.data.damage > 0.0 {
+            stats = stats.with_attribute(
+                AttributeType::Durability,
+                Attribute {
+                    uuid: DAMAGE_ATTRIBUTE_ID,
+                    reason: AttributeReason::Display("💥".into()),
+                    priority: u8::MAX,
+                    modifier: AttributeModifier::Add(-self.data.damage),
+                    condition: None,
+                    lifetime: None,
+                },
+            );
+        }
+
+        Box::new(stats.build())
+    }
+
+    fn special_abilities(&self) -> Vec<Box<dyn SpecialAbility<THook>>> {
+        self.prototype.special_abilities()
+    }
+
+    fn get_texture(&self) -> ItemTexture {
+        self.prototype.get_texture()
+    }
+
+    fn get_max_stack_size(&self) -> u32 {
+        self.prototype.get_max_stack_size()
+    }
+
+    fn get_tags(&self) -> Vec<String> {
+        self.prototype.get_tags()
+    }
+
+    fn get_components(&self) -> HashMap<String, Arc<dyn Item<THook>>> {
+        self.prototype.get_components()
+    }
+
+    fn with_components(
+        &self,
+        components: HashMap<String, Arc<dyn Item<THook>>>,
+    ) -> Option<Arc<dyn Item<THook>>> {
+        self.prototype.with_components(components).map(|prototype| {
+            Arc::new(ItemInstance::with_data(prototype, self.data.clone())) as Arc<dyn Item<THook>>
+        })
+    }
+
+    fn as_instance(&self) -> Option<&ItemInstance<THook>> {
+        Some(self)
+    }
+}
+
+impl<THook: EngineHook> ItemMut for ItemInstance<THook> {
+    fn get_stats_mut(&mut self) -> &mut dyn Stats {
+        &mut self.data.stats
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{item::ItemStack, prelude_items::rock};
+
+    use super::*;
+
+    #[test]
+    fn damage_is_per_instance() {
+        let rock: Arc<dyn Item> = Arc::new(rock());
+
+        let mut first = ItemStack::new(rock.clone(), 1);
+        let second = ItemStack::new(rock, 1);
+
+        assert!(!first.get_item_mut().damage(20.0));
+
+        assert_eq!(first.get_item().get_durability(), 30.0);
+        assert_eq!(second.get_item().get_durability(), 50.0);
+
+        let parser = AttributeParser::from(first.get_stats().get_all_attributes());
+        assert_eq!(parser.aggregate_to_value(AttributeType::Durability), 30.0);
+
+        assert!(first.get_item_mut().damage(100.0));
+        assert_eq!(first.get_item().get_durability(), 0.0);
+    }
+
+    #[test]
+    fn instance_stats() {
+        let mut rock = ItemStack::new(Arc::new(rock()), 1);
+
+        rock.get_stats_mut().push_attribute(
+            AttributeType::Sharpness,
+            Attribute {
+                uuid: uuid::Uuid::new_v4(),
+                reason: AttributeReason::Display("🔪".into()),
+                priority: 1,
+                modifier: AttributeModifier::Add(3.0),
+                condition: None,
+                lifetime: None,
+            },
+        );
+        rock.get_item_mut().set_custom_name(Some("Pointy rock".into()));
+
+        let parser = AttributeParser::from(rock.get_stats().get_all_attributes());
+        assert_eq!(parser.aggregate_to_value(AttributeType::Sharpness), 5.0);
+        assert_eq!(rock.get_name(), "Pointy rock");
+        assert_eq!(rock.get_prototype().get_name(), "🪨");
+    }
+}
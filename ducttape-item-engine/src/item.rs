@@ -1,11 +1,21 @@
-use std::{any::Any, collections::HashMap, fmt::Debug, sync::Arc};
+use std::{
+    any::Any,
+    collections::{BTreeMap, HashMap},
+    fmt::Debug,
+    sync::Arc,
+};
 
 use dyn_clone::DynClone;
 use godot::builtin::{Rect2, Vector2};
 use image::DynamicImage;
 
 use crate::{
-    attribute::{Attribute, AttributeType},
+    attribute::{
+        condition::{AttributeContext, AttributeLifetime},
+        Attribute, AttributeType,
+    },
+    hook::HookContext,
+    instance::ItemInstance,
     prelude_items::air::Air,
 };
 
@@ -15,7 +25,7 @@ pub enum AnimationType {
     // For the future
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct FrameProperties {
     pub duration: f64,
     pub width: u32,
@@ -63,6 +73,9 @@ impl From<(DynamicImage,FrameProperties, AnimationType)> for ItemTexture {
     }
 }
 
+/// How many items fit in one stack unless the item says otherwise
+pub const DEFAULT_MAX_STACK_SIZE: u32 = 64;
+
 /// The dynamic Item trait that represents an abstract game item. This trait will provide methods for getting every item's stats, name, and description.
 pub trait Item<THook: EngineHook = DummyHook>: Any + Debug + Send + Sync + DynClone {
     /// Get the item's name.
@@ -75,6 +88,29 @@ pub trait Item<THook: EngineHook = DummyHook>: Any + Debug + Send + Sync + DynCl
     fn special_abilities(&self) -> Vec<Box<dyn SpecialAbility<THook>>>;
     /// Get the item's texture
     fn get_texture(&self) -> ItemTexture;
+    /// Get how many of this item fit in a single stack
+    fn get_max_stack_size(&self) -> u32 {
+        DEFAULT_MAX_STACK_SIZE
+    }
+    /// Get the tags used to group items, e.g. for recipes that accept any `sharp` item
+    fn get_tags(&self) -> Vec<String> {
+        Vec::new()
+    }
+    /// Get the components this item is built from, this is empty unless the item is a compound item
+    fn get_components(&self) -> HashMap<String, Arc<dyn Item<THook>>> {
+        HashMap::new()
+    }
+    /// Build a copy of this item from a different set of components, `None` if this item is not a compound item
+    fn with_components(
+        &self,
+        _components: HashMap<String, Arc<dyn Item<THook>>>,
+    ) -> Option<Arc<dyn Item<THook>>> {
+        None
+    }
+    /// Get the per-instance data of this item, `None` if the item is a shared prototype
+    fn as_instance(&self) -> Option<&ItemInstance<THook>> {
+        None
+    }
 }
 
 dyn_clone::clone_trait_object!(<THook> Item<THook>);
@@ -85,21 +121,37 @@ pub trait ItemMut {
     fn get_stats_mut(&mut self) -> &mut dyn Stats;
 }
 
+/// Something an item can do in response to game events, see `ItemStack::dispatch_hook`
 pub trait SpecialAbility<THook: EngineHook = DummyHook>:
     Debug + Send + Sync + DynClone + 'static
 {
     fn get_name(&self) -> String;
+    /// The hooks this ability subscribes to, matched against dispatched hooks with `EngineHook::matches`
     fn get_hooks(&self) -> Vec<THook>;
+    /// Run the ability for a dispatched hook it subscribed to
+    fn on_hook(&self, hook: &THook, context: &mut HookContext<THook>);
 }
 
 dyn_clone::clone_trait_object!(<THook> SpecialAbility<THook>);
 
-pub trait EngineHook: Debug + Send + Sync + Clone + 'static {}
+/// The game events items can react to, defined by the host game (usually an enum with variants like `Use`, `Hit`, `Tick`)
+pub trait EngineHook: Debug + Send + Sync + Clone + 'static {
+    /// What abilities can ask the host game to do in response to a hook
+    type Command: Debug + Send + 'static;
+
+    /// Check if an ability subscribed to this hook should run for `hook`,
+    /// by default any hook of the same enum variant matches regardless of its data
+    fn matches(&self, hook: &Self) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(hook)
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DummyHook {}
 
-impl EngineHook for DummyHook {}
+impl EngineHook for DummyHook {
+    type Command = ();
+}
 
 /// The Stats trait that represents an abstract game item's stats.
 pub trait Stats {
@@ -116,6 +168,37 @@ pub trait Stats {
 
     fn remove_attribute(&mut self, at: AttributeType, id: uuid::Uuid);
     fn remove_attributes(&mut self, at: AttributeType);
+
+    /// Count a use of the item for every attribute with a limited number of uses,
+    /// attributes that run out of uses are removed
+    fn consume_use(&mut self) {
+        for (at, mut attributes) in self.get_all_attributes() {
+            let mut changed = false;
+            attributes.retain_mut(|attribute| match attribute.lifetime.as_mut() {
+                Some(lifetime @ AttributeLifetime::Uses(_)) => {
+                    changed = true;
+                    !lifetime.consume_use()
+                }
+                _ => true,
+            });
+
+            if changed {
+                self.set_attributes(at, attributes);
+            }
+        }
+    }
+
+    /// Remove every attribute whose lifetime is over in the context
+    fn remove_expired(&mut self, context: &AttributeContext) {
+        for (at, mut attributes) in self.get_all_attributes() {
+            let len = attributes.len();
+            attributes.retain(|attribute| !attribute.is_expired(context));
+
+            if attributes.len() != len {
+                self.set_attributes(at, attributes);
+            }
+        }
+    }
 }
 
 pub struct ItemCollectionUnsized<THook: EngineHook = DummyHook> {
@@ -129,6 +212,21 @@ pub struct ItemCollectionSized<THook: EngineHook = DummyHook> {
     size: usize,
 }
 
+impl<THook: EngineHook> Default for ItemCollectionUnsized<THook> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<THook: EngineHook> ItemCollectionUnsized<THook> {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            listeners: Vec::new(),
+        }
+    }
+}
+
 impl<THook: EngineHook> ItemCollectionSized<THook> {
     pub fn new(size: usize) -> Self {
         let mut items: Vec<ItemStack<THook>> = Vec::with_capacity(size);
@@ -157,6 +255,12 @@ pub enum ItemCollectionError {
     Empty,
     /// The item collection does not contain the item.
     NotFound,
+    /// The requested count is zero or more than the stack holds.
+    InvalidCount,
+    /// The items are different and cannot share a stack.
+    NotStackable,
+    /// The slot doesn't accept the item.
+    NotAccepted,
 }
 
 impl std::fmt::Display for ItemCollectionError {
@@ -165,6 +269,9 @@ impl std::fmt::Display for ItemCollectionError {
             ItemCollectionError::Full => write!(f, "Item collection is full"),
             ItemCollectionError::Empty => write!(f, "Item collection is empty"),
             ItemCollectionError::NotFound => write!(f, "Item not found in collection"),
+            ItemCollectionError::InvalidCount => write!(f, "Invalid item count for stack"),
+            ItemCollectionError::NotStackable => write!(f, "Items cannot be stacked together"),
+            ItemCollectionError::NotAccepted => write!(f, "Item is not accepted in this slot"),
         }
     }
 }
@@ -173,6 +280,28 @@ impl std::error::Error for ItemCollectionError {}
 
 pub type ItemCollectionResult<T> = Result<T, ItemCollectionError>;
 
+/// The outcome of adding as much of a stack as fits into a collection
+#[derive(Debug, Clone)]
+pub enum AddResult<THook: EngineHook = DummyHook> {
+    /// The whole stack was added
+    Complete,
+    /// Only part of the stack fit, the rest of the stack is returned
+    Partial(ItemStack<THook>),
+}
+
+impl<THook: EngineHook> AddResult<THook> {
+    pub fn is_complete(&self) -> bool {
+        matches!(self, AddResult::Complete)
+    }
+
+    pub fn get_leftover_count(&self) -> u32 {
+        match self {
+            AddResult::Complete => 0,
+            AddResult::Partial(leftover) => leftover.get_count(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ItemCollectionEvent<THook: EngineHook = DummyHook> {
     Add {
@@ -183,18 +312,68 @@ pub enum ItemCollectionEvent<THook: EngineHook = DummyHook> {
         index: usize,
         item: Arc<ItemStack<THook>>,
     },
+    /// The count of an existing stack changed
+    Update {
+        index: usize,
+        item: Arc<ItemStack<THook>>,
+    },
     Clear,
     ManualRefresh,
 }
 
+pub(crate) fn is_air<THook: EngineHook>(item: &ItemStack<THook>) -> bool {
+    item.get_ident() == "air"
+}
+
+/// Top up existing stacks that can hold more of the item, returns the indices of the changed stacks
+pub(crate) fn merge_into_stacks<THook: EngineHook>(
+    items: &mut [ItemStack<THook>],
+    item: &mut ItemStack<THook>,
+) -> Vec<usize> {
+    let mut changed = Vec::new();
+
+    for (index, stack) in items.iter_mut().enumerate() {
+        if item.get_count() == 0 {
+            break;
+        }
+
+        if is_air(stack) || !stack.can_stack_with(item) || stack.get_space() == 0 {
+            continue;
+        }
+
+        let moved = stack.get_space().min(item.get_count());
+        stack.increment_count(moved);
+        item.set_count(item.get_count() - moved);
+        changed.push(index);
+    }
+
+    changed
+}
+
+/// Space left in the existing stacks that can hold more of the item
+pub(crate) fn space_in_stacks<THook: EngineHook>(items: &[ItemStack<THook>], item: &ItemStack<THook>) -> u32 {
+    items
+        .iter()
+        .filter(|stack| !is_air(stack) && stack.can_stack_with(item))
+        .map(|stack| stack.get_space())
+        .sum()
+}
+
 pub trait ItemCollection<THook: EngineHook = DummyHook> {
+    /// Add a whole stack, merging into existing stacks first. Nothing is added if the stack doesn't fit
     fn add_item(&mut self, item: ItemStack<THook>) -> ItemCollectionResult<()>;
+    /// Add as much of a stack as fits, merging into existing stacks first
+    fn add_item_partial(&mut self, item: ItemStack<THook>) -> AddResult<THook>;
+    /// Get how many of the stack's items the collection can still hold
+    fn get_space_for(&self, item: &ItemStack<THook>) -> u32;
+    /// Move `count` items of a stack into a new stack, returns the index of the new stack
+    fn split_stack(&mut self, index: usize, count: u32) -> ItemCollectionResult<usize>;
     fn get_item(&self, index: usize) -> ItemCollectionResult<&ItemStack<THook>>;
     fn get_item_mut(&mut self, index: usize) -> ItemCollectionResult<&mut ItemStack<THook>>;
     fn remove_item(&mut self, index: usize) -> ItemCollectionResult<ItemStack<THook>>;
     fn len(&self) -> usize;
-    fn iter(&self) -> std::slice::Iter<ItemStack<THook>>;
-    fn iter_mut(&mut self) -> std::slice::IterMut<ItemStack<THook>>;
+    fn iter(&self) -> std::slice::Iter<'_, ItemStack<THook>>;
+    fn iter_mut(&mut self) -> std::slice::IterMut<'_, ItemStack<THook>>;
     fn refresh(&mut self); // Call this when you want to refresh the collection, usually after a manual change with get_iter_mut or get_item_mut
     fn into_iter(self) -> std::vec::IntoIter<ItemStack<THook>>;
     fn clear(&mut self);
@@ -203,18 +382,123 @@ pub trait ItemCollection<THook: EngineHook = DummyHook> {
     fn get_items_mut(&mut self) -> &mut Vec<ItemStack<THook>>;
     fn listen(&mut self, f: Box<dyn Fn(ItemCollectionEvent<THook>) + Send + Sync>);
     fn notify(&self, event: ItemCollectionEvent<THook>);
+
+    /// Take `count` items out of the stack at `index`, the slot turns into air once the stack is empty
+    fn take_item(&mut self, index: usize, count: u32) -> ItemCollectionResult<ItemStack<THook>> {
+        let stack_count = self.get_item(index)?.get_count();
+
+        if count == 0 || count > stack_count {
+            return Err(ItemCollectionError::InvalidCount);
+        }
+
+        if count == stack_count {
+            return self.remove_item(index);
+        }
+
+        let taken = self.get_item_mut(index)?.take(count);
+        let item = self.get_item(index)?.clone();
+        self.notify(ItemCollectionEvent::Update {
+            index,
+            item: Arc::new(item),
+        });
+
+        Ok(taken)
+    }
+
+    /// Move as many items as fit from the stack at `from` into the stack at `to`
+    fn merge(&mut self, from: usize, to: usize) -> ItemCollectionResult<()> {
+        if from == to {
+            return Ok(());
+        }
+
+        let source = self.get_item(from)?;
+        let target = self.get_item(to)?;
+
+        if !target.can_stack_with(source) {
+            return Err(ItemCollectionError::NotStackable);
+        }
+
+        let moved = target.get_space().min(source.get_count());
+        if moved == 0 {
+            return Err(ItemCollectionError::Full);
+        }
+
+        let items = self.get_items_mut();
+        items[to].increment_count(moved);
+        items[from].decrement_count(moved);
+
+        let (source, target) = (items[from].clone(), items[to].clone());
+
+        self.notify(ItemCollectionEvent::Update {
+            index: to,
+            item: Arc::new(target),
+        });
+
+        if is_air(&source) {
+            self.notify(ItemCollectionEvent::Remove {
+                index: from,
+                item: Arc::new(source),
+            });
+        } else {
+            self.notify(ItemCollectionEvent::Update {
+                index: from,
+                item: Arc::new(source),
+            });
+        }
+
+        Ok(())
+    }
 }
 
-impl<THook: EngineHook> ItemCollection<THook> for ItemCollectionUnsized<THook> {
-    fn add_item(&mut self, item: ItemStack<THook>) -> ItemCollectionResult<()> {
+impl<THook: EngineHook> ItemCollectionUnsized<THook> {
+    fn push_stack(&mut self, item: ItemStack<THook>) -> usize {
+        let index = self.items.len();
         self.notify(ItemCollectionEvent::Add {
-            index: self.items.len(),
+            index,
             item: Arc::new(item.clone()),
         });
         self.items.push(item);
+        index
+    }
+}
+
+impl<THook: EngineHook> ItemCollection<THook> for ItemCollectionUnsized<THook> {
+    fn add_item(&mut self, item: ItemStack<THook>) -> ItemCollectionResult<()> {
+        // An unsized collection always has room for more stacks
+        self.add_item_partial(item);
         Ok(())
     }
 
+    fn add_item_partial(&mut self, mut item: ItemStack<THook>) -> AddResult<THook> {
+        for index in merge_into_stacks(&mut self.items, &mut item) {
+            self.notify(ItemCollectionEvent::Update {
+                index,
+                item: Arc::new(self.items[index].clone()),
+            });
+        }
+
+        while item.get_count() > 0 {
+            let stack = item.take(item.get_max_stack_size());
+            self.push_stack(stack);
+        }
+
+        AddResult::Complete
+    }
+
+    fn get_space_for(&self, _item: &ItemStack<THook>) -> u32 {
+        u32::MAX
+    }
+
+    fn split_stack(&mut self, index: usize, count: u32) -> ItemCollectionResult<usize> {
+        let stack = self.get_item_mut(index)?.split(count)?;
+        self.notify(ItemCollectionEvent::Update {
+            index,
+            item: Arc::new(self.items[index].clone()),
+        });
+
+        Ok(self.push_stack(stack))
+    }
+
     fn notify(&self, event: ItemCollectionEvent<THook>) {
         self.listeners.iter().for_each(|f| f(event.clone()));
     }
@@ -268,11 +552,11 @@ impl<THook: EngineHook> ItemCollection<THook> for ItemCollectionUnsized<THook> {
         self.items.len()
     }
 
-    fn iter(&self) -> std::slice::Iter<ItemStack<THook>> {
+    fn iter(&self) -> std::slice::Iter<'_, ItemStack<THook>> {
         self.items.iter()
     }
 
-    fn iter_mut(&mut self) -> std::slice::IterMut<ItemStack<THook>> {
+    fn iter_mut(&mut self) -> std::slice::IterMut<'_, ItemStack<THook>> {
         self.items.iter_mut()
     }
 
@@ -306,19 +590,73 @@ impl<THook: EngineHook> ItemCollection<THook> for ItemCollectionUnsized<THook> {
     }
 }
 
-impl<THook: EngineHook> ItemCollection<THook> for ItemCollectionSized<THook> {
-    fn add_item(&mut self, item: ItemStack<THook>) -> ItemCollectionResult<()> {
-        if let Some(index) = self.items.iter().position(|i| i.get_ident() == "air") {
+impl<THook: EngineHook> ItemCollectionSized<THook> {
+    /// Place a stack into the first empty slot, returns the index of the slot
+    fn place_stack(&mut self, item: ItemStack<THook>) -> ItemCollectionResult<usize> {
+        if let Some(index) = self.items.iter().position(is_air) {
             self.notify(ItemCollectionEvent::Add {
                 index,
                 item: Arc::new(item.clone()),
             });
             self.items[index] = item;
-            Ok(())
+            Ok(index)
         } else {
             Err(ItemCollectionError::Full)
         }
     }
+}
+
+impl<THook: EngineHook> ItemCollection<THook> for ItemCollectionSized<THook> {
+    fn add_item(&mut self, item: ItemStack<THook>) -> ItemCollectionResult<()> {
+        if self.get_space_for(&item) < item.get_count() {
+            return Err(ItemCollectionError::Full);
+        }
+
+        self.add_item_partial(item);
+        Ok(())
+    }
+
+    fn add_item_partial(&mut self, mut item: ItemStack<THook>) -> AddResult<THook> {
+        for index in merge_into_stacks(&mut self.items, &mut item) {
+            self.notify(ItemCollectionEvent::Update {
+                index,
+                item: Arc::new(self.items[index].clone()),
+            });
+        }
+
+        while item.get_count() > 0 {
+            if !self.items.iter().any(is_air) {
+                return AddResult::Partial(item);
+            }
+
+            let stack = item.take(item.get_max_stack_size());
+            // There is an empty slot, so this can't fail
+            let _ = self.place_stack(stack);
+        }
+
+        AddResult::Complete
+    }
+
+    fn get_space_for(&self, item: &ItemStack<THook>) -> u32 {
+        let empty_slots = self.items.iter().filter(|stack| is_air(stack)).count() as u32;
+
+        space_in_stacks(&self.items, item)
+            .saturating_add(empty_slots.saturating_mul(item.get_max_stack_size()))
+    }
+
+    fn split_stack(&mut self, index: usize, count: u32) -> ItemCollectionResult<usize> {
+        if !self.items.iter().any(is_air) {
+            return Err(ItemCollectionError::Full);
+        }
+
+        let stack = self.get_item_mut(index)?.split(count)?;
+        self.notify(ItemCollectionEvent::Update {
+            index,
+            item: Arc::new(self.items[index].clone()),
+        });
+
+        self.place_stack(stack)
+    }
 
     fn notify(&self, event: ItemCollectionEvent<THook>) {
         self.listeners.iter().for_each(|f| f(event.clone()));
@@ -373,11 +711,11 @@ impl<THook: EngineHook> ItemCollection<THook> for ItemCollectionSized<THook> {
         self.items.len()
     }
 
-    fn iter(&self) -> std::slice::Iter<ItemStack<THook>> {
+    fn iter(&self) -> std::slice::Iter<'_, ItemStack<THook>> {
         self.items.iter()
     }
 
-    fn iter_mut(&mut self) -> std::slice::IterMut<ItemStack<THook>> {
+    fn iter_mut(&mut self) -> std::slice::IterMut<'_, ItemStack<THook>> {
         self.items.iter_mut()
     }
 
@@ -493,17 +831,34 @@ impl IntoIterator for ItemRegistry {
 
 #[derive(Debug, Clone)]
 pub struct ItemStack<THook: EngineHook = DummyHook> {
-    item: Arc<dyn Item<THook>>,
+    item: ItemInstance<THook>,
     count: u32,
 }
 
 impl<THook: EngineHook> ItemStack<THook> {
     pub fn new(item: Arc<dyn Item<THook>>, count: u32) -> Self {
+        Self {
+            item: ItemInstance::new(item),
+            count,
+        }
+    }
+
+    pub fn from_instance(item: ItemInstance<THook>, count: u32) -> Self {
         Self { item, count }
     }
 
-    pub fn get_item(&self) -> Arc<dyn Item<THook>> {
-        self.item.clone()
+    /// Get the item instance shared by every item in this stack
+    pub fn get_item(&self) -> &ItemInstance<THook> {
+        &self.item
+    }
+
+    pub fn get_item_mut(&mut self) -> &mut ItemInstance<THook> {
+        &mut self.item
+    }
+
+    /// Get the registered prototype the items in this stack are instances of
+    pub fn get_prototype(&self) -> Arc<dyn Item<THook>> {
+        self.item.get_prototype()
     }
 
     pub fn get_count(&self) -> u32 {
@@ -518,8 +873,57 @@ impl<THook: EngineHook> ItemStack<THook> {
         self.count += count;
     }
 
+    /// Remove `count` items from the stack, the stack turns into air once it is empty
     pub fn decrement_count(&mut self, count: u32) {
+        if count >= self.count {
+            *self = Air::new_itemstack();
+        } else {
+            self.count -= count;
+        }
+    }
+
+    pub fn get_max_stack_size(&self) -> u32 {
+        self.item.get_max_stack_size().max(1)
+    }
+
+    /// Get how many more items fit in this stack
+    pub fn get_space(&self) -> u32 {
+        self.get_max_stack_size().saturating_sub(self.count)
+    }
+
+    /// Check if two stacks hold the same kind of item and can be merged
+    pub fn can_stack_with(&self, other: &ItemStack<THook>) -> bool {
+        let component_idents = |stack: &ItemStack<THook>| -> BTreeMap<String, String> {
+            stack
+                .get_components()
+                .into_iter()
+                .map(|(part, component)| (part, component.get_ident()))
+                .collect()
+        };
+
+        self.get_ident() == other.get_ident()
+            && self.item.get_data() == other.item.get_data()
+            && component_idents(self) == component_idents(other)
+    }
+
+    /// Split `count` items off into a new stack, at least one item has to stay in this stack
+    pub fn split(&mut self, count: u32) -> ItemCollectionResult<ItemStack<THook>> {
+        if count == 0 || count >= self.count {
+            return Err(ItemCollectionError::InvalidCount);
+        }
+
+        Ok(self.take(count))
+    }
+
+    /// Take up to `count` items off this stack, unlike `decrement_count` this can leave an empty stack
+    pub(crate) fn take(&mut self, count: u32) -> ItemStack<THook> {
+        let count = count.min(self.count);
         self.count -= count;
+
+        Self {
+            item: self.item.clone(),
+            count,
+        }
     }
 }
 
@@ -543,6 +947,35 @@ impl<THook: EngineHook> Item<THook> for ItemStack<THook> {
     fn get_texture(&self) -> ItemTexture {
         self.item.get_texture()
     }
+
+    fn get_max_stack_size(&self) -> u32 {
+        self.item.get_max_stack_size()
+    }
+
+    fn get_tags(&self) -> Vec<String> {
+        self.item.get_tags()
+    }
+
+    fn get_components(&self) -> HashMap<String, Arc<dyn Item<THook>>> {
+        self.item.get_components()
+    }
+
+    fn with_components(
+        &self,
+        components: HashMap<String, Arc<dyn Item<THook>>>,
+    ) -> Option<Arc<dyn Item<THook>>> {
+        self.item.with_components(components)
+    }
+
+    fn as_instance(&self) -> Option<&ItemInstance<THook>> {
+        Some(&self.item)
+    }
+}
+
+impl<THook: EngineHook> ItemMut for ItemStack<THook> {
+    fn get_stats_mut(&mut self) -> &mut dyn Stats {
+        self.item.get_stats_mut()
+    }
 }
 
 pub mod macros {
@@ -557,6 +990,8 @@ pub mod macros {
                 },
                 priority: 0,
                 modifier: $crate::attribute::AttributeModifier::Set($value),
+                condition: None,
+                lifetime: None,
             }
         };
     }
@@ -574,6 +1009,8 @@ pub mod macros {
                     reason: $crate::attribute::AttributeReason::Display(name),
                     priority: 0,
                     modifier: $crate::attribute::AttributeModifier::Set($value),
+                    condition: None,
+                    lifetime: None,
                 },
             );
         };
@@ -589,3 +1026,74 @@ pub mod macros {
         };
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude_items::{dev_tablet, rock};
+
+    use super::*;
+
+    fn rocks(count: u32) -> ItemStack {
+        ItemStack::new(Arc::new(rock()), count)
+    }
+
+    #[test]
+    fn add_merges_stacks() {
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(4);
+        inventory.add_item(rocks(3)).unwrap();
+        inventory.add_item(rocks(1)).unwrap();
+        inventory.add_item(rocks(2)).unwrap();
+
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 6);
+        assert!(inventory.get_item(1).is_err());
+    }
+
+    #[test]
+    fn partial_add_reports_leftover() {
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(2);
+        inventory
+            .add_item(ItemStack::new(Arc::new(dev_tablet()), 1))
+            .unwrap();
+
+        let result = inventory.add_item_partial(rocks(DEFAULT_MAX_STACK_SIZE + 5));
+        assert_eq!(result.get_leftover_count(), 5);
+        assert_eq!(
+            inventory.get_item(1).unwrap().get_count(),
+            DEFAULT_MAX_STACK_SIZE
+        );
+
+        // A full add is all or nothing
+        assert!(matches!(
+            inventory.add_item(rocks(1)),
+            Err(ItemCollectionError::Full)
+        ));
+    }
+
+    #[test]
+    fn split_and_merge() {
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(4);
+        inventory.add_item(rocks(5)).unwrap();
+
+        let index = inventory.split_stack(0, 2).unwrap();
+        assert_eq!(index, 1);
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 3);
+        assert_eq!(inventory.get_item(1).unwrap().get_count(), 2);
+
+        assert!(matches!(
+            inventory.split_stack(0, 3),
+            Err(ItemCollectionError::InvalidCount)
+        ));
+
+        inventory.merge(1, 0).unwrap();
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 5);
+        assert!(inventory.get_item(1).is_err());
+    }
+
+    #[test]
+    fn decrement_to_air() {
+        let mut stack = rocks(2);
+        stack.decrement_count(5);
+
+        assert_eq!(stack.get_ident(), "air");
+    }
+}
@@ -1,5 +1,15 @@
 pub mod attribute;
+pub mod crafting;
+pub mod data_item;
+pub mod encumbrance;
+pub mod equipment;
+pub mod hook;
+pub mod instance;
 pub mod item;
+pub mod pickup;
 #[cfg(feature = "prelude-items")]
 pub mod prelude_items;
-pub mod text_renderer;
\ No newline at end of file
+pub mod save;
+pub mod template;
+pub mod text_renderer;
+pub mod usage;
\ No newline at end of file
@@ -0,0 +1,105 @@
+use crate::item::{is_air, AddResult, DummyHook, EngineHook, ItemCollection, ItemStack};
+
+/// A stack lying in the world until it is picked up, possibly over several pickups
+#[derive(Debug, Clone)]
+pub struct DroppedStack<THook: EngineHook = DummyHook> {
+    /// `None` once everything was picked up
+    stack: Option<ItemStack<THook>>,
+}
+
+impl<THook: EngineHook> DroppedStack<THook> {
+    /// Drop a stack, air is never dropped and leaves nothing to pick up
+    pub fn new(stack: ItemStack<THook>) -> Self {
+        Self {
+            stack: (!is_air(&stack) && stack.get_count() > 0).then_some(stack),
+        }
+    }
+
+    /// Get what is left of the dropped stack
+    pub fn get_stack(&self) -> Option<&ItemStack<THook>> {
+        self.stack.as_ref()
+    }
+
+    pub fn is_picked_up(&self) -> bool {
+        self.stack.is_none()
+    }
+
+    /// Add as much of the stack to a collection as fits, returns true once nothing is left.
+    /// Merges into existing stacks first, like any other item added to the collection
+    pub fn pick_up(&mut self, collection: &mut impl ItemCollection<THook>) -> bool {
+        let Some(dropped) = self.stack.take() else {
+            return true;
+        };
+
+        match collection.add_item_partial(dropped) {
+            AddResult::Complete => true,
+            AddResult::Partial(leftover) => {
+                self.stack = Some(leftover);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        item::{Item, ItemCollectionSized},
+        prelude_items::{air::Air, dev_tablet, rock},
+    };
+
+    use super::*;
+
+    #[test]
+    fn air_is_not_dropped() {
+        let dropped = DroppedStack::<DummyHook>::new(Air::new_itemstack());
+
+        assert!(dropped.is_picked_up());
+        assert!(dropped.get_stack().is_none());
+    }
+
+    #[test]
+    fn pick_up_merges_into_stacks() {
+        let mut inventory = ItemCollectionSized::new(2);
+        inventory
+            .add_item(ItemStack::new(Arc::new(rock()), 3))
+            .unwrap();
+
+        let mut dropped = DroppedStack::new(ItemStack::new(Arc::new(rock()), 2));
+
+        assert!(dropped.pick_up(&mut inventory));
+        assert!(dropped.is_picked_up());
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 5);
+        assert!(inventory.get_item(1).is_err());
+
+        // Picking up again does nothing
+        assert!(dropped.pick_up(&mut inventory));
+        assert_eq!(inventory.get_item(0).unwrap().get_count(), 5);
+    }
+
+    #[test]
+    fn leftovers_stay_dropped() {
+        let tablet = Arc::new(dev_tablet());
+        let mut inventory = ItemCollectionSized::new(1);
+
+        let mut dropped = DroppedStack::new(ItemStack::new(tablet.clone(), 2));
+        assert_eq!(dropped.get_stack().unwrap().get_max_stack_size(), 1);
+
+        assert!(!dropped.pick_up(&mut inventory));
+        assert_eq!(dropped.get_stack().unwrap().get_count(), 1);
+        assert_eq!(
+            inventory.get_item(0).unwrap().get_ident(),
+            tablet.get_ident()
+        );
+
+        // A full inventory leaves the stack where it is
+        assert!(!dropped.pick_up(&mut inventory));
+        assert_eq!(dropped.get_stack().unwrap().get_count(), 1);
+
+        inventory.remove_item(0).unwrap();
+        assert!(dropped.pick_up(&mut inventory));
+        assert!(dropped.is_picked_up());
+    }
+}
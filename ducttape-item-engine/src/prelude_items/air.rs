@@ -49,6 +49,10 @@ impl<THook: EngineHook> Item<THook> for Air<THook> {
         // None
         super::AIR_TEXTURE.clone()
     }
+
+    fn get_max_stack_size(&self) -> u32 {
+        1
+    }
 }
 
 impl<THook: EngineHook> ItemMut for Air<THook> {
@@ -1,19 +1,33 @@
+use crate::{data_item::DataItem, template::source::FsAssetSource};
+
 pub mod air;
-pub mod rock;
 pub mod stats;
-pub mod dev_tablet;
 
 lazy_static::lazy_static! {
     pub static ref MISSING_TEXTURE: image::DynamicImage = image::open(asset_to_absolute("missing.png")).unwrap();
-    pub static ref ROCK_TEXTURE: crate::item::ItemTexture = image::open(asset_to_absolute("rock.png")).ok().into();
     pub static ref AIR_TEXTURE: crate::item::ItemTexture = image::open(asset_to_absolute("air.png")).ok().into();
-    pub static ref DEV_TABLET_TEXTURE: crate::item::ItemTexture = image::open(asset_to_absolute("tablet.png")).map_or(crate::item::ItemTexture::None, |atlas| (atlas, crate::item::FrameProperties {width: 32, height: 32, duration: 0.5}, crate::item::AnimationType::Loop).into());
+    /// The engine's own assets, the built-in data items load their textures from here
+    pub static ref PRELUDE_ASSETS: FsAssetSource = FsAssetSource::new(asset_to_absolute(""));
 }
 
 fn asset_to_absolute(asset_relative_path: &str) -> String {
     format!("{}/assets/{}", env!("CARGO_MANIFEST_DIR"), asset_relative_path)
 }
 
+fn prelude_item(path: &str, toml: &str) -> DataItem {
+    DataItem::from_toml(&*PRELUDE_ASSETS, path, toml).expect("Built-in item definition is invalid")
+}
+
+/// A plain rock, defined in `rock.toml`
+pub fn rock() -> DataItem {
+    prelude_item("rock.toml", include_str!("rock.toml"))
+}
+
+/// The developer tablet, defined in `dev_tablet.toml`
+pub fn dev_tablet() -> DataItem {
+    prelude_item("dev_tablet.toml", include_str!("dev_tablet.toml"))
+}
+
 #[cfg(test)]
 mod tests {
     use valence_text::IntoText;
@@ -86,30 +100,40 @@ mod tests {
                         reason: AttributeReason::Display("⚽".to_string()),
                         priority: 0,
                         modifier: AttributeModifier::Set(100.0),
+                        condition: None,
+                        lifetime: None,
                     },
                     Attribute {
                         uuid: uuid::Uuid::new_v4(),
                         reason: AttributeReason::Display("✖️".to_string()),
                         priority: 4,
                         modifier: AttributeModifier::Multiply(1.5),
+                        condition: None,
+                        lifetime: None,
                     },
                     Attribute {
                         uuid: uuid::Uuid::new_v4(),
                         reason: AttributeReason::Display("➕".to_string()),
                         priority: 2,
                         modifier: AttributeModifier::Add(10.0),
+                        condition: None,
+                        lifetime: None,
                     },
                     Attribute {
                         uuid: uuid::Uuid::new_v4(),
                         reason: AttributeReason::Display("➗".to_string()),
                         priority: 6,
                         modifier: AttributeModifier::Multiply(0.75),
+                        condition: None,
+                        lifetime: None,
                     },
                     Attribute {
                         uuid: uuid::Uuid::new_v4(),
                         reason: AttributeReason::Display("➖".to_string()),
                         priority: 8,
                         modifier: AttributeModifier::Add(-10.0),
+                        condition: None,
+                        lifetime: None,
                     },
                 ],
             );
@@ -170,6 +194,21 @@ mod tests {
         }
     }
 
+    #[test]
+    fn rock_attrs() {
+        let rock = super::rock();
+
+        let stats = rock.get_stats();
+        let parser = AttributeParser::from(stats.get_all_attributes());
+
+        let debug_txt = rock.get_name().into_text() + "\n---\n" + parser.clone();
+
+        // Print out the item info for debugging
+        println!("{}", debug_txt.to_ansi_string());
+
+        assert_eq!(parser.aggregate_to_value(AttributeType::Durability), 50.0);
+    }
+
     #[test]
     fn ball_item() {
         let ball: Ball = Ball::new();
@@ -182,6 +221,6 @@ mod tests {
 
         let total_durability = parser.aggregate_to_value(AttributeType::Durability);
 
-        assert_eq!(total_durability, 110.0);
+        assert_eq!(total_durability, 112.5);
     }
 }
@@ -5,7 +5,8 @@ use crate::{
     item::Stats,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(transparent)]
 pub struct BasicStats {
     attributes: HashMap<AttributeType, Vec<Attribute>>,
 }
@@ -68,6 +69,10 @@ impl BasicStats {
             attributes: HashMap::new(),
         }
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.attributes.values().all(|attributes| attributes.is_empty())
+    }
 }
 
 impl From<HashMap<AttributeType, Vec<Attribute>>> for BasicStats {
@@ -0,0 +1,414 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    instance::{ItemInstance, ItemInstanceData},
+    item::{
+        is_air, EngineHook, Item, ItemCollection, ItemCollectionError, ItemCollectionSized,
+        ItemCollectionUnsized, ItemRegistry, ItemStack,
+    },
+    prelude_items::air::Air,
+    template::source::AssetSource,
+};
+
+/// A single item in the save format, items are stored by their registry ident and
+/// only the data that makes the instance distinct from its registered prototype is kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedItem {
+    pub ident: String,
+    /// The components of a compound (template) item
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub components: BTreeMap<String, SavedItem>,
+    /// Instance data (attributes, damage, name and metadata) of the item
+    #[serde(flatten)]
+    pub instance: ItemInstanceData,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedItemStack {
+    #[serde(flatten)]
+    pub item: SavedItem,
+    pub count: u32,
+}
+
+/// A saved item collection, empty (air) slots are stored as `None` so slot positions are kept
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedItemCollection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub size: Option<usize>,
+    pub items: Vec<Option<SavedItemStack>>,
+}
+
+/// Idents renamed in a version of a save format, applied to saves made before that version
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SaveMigration {
+    pub version: u32,
+    /// Old ident to new ident
+    #[serde(default)]
+    pub renamed: BTreeMap<String, String>,
+}
+
+/// The contents of a migration file, one `[[migration]]` table per version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SaveMigrationFile {
+    #[serde(default)]
+    pub migration: Vec<SaveMigration>,
+}
+
+impl SaveMigrationFile {
+    pub fn load_toml(toml: &str) -> Result<Self, toml::de::Error> {
+        toml::from_str(toml)
+    }
+
+    pub fn load(source: &dyn AssetSource, path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::load_toml(&source.read_to_string(path)?)?)
+    }
+}
+
+/// Combine the renames of every migration newer than `version`, in version order,
+/// so an ident renamed twice maps straight to its newest name
+pub fn get_renames(migrations: &[SaveMigration], version: u32) -> BTreeMap<String, String> {
+    let mut migrations: Vec<&SaveMigration> = migrations
+        .iter()
+        .filter(|migration| migration.version > version)
+        .collect();
+    migrations.sort_by_key(|migration| migration.version);
+
+    let mut renames: BTreeMap<String, String> = BTreeMap::new();
+    for migration in migrations {
+        for new in renames.values_mut() {
+            if let Some(newer) = migration.renamed.get(new) {
+                *new = newer.clone();
+            }
+        }
+
+        for (old, new) in migration.renamed.iter() {
+            renames.entry(old.clone()).or_insert_with(|| new.clone());
+        }
+    }
+
+    renames
+}
+
+impl SavedItem {
+    /// Replace renamed idents, including the idents of components
+    pub fn rename_idents(&mut self, renames: &BTreeMap<String, String>) {
+        if let Some(new) = renames.get(&self.ident) {
+            self.ident = new.clone();
+        }
+
+        for component in self.components.values_mut() {
+            component.rename_idents(renames);
+        }
+    }
+}
+
+impl SavedItemStack {
+    pub fn rename_idents(&mut self, renames: &BTreeMap<String, String>) {
+        self.item.rename_idents(renames);
+    }
+}
+
+impl SavedItemCollection {
+    pub fn rename_idents(&mut self, renames: &BTreeMap<String, String>) {
+        for item in self.items.iter_mut().flatten() {
+            item.rename_idents(renames);
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    /// The item ident is not in the item registry.
+    UnknownItem(String),
+    /// The saved item has components but the registered item is not a compound item.
+    NotCompound(String),
+    /// The saved collection doesn't fit in the collection it is loaded into.
+    Collection(ItemCollectionError),
+}
+
+impl std::fmt::Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveError::UnknownItem(ident) => write!(f, "Unknown item: {}", ident),
+            SaveError::NotCompound(ident) => write!(f, "Item has no components: {}", ident),
+            SaveError::Collection(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<ItemCollectionError> for SaveError {
+    fn from(e: ItemCollectionError) -> Self {
+        SaveError::Collection(e)
+    }
+}
+
+pub type SaveResult<T> = Result<T, SaveError>;
+
+impl<THook: EngineHook> ItemRegistry<THook> {
+    pub fn save_item(&self, item: &dyn Item<THook>) -> SavedItem {
+        let instance = item
+            .as_instance()
+            .map(|instance| instance.get_data().clone())
+            .unwrap_or_default();
+
+        let components = item
+            .get_components()
+            .into_iter()
+            .map(|(part, component)| (part, self.save_item(component.as_ref())))
+            .collect();
+
+        SavedItem {
+            ident: item.get_ident(),
+            components,
+            instance,
+        }
+    }
+
+    /// Load an item as a shared item, instance data is kept by wrapping the item in an instance
+    pub fn load_item(&self, saved: &SavedItem) -> SaveResult<Arc<dyn Item<THook>>> {
+        let prototype = self.load_prototype(saved)?;
+
+        if saved.instance.is_empty() {
+            Ok(prototype)
+        } else {
+            Ok(Arc::new(ItemInstance::with_data(
+                prototype,
+                saved.instance.clone(),
+            )))
+        }
+    }
+
+    pub fn load_instance(&self, saved: &SavedItem) -> SaveResult<ItemInstance<THook>> {
+        Ok(ItemInstance::with_data(
+            self.load_prototype(saved)?,
+            saved.instance.clone(),
+        ))
+    }
+
+    /// Get the registered item for a saved item, rebuilt from the saved components if it has any
+    fn load_prototype(&self, saved: &SavedItem) -> SaveResult<Arc<dyn Item<THook>>> {
+        let prototype = self
+            .get(&saved.ident)
+            .ok_or_else(|| SaveError::UnknownItem(saved.ident.clone()))?;
+
+        if saved.components.is_empty() {
+            return Ok(prototype.clone());
+        }
+
+        let components = saved
+            .components
+            .iter()
+            .map(|(part, component)| Ok((part.clone(), self.load_item(component)?)))
+            .collect::<SaveResult<HashMap<_, _>>>()?;
+
+        prototype
+            .with_components(components)
+            .ok_or_else(|| SaveError::NotCompound(saved.ident.clone()))
+    }
+
+    pub fn save_stack(&self, stack: &ItemStack<THook>) -> SavedItemStack {
+        SavedItemStack {
+            item: self.save_item(stack.get_item()),
+            count: stack.get_count(),
+        }
+    }
+
+    pub fn load_stack(&self, saved: &SavedItemStack) -> SaveResult<ItemStack<THook>> {
+        Ok(ItemStack::from_instance(
+            self.load_instance(&saved.item)?,
+            saved.count,
+        ))
+    }
+
+    fn save_stacks<'a>(
+        &self,
+        items: impl Iterator<Item = &'a ItemStack<THook>>,
+    ) -> Vec<Option<SavedItemStack>> {
+        items
+            .map(|item| (!is_air(item)).then(|| self.save_stack(item)))
+            .collect()
+    }
+
+    fn load_stacks(&self, saved: &[Option<SavedItemStack>]) -> SaveResult<Vec<ItemStack<THook>>> {
+        saved
+            .iter()
+            .map(|item| match item {
+                Some(item) => self.load_stack(item),
+                None => Ok(Air::new_itemstack()),
+            })
+            .collect()
+    }
+
+    pub fn save_collection_sized(
+        &self,
+        collection: &ItemCollectionSized<THook>,
+    ) -> SavedItemCollection {
+        SavedItemCollection {
+            size: Some(collection.get_size()),
+            items: self.save_stacks(collection.iter()),
+        }
+    }
+
+    /// Load a sized collection, if the save doesn't store a size the collection is made just big enough
+    pub fn load_collection_sized(
+        &self,
+        saved: &SavedItemCollection,
+    ) -> SaveResult<ItemCollectionSized<THook>> {
+        let size = saved.size.unwrap_or(saved.items.len());
+        if saved.items.len() > size {
+            return Err(ItemCollectionError::Full.into());
+        }
+
+        let mut collection = ItemCollectionSized::new(size);
+        for (slot, item) in collection
+            .get_items_mut()
+            .iter_mut()
+            .zip(self.load_stacks(&saved.items)?)
+        {
+            *slot = item;
+        }
+
+        Ok(collection)
+    }
+
+    pub fn save_collection_unsized(
+        &self,
+        collection: &ItemCollectionUnsized<THook>,
+    ) -> SavedItemCollection {
+        SavedItemCollection {
+            size: None,
+            items: self.save_stacks(collection.iter()),
+        }
+    }
+
+    pub fn load_collection_unsized(
+        &self,
+        saved: &SavedItemCollection,
+    ) -> SaveResult<ItemCollectionUnsized<THook>> {
+        let mut collection = ItemCollectionUnsized::new();
+        *collection.get_items_mut() = self.load_stacks(&saved.items)?;
+
+        Ok(collection)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        attribute::{Attribute, AttributeModifier, AttributeParser, AttributeReason, AttributeType},
+        item::{DummyHook, ItemMut},
+        prelude_items::rock,
+    };
+
+    use super::*;
+
+    fn registry() -> ItemRegistry<DummyHook> {
+        let mut registry = ItemRegistry::new();
+        registry.register("rock".to_owned(), Arc::new(rock()));
+        registry
+    }
+
+    #[test]
+    fn collection_round_trip() {
+        let registry = registry();
+        let rock = registry.get("rock").unwrap().clone();
+
+        let mut chipped_rock = ItemStack::new(rock.clone(), 1);
+        chipped_rock.get_item_mut().damage(10.0);
+        chipped_rock.get_stats_mut().push_attribute(
+            AttributeType::Sharpness,
+            Attribute {
+                uuid: uuid::Uuid::new_v4(),
+                reason: AttributeReason::Display("🔨".to_owned()),
+                priority: 1,
+                modifier: AttributeModifier::Add(1.0),
+                condition: None,
+                lifetime: None,
+            },
+        );
+
+        let mut inventory: ItemCollectionSized = ItemCollectionSized::new(4);
+        inventory.add_item(ItemStack::new(rock, 3)).unwrap();
+        inventory.add_item(chipped_rock).unwrap();
+
+        let json = serde_json::to_string(&registry.save_collection_sized(&inventory)).unwrap();
+        let saved: SavedItemCollection = serde_json::from_str(&json).unwrap();
+        let loaded = registry.load_collection_sized(&saved).unwrap();
+
+        assert_eq!(loaded.get_size(), 4);
+        assert_eq!(loaded.get_item(0).unwrap().get_count(), 3);
+        assert!(loaded.get_item(2).is_err());
+
+        let stats = loaded.get_item(1).unwrap().get_stats();
+        let parser = AttributeParser::from(stats.get_all_attributes());
+        assert_eq!(parser.aggregate_to_value(AttributeType::Durability), 40.0);
+        assert_eq!(parser.aggregate_to_value(AttributeType::Sharpness), 3.0);
+    }
+
+    #[test]
+    fn migrate_renamed_idents() {
+        let migrations = [
+            SaveMigration {
+                version: 3,
+                renamed: BTreeMap::from([("stone".to_owned(), "rock".to_owned())]),
+            },
+            SaveMigration {
+                version: 2,
+                renamed: BTreeMap::from([("pebble".to_owned(), "stone".to_owned())]),
+            },
+        ];
+
+        let renames = get_renames(&migrations, 1);
+        assert_eq!(renames.get("pebble").unwrap(), "rock");
+        assert_eq!(renames.get("stone").unwrap(), "rock");
+        assert!(get_renames(&migrations, 3).is_empty());
+
+        let saved_item = |ident: &str| SavedItem {
+            ident: ident.to_owned(),
+            components: BTreeMap::new(),
+            instance: ItemInstanceData::default(),
+        };
+        let mut saved = SavedItemCollection {
+            size: Some(2),
+            items: vec![
+                Some(SavedItemStack {
+                    item: SavedItem {
+                        components: BTreeMap::from([("tip".to_owned(), saved_item("pebble"))]),
+                        ..saved_item("pebble")
+                    },
+                    count: 2,
+                }),
+                None,
+            ],
+        };
+        saved.rename_idents(&renames);
+
+        let item = &saved.items[0].as_ref().unwrap().item;
+        assert_eq!(item.ident, "rock");
+        assert_eq!(item.components["tip"].ident, "rock");
+    }
+
+    #[test]
+    fn unknown_item() {
+        let saved = SavedItemStack {
+            item: SavedItem {
+                ident: "ducttape".to_owned(),
+                components: BTreeMap::new(),
+                instance: ItemInstanceData::default(),
+            },
+            count: 1,
+        };
+
+        assert!(matches!(
+            registry().load_stack(&saved),
+            Err(SaveError::UnknownItem(_))
+        ));
+    }
+}
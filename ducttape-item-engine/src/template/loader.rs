@@ -0,0 +1,748 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Debug,
+    sync::Arc,
+};
+
+use hex_color::HexColor;
+use image::{GenericImage, GenericImageView as _};
+use ndarray::Array2;
+use serde::Deserialize;
+use uuid::Uuid;
+
+use crate::{
+    attribute::{
+        condition::{AttributeCondition, AttributeLifetime},
+        Attribute, AttributeModifier, AttributeParser, AttributeReason, AttributeType,
+    },
+    item::{EngineHook, Item, ItemTexture, SpecialAbility, Stats},
+    prelude_items::stats::BasicStatsBuilder,
+};
+
+use super::source::AssetSource;
+
+#[derive(Debug, Deserialize)]
+pub struct SerializableAttribute {
+    pub priority: u8,
+    pub reason: AttributeReason,
+    pub modifier: AttributeModifier,
+    #[serde(default)]
+    pub condition: Option<AttributeCondition>,
+    #[serde(default)]
+    pub lifetime: Option<AttributeLifetime>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum AttributeStrategy {
+    // Sum the attributes of each of the components
+    #[default]
+    Sum,
+    // Take the mean of the attributes of each of the components
+    Average,
+    /// Push all the component attributes to the item handler's attribute handler method
+    Manual,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AttributeTypeEntry {
+    pub strategy: AttributeStrategy,
+    pub attr: HashMap<Uuid, SerializableAttribute>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ItemTemplateData {
+    pub data_name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub attribute: HashMap<AttributeType, AttributeTypeEntry>,
+    pub components: HashMap<String, HexColor>,
+    pub fallback: HashMap<String, String>,
+}
+
+pub struct Mask(Array2<bool>);
+
+pub struct MaskedImage {
+    // The mask is a 2D array of booleans, where true means the pixel is part of the component
+    // mask: Vec<Vec<bool>>,
+    mask: Mask,
+
+    // The source image of the component
+    image: image::DynamicImage,
+}
+
+impl MaskedImage {
+    pub fn new(mask: Mask, image: image::DynamicImage) -> Self {
+        Self { mask, image }
+    }
+
+    pub fn new_from_template(
+        template: &ItemTemplate,
+        component: &str,
+        item: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let source = &template.source;
+
+        let image_path = format!("{}/{}-{}.png", template.folder, component, item);
+        let fallback_path = template.fallback.get(component).ok_or("Missing Fallback")?;
+
+        let image_data = source
+            .read(&image_path)
+            .or_else(|_| source.read(fallback_path))?;
+
+        Self::from_component_image(template, component, image::load_from_memory(&image_data)?)
+    }
+
+    /// Mask the fallback image of a component
+    pub fn new_from_fallback(
+        template: &ItemTemplate,
+        component: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let fallback_path = template.fallback.get(component).ok_or("Missing Fallback")?;
+        let image = image::load_from_memory(&template.source.read(fallback_path)?)?;
+
+        Self::from_component_image(template, component, image)
+    }
+
+    fn from_component_image(
+        template: &ItemTemplate,
+        component: &str,
+        image: image::DynamicImage,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let mask_image = template.load_mask()?;
+
+        // The mask is indexed with the image's size, so a smaller image would be read out of bounds
+        if mask_image.dimensions() != image.dimensions() {
+            return Err(format!(
+                "The {} image is {}x{}, the mask is {}x{}",
+                component,
+                image.width(),
+                image.height(),
+                mask_image.width(),
+                mask_image.height()
+            )
+            .into());
+        }
+
+        let (width, height) = (image.width() as usize, image.height() as usize);
+
+        let mask_data = mask_image.pixels().map(|(x, y, pixel)| {
+            let color = HexColor::rgba(pixel[0], pixel[1], pixel[2], pixel[3]);
+            let is_masked = template.components.get(component) == Some(&color);
+            ((y as usize, x as usize), is_masked)
+        });
+
+        let mut mask_array = Array2::default((height, width));
+        for ((y, x), is_masked) in mask_data {
+            mask_array[[y, x]] = is_masked;
+        }
+
+        Ok(Self::new(Mask(mask_array), image))
+    }
+
+    pub fn get_mask(&self) -> &Mask {
+        &self.mask
+    }
+
+    pub fn get_image(&self) -> &image::DynamicImage {
+        &self.image
+    }
+
+    pub fn get_masked_image(&self) -> image::DynamicImage {
+        let mut masked_image = self.image.clone();
+
+        let mask = &self.mask.0;
+
+        for (y, row) in mask.outer_iter().enumerate() {
+            for (x, is_masked) in row.iter().enumerate() {
+                if !is_masked {
+                    masked_image.put_pixel(x as u32, y as u32, image::Rgba([0, 0, 0, 0]));
+                }
+            }
+        }
+
+        masked_image
+    }
+}
+
+pub struct TemplateComponentRenderer {
+    size: (u32, u32),
+    component_map: Vec<MaskedImage>,
+}
+
+impl TemplateComponentRenderer {
+    pub fn new(size: (u32, u32)) -> Self {
+        Self {
+            component_map: Vec::new(),
+            size,
+        }
+    }
+
+    pub fn push(&mut self, masked_image: MaskedImage) {
+        self.component_map.push(masked_image);
+    }
+
+    pub fn render(&self) -> ItemTexture {
+        let mut image = image::DynamicImage::new_rgba8(self.size.0, self.size.1);
+
+        for masked_image in &self.component_map {
+            let mask = masked_image.get_mask();
+            let img = masked_image.get_image();
+
+            for (y, row) in mask.0.outer_iter().enumerate() {
+                for (x, is_masked) in row.iter().enumerate() {
+                    if *is_masked {
+                        let pixel = img.get_pixel(x as u32, y as u32);
+                        image.put_pixel(x as u32, y as u32, pixel);
+                    }
+                }
+            }
+        }
+
+        image.into()
+    }
+}
+
+pub trait TemplateHandler: Debug + Send + Sync {
+    /// Handle the attributes of each of the component items
+    fn attribute_handler(
+        &self,
+        base_attributes: HashMap<AttributeType, Vec<Attribute>>,
+        components: HashMap<String, HashMap<AttributeType, Vec<Attribute>>>,
+    ) -> HashMap<AttributeType, Vec<Attribute>> {
+        let mut attributes = base_attributes;
+
+        for (_, component_attributes) in components {
+            for (at, component_attribute) in component_attributes {
+                attributes
+                    .entry(at)
+                    .or_default()
+                    .extend(component_attribute);
+            }
+        }
+
+        attributes
+    }
+}
+
+/// Handler used by templates that don't need any custom `Manual` attribute logic
+#[derive(Debug, Clone, Default)]
+pub struct DefaultTemplateHandler;
+
+impl TemplateHandler for DefaultTemplateHandler {}
+
+#[derive(Debug, Clone)]
+pub struct TemplateItem<THook: EngineHook> {
+    template: ItemTemplate,
+    /// Set by `with_derived_ident`, otherwise the ident is the template's data name
+    ident: Option<String>,
+    components: HashMap<String, Arc<dyn Item<THook>>>,
+    special_abilities: Vec<Box<dyn SpecialAbility<THook>>>,
+    handler: Arc<dyn TemplateHandler>,
+}
+
+impl<THook: EngineHook> TemplateItem<THook> {
+    pub fn new(template: ItemTemplate) -> Self {
+        Self {
+            template,
+            ident: None,
+            components: HashMap::new(),
+            special_abilities: Vec::new(),
+            handler: Arc::new(DefaultTemplateHandler),
+        }
+    }
+
+    /// Set the handler that receives the attributes of every `Manual` attribute type
+    pub fn with_handler(mut self, handler: Arc<dyn TemplateHandler>) -> Self {
+        self.handler = handler;
+        self
+    }
+
+    /// Identify the item by its components, e.g. `spear[shaft=stick,tip=rock]`, for items that are
+    /// registered once per combination of components. Components added later aren't included
+    pub fn with_derived_ident(mut self) -> Self {
+        let idents = self
+            .components
+            .iter()
+            .map(|(part, item)| (part.clone(), item.get_ident()))
+            .collect();
+
+        self.ident = Some(self.template.derived_ident(&idents));
+        self
+    }
+
+    pub fn add_component(&mut self, part: String, component: Arc<dyn Item<THook>>) {
+        self.components.insert(part, component);
+    }
+
+    /// Components sorted by part name, so the generated stats have a stable order
+    fn sorted_components(&self) -> Vec<(&String, &Arc<dyn Item<THook>>)> {
+        let mut components: Vec<_> = self.components.iter().collect();
+        components.sort_by_key(|(part, _)| *part);
+        components
+    }
+
+    /// Component attributes come after every base attribute of the template
+    fn component_priority(&self) -> u8 {
+        self.template
+            .attribute
+            .values()
+            .flatten()
+            .map(|attribute| attribute.priority)
+            .max()
+            .map_or(0, |priority| priority.saturating_add(1))
+    }
+}
+
+impl<THook: EngineHook> Item<THook> for TemplateItem<THook> {
+    fn get_name(&self) -> String {
+        self.template.data_name.clone()
+    }
+
+    fn get_ident(&self) -> String {
+        self.ident
+            .clone()
+            .unwrap_or_else(|| self.template.data_name.clone())
+    }
+
+    fn get_stats(&self) -> Box<dyn Stats> {
+        let components = self.sorted_components();
+        let component_attributes: Vec<_> = components
+            .iter()
+            .map(|(part, item)| {
+                (
+                    *part,
+                    item.get_name(),
+                    item.get_stats().get_all_attributes(),
+                )
+            })
+            .collect();
+
+        let mut attribute_types: HashSet<AttributeType> =
+            self.template.attribute.keys().copied().collect();
+        attribute_types.extend(
+            component_attributes
+                .iter()
+                .flat_map(|(_, _, attributes)| attributes.keys().copied()),
+        );
+
+        let priority = self.component_priority();
+
+        let mut stats = BasicStatsBuilder::new();
+        let mut manual_base = HashMap::new();
+        let mut manual_components: HashMap<String, HashMap<AttributeType, Vec<Attribute>>> =
+            HashMap::new();
+
+        for at in attribute_types {
+            let base = self.template.attribute.get(&at).cloned().unwrap_or_default();
+
+            // The aggregated value of every component that has this attribute type
+            let values: Vec<(&String, &String, f64)> = component_attributes
+                .iter()
+                .filter(|(_, _, attributes)| attributes.contains_key(&at))
+                .map(|(part, name, attributes)| {
+                    (
+                        *part,
+                        name,
+                        AttributeParser::from(attributes.clone()).aggregate_to_value(at),
+                    )
+                })
+                .collect();
+
+            match self.template.get_strategy(at) {
+                AttributeStrategy::Sum => {
+                    stats = stats.with_attribute_vec(at, base);
+
+                    for (part, name, value) in values {
+                        stats = stats.with_attribute(
+                            at,
+                            Attribute {
+                                uuid: Attribute::stable_uuid(&[
+                                    &self.template.data_name,
+                                    at.get_id(),
+                                    part,
+                                ]),
+                                reason: AttributeReason::Display(name.clone()),
+                                priority,
+                                modifier: AttributeModifier::Add(value),
+                                condition: None,
+                                lifetime: None,
+                            },
+                        );
+                    }
+                }
+                AttributeStrategy::Average => {
+                    stats = stats.with_attribute_vec(at, base);
+
+                    if !values.is_empty() {
+                        let mean = values.iter().map(|(_, _, value)| value).sum::<f64>()
+                            / values.len() as f64;
+                        let names = values
+                            .iter()
+                            .map(|(_, name, _)| name.as_str())
+                            .collect::<Vec<_>>()
+                            .join(", ");
+
+                        stats = stats.with_attribute(
+                            at,
+                            Attribute {
+                                uuid: Attribute::stable_uuid(&[
+                                    &self.template.data_name,
+                                    at.get_id(),
+                                    "average",
+                                ]),
+                                reason: AttributeReason::Display(names),
+                                priority,
+                                modifier: AttributeModifier::Add(mean),
+                                condition: None,
+                                lifetime: None,
+                            },
+                        );
+                    }
+                }
+                AttributeStrategy::Manual => {
+                    manual_base.insert(at, base);
+
+                    for (part, _, attributes) in &component_attributes {
+                        if let Some(attributes) = attributes.get(&at) {
+                            manual_components
+                                .entry((*part).clone())
+                                .or_default()
+                                .insert(at, attributes.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        if !manual_base.is_empty() {
+            stats = stats.with_attribute_vecs(
+                self.handler
+                    .attribute_handler(manual_base, manual_components),
+            );
+        }
+
+        Box::new(stats.build())
+    }
+
+    fn special_abilities(&self) -> Vec<Box<dyn SpecialAbility<THook>>> {
+        self.special_abilities.clone()
+    }
+
+    fn get_texture(&self) -> ItemTexture {
+        let Ok(mask) = self.template.load_mask() else {
+            return ItemTexture::None;
+        };
+        let mut renderer = TemplateComponentRenderer::new(mask.dimensions());
+
+        for (component, item) in &self.components {
+            let _ = MaskedImage::new_from_template(&self.template, component, &item.get_ident())
+                .map(|masked_image| {
+                    renderer.push(masked_image);
+                });
+        }
+
+        renderer.render()
+    }
+
+    fn get_max_stack_size(&self) -> u32 {
+        // Every template item is crafted separately, so they don't stack
+        1
+    }
+
+    fn get_tags(&self) -> Vec<String> {
+        self.template.tags.clone()
+    }
+
+    fn get_components(&self) -> HashMap<String, Arc<dyn Item<THook>>> {
+        self.components.clone()
+    }
+
+    fn with_components(
+        &self,
+        components: HashMap<String, Arc<dyn Item<THook>>>,
+    ) -> Option<Arc<dyn Item<THook>>> {
+        let mut item = self
+            .template
+            .populate_template(components)
+            .with_handler(self.handler.clone());
+        item.special_abilities = self.special_abilities.clone();
+        if self.ident.is_some() {
+            item = item.with_derived_ident();
+        }
+
+        Some(Arc::new(item))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ItemTemplate {
+    // Where the template's assets are read from, laid out as {template_name}/{component_name}-{item_name}.png
+    source: Arc<dyn AssetSource>,
+    folder: String,
+    data_name: String,
+    tags: Vec<String>,
+    attribute: HashMap<AttributeType, Vec<Attribute>>,
+    strategy: HashMap<AttributeType, AttributeStrategy>,
+    components: HashMap<String, HexColor>,
+    fallback: HashMap<String, String>,
+}
+
+impl ItemTemplate {
+    pub fn get_data_name(&self) -> &str {
+        &self.data_name
+    }
+
+    pub fn get_tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Get the folder of the template, relative to the root of its asset source
+    pub fn get_folder(&self) -> &str {
+        &self.folder
+    }
+
+    pub fn get_source(&self) -> Arc<dyn AssetSource> {
+        self.source.clone()
+    }
+
+    /// Get the names of the components this template is built from, sorted by name
+    pub fn get_component_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.components.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    pub fn get_attributes(&self) -> &HashMap<AttributeType, Vec<Attribute>> {
+        &self.attribute
+    }
+
+    /// Get the color of a component in the template's mask
+    pub fn get_component_color(&self, component: &str) -> Option<HexColor> {
+        self.components.get(component).copied()
+    }
+
+    /// Get the path of a component's fallback image, relative to the root of the asset source
+    pub fn get_fallback_path(&self, component: &str) -> Option<&str> {
+        self.fallback.get(component).map(String::as_str)
+    }
+
+    /// Load the `template.png` mask, every component is painted in its own color
+    pub fn load_mask(&self) -> Result<image::DynamicImage, Box<dyn std::error::Error>> {
+        let data = self.source.read(&format!("{}/template.png", self.folder))?;
+        Ok(image::load_from_memory(&data)?)
+    }
+
+    /// Composite the texture of the template at the size of its mask. Components are drawn
+    /// from the image of their assigned item ident, components without one from their fallback
+    pub fn render(
+        &self,
+        items: &HashMap<String, String>,
+    ) -> Result<ItemTexture, Box<dyn std::error::Error>> {
+        let mask = self.load_mask()?;
+        let mut renderer = TemplateComponentRenderer::new(mask.dimensions());
+
+        for component in self.get_component_names() {
+            renderer.push(match items.get(&component) {
+                Some(item) => MaskedImage::new_from_template(self, &component, item)?,
+                None => MaskedImage::new_from_fallback(self, &component)?,
+            });
+        }
+
+        Ok(renderer.render())
+    }
+
+    /// The ident of the item made from the given component item idents, e.g.
+    /// `spear[shaft=stick,tip=rock]`
+    pub fn derived_ident(&self, components: &BTreeMap<String, String>) -> String {
+        let parts: Vec<String> = components
+            .iter()
+            .map(|(component, item)| format!("{}={}", component, item))
+            .collect();
+
+        format!("{}[{}]", self.data_name, parts.join(","))
+    }
+
+    /// Get the strategy used to combine component attributes, attribute types the template doesn't declare are summed
+    pub fn get_strategy(&self, at: AttributeType) -> AttributeStrategy {
+        self.strategy.get(&at).copied().unwrap_or_default()
+    }
+
+    pub fn populate_template<THook: EngineHook>(
+        &self,
+        components: HashMap<String, Arc<dyn Item<THook>>>,
+    ) -> TemplateItem<THook> {
+        let mut item: TemplateItem<THook> = TemplateItem::new(self.clone());
+
+        for (component, component_item) in components {
+            item.add_component(component, component_item);
+        }
+
+        item
+    }
+
+    pub fn load_template(
+        source: Arc<dyn AssetSource>,
+        name: &str,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let folder = name.to_owned();
+
+        let template: ItemTemplateData =
+            toml::from_str(&source.read_to_string(&format!("{}/template.toml", folder))?)?;
+
+        let strategy_map = template
+            .attribute
+            .iter()
+            .map(|(at, entry)| (*at, entry.strategy))
+            .collect();
+
+        let attribute_map = template
+            .attribute
+            .into_iter()
+            .map(|(at, entry)| {
+                let attributes = entry
+                    .attr
+                    .into_iter()
+                    .map(|(id, attr)| Attribute {
+                        uuid: id,
+                        reason: attr.reason,
+                        modifier: attr.modifier,
+                        priority: attr.priority,
+                        condition: attr.condition,
+                        lifetime: attr.lifetime,
+                    })
+                    .collect();
+                (at, attributes)
+            })
+            .collect();
+
+        Ok(Self {
+            fallback: template
+                .fallback
+                .into_iter()
+                .map(|(k, v)| (k.clone(), format!("{}/{}-{}.png", folder, k, v)))
+                .collect(),
+            source,
+            folder,
+            data_name: template.data_name,
+            tags: template.tags,
+            attribute: attribute_map,
+            strategy: strategy_map,
+            components: template.components,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, GenericImageView as _, Rgba};
+    use maplit::hashmap;
+
+    use crate::{
+        item::DummyHook,
+        prelude_items::rock,
+        template::source::{FsAssetSource, MemoryAssetSource},
+    };
+
+    use super::*;
+
+    fn png(pixels: &[[u8; 4]]) -> Vec<u8> {
+        let mut image = DynamicImage::new_rgba8(pixels.len() as u32, 1);
+        for (x, pixel) in pixels.iter().enumerate() {
+            image.put_pixel(x as u32, 0, Rgba(*pixel));
+        }
+
+        let mut buf = Cursor::new(Vec::new());
+        image
+            .write_to(&mut buf, image::ImageFormat::Png)
+            .expect("Failed to write image to buffer");
+        buf.into_inner()
+    }
+
+    fn rock_components() -> HashMap<String, Arc<dyn Item<DummyHook>>> {
+        hashmap! {
+            "tip".to_owned() => Arc::new(rock()) as Arc<dyn Item<DummyHook>>,
+            "shaft".to_owned() => Arc::new(rock()) as Arc<dyn Item<DummyHook>>,
+        }
+    }
+
+    #[test]
+    fn spear_from_fs() {
+        let source = FsAssetSource::new(format!(
+            "{}/../ducttape-godot/assets/item/generated",
+            env!("CARGO_MANIFEST_DIR")
+        ));
+
+        let template = ItemTemplate::load_template(Arc::new(source), "spear").unwrap();
+        let spear = template.populate_template(rock_components());
+
+        let parser = AttributeParser::from(spear.get_stats().get_all_attributes());
+
+        // 5 from the spear and 2 from each rock
+        assert_eq!(parser.aggregate_to_value(AttributeType::Sharpness), 9.0);
+        // 50 from the spear and the mean of both rocks
+        assert_eq!(parser.aggregate_to_value(AttributeType::Strength), 60.0);
+
+        // Generated attributes keep their uuids between calls
+        let stats = spear.get_stats().get_all_attributes();
+        assert_eq!(stats, spear.get_stats().get_all_attributes());
+
+        let rock_name = Item::<DummyHook>::get_name(&rock());
+        assert!(stats[&AttributeType::Strength]
+            .iter()
+            .any(|attribute| attribute.reason
+                == AttributeReason::Display(format!("{}, {}", rock_name, rock_name))));
+
+        assert!(matches!(spear.get_texture(), ItemTexture::Image(_)));
+    }
+
+    #[test]
+    fn stick_from_memory() {
+        const RED: [u8; 4] = [255, 0, 0, 255];
+        const GREEN: [u8; 4] = [0, 255, 0, 255];
+        const GRAY: [u8; 4] = [128, 128, 128, 255];
+        const BROWN: [u8; 4] = [128, 64, 0, 255];
+
+        let source = MemoryAssetSource::new()
+            .with_file(
+                "stick/template.toml",
+                r##"
+                data_name = "stick"
+
+                [attribute.Sharpness]
+                strategy = "Average"
+
+                [attribute.Sharpness.attr.'9c1ba4b5-1d4d-4bcb-8d7e-5a6f2a1f4e0b']
+                priority = 1
+                reason = "🪵"
+                modifier = { "Set" = 1 }
+
+                [components]
+                tip = "#ff0000"
+                shaft = "#00ff00"
+
+                [fallback]
+                tip = "rock"
+                shaft = "rock"
+                "##,
+            )
+            .with_file("stick/template.png", png(&[RED, GREEN]))
+            .with_file("stick/tip-rock.png", png(&[GRAY, GRAY]))
+            .with_file("stick/shaft-rock.png", png(&[BROWN, BROWN]));
+
+        let template = ItemTemplate::load_template(Arc::new(source), "stick").unwrap();
+        let stick = template.populate_template(rock_components());
+
+        let parser = AttributeParser::from(stick.get_stats().get_all_attributes());
+        assert_eq!(parser.aggregate_to_value(AttributeType::Sharpness), 3.0);
+
+        let ItemTexture::Image(image) = stick.get_texture() else {
+            panic!("Expected the stick to render to an image");
+        };
+
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0), Rgba(GRAY));
+        assert_eq!(image.get_pixel(1, 0), Rgba(BROWN));
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Debug,
+    fs,
+    io::{Error, ErrorKind},
+    path::PathBuf,
+};
+
+/// A source of template assets, paths are always relative to the root of the source
+/// and use `/` as a separator (e.g. `spear/template.toml`)
+pub trait AssetSource: Debug + Send + Sync {
+    /// Read the raw bytes of an asset
+    fn read(&self, path: &str) -> std::io::Result<Vec<u8>>;
+
+    /// Read an asset as UTF-8 text
+    fn read_to_string(&self, path: &str) -> std::io::Result<String> {
+        String::from_utf8(self.read(path)?).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+
+    /// Check if an asset exists in this source
+    fn exists(&self, path: &str) -> bool {
+        self.read(path).is_ok()
+    }
+
+    /// List the names of the entries (files and folders) directly inside a folder, sorted by name.
+    /// An empty path lists the root of the source
+    fn list(&self, path: &str) -> std::io::Result<Vec<String>>;
+}
+
+/// Asset source backed by a folder on the filesystem
+#[derive(Debug, Clone)]
+pub struct FsAssetSource {
+    root: PathBuf,
+}
+
+impl FsAssetSource {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    pub fn get_root(&self) -> &PathBuf {
+        &self.root
+    }
+}
+
+impl AssetSource for FsAssetSource {
+    fn read(&self, path: &str) -> std::io::Result<Vec<u8>> {
+        fs::read(self.root.join(path))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.root.join(path).is_file()
+    }
+
+    fn list(&self, path: &str) -> std::io::Result<Vec<String>> {
+        let mut names = fs::read_dir(self.root.join(path))?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<std::io::Result<Vec<String>>>()?;
+        names.sort();
+        Ok(names)
+    }
+}
+
+/// Asset source that keeps every asset in memory, mostly useful for tests and generated assets
+#[derive(Debug, Clone, Default)]
+pub struct MemoryAssetSource {
+    files: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryAssetSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, path: impl Into<String>, data: impl Into<Vec<u8>>) {
+        self.files.insert(path.into(), data.into());
+    }
+
+    pub fn with_file(mut self, path: impl Into<String>, data: impl Into<Vec<u8>>) -> Self {
+        self.insert(path, data);
+        self
+    }
+
+    pub fn remove(&mut self, path: &str) -> Option<Vec<u8>> {
+        self.files.remove(path)
+    }
+}
+
+impl AssetSource for MemoryAssetSource {
+    fn read(&self, path: &str) -> std::io::Result<Vec<u8>> {
+        self.files
+            .get(path)
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, format!("Asset not found: {}", path)))
+    }
+
+    fn exists(&self, path: &str) -> bool {
+        self.files.contains_key(path)
+    }
+
+    fn list(&self, path: &str) -> std::io::Result<Vec<String>> {
+        let prefix = if path.is_empty() {
+            String::new()
+        } else {
+            format!("{}/", path.trim_end_matches('/'))
+        };
+
+        let names: BTreeSet<String> = self
+            .files
+            .keys()
+            .filter_map(|file| file.strip_prefix(&prefix))
+            .filter_map(|rest| rest.split('/').next())
+            .map(str::to_owned)
+            .collect();
+
+        if names.is_empty() {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                format!("Folder not found: {}", path),
+            ));
+        }
+
+        Ok(names.into_iter().collect())
+    }
+}
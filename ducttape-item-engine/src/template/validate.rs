@@ -0,0 +1,368 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+};
+
+use hex_color::HexColor;
+use image::GenericImageView as _;
+use uuid::Uuid;
+
+use super::{loader::ItemTemplate, source::AssetSource};
+
+/// A problem with a template's definition or assets, found by `validate_template`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateIssue {
+    /// The template couldn't be loaded at all
+    Load(String),
+    /// An attribute uuid used more than once within the template
+    DuplicateUuid(Uuid),
+    /// An attribute uuid also used by other templates
+    SharedUuid {
+        uuid: Uuid,
+        templates: Vec<String>,
+    },
+    /// The `template.png` mask couldn't be read
+    MissingMask(String),
+    /// No pixel of the mask has the component's color
+    MissingMaskColor {
+        component: String,
+        color: HexColor,
+    },
+    /// The component has no fallback image
+    MissingFallback(String),
+    /// The fallback image of a component doesn't exist
+    UnresolvedFallback {
+        component: String,
+        path: String,
+    },
+    /// A component image that isn't the size of the mask
+    SizeMismatch {
+        path: String,
+        size: (u32, u32),
+        mask: (u32, u32),
+    },
+    UnreadableImage {
+        path: String,
+        error: String,
+    },
+    /// An image named after a component the template doesn't have
+    UnknownComponent {
+        path: String,
+        component: String,
+    },
+}
+
+impl std::fmt::Display for TemplateIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TemplateIssue::Load(error) => write!(f, "Failed to load: {}", error),
+            TemplateIssue::DuplicateUuid(uuid) => {
+                write!(f, "Attribute uuid {} is used twice", uuid)
+            }
+            TemplateIssue::SharedUuid { uuid, templates } => write!(
+                f,
+                "Attribute uuid {} is also used by {}",
+                uuid,
+                templates.join(", ")
+            ),
+            TemplateIssue::MissingMask(error) => write!(f, "Failed to read the mask: {}", error),
+            TemplateIssue::MissingMaskColor { component, color } => write!(
+                f,
+                "No pixel of the mask has the color {} of {}",
+                color.display_rgba(),
+                component
+            ),
+            TemplateIssue::MissingFallback(component) => {
+                write!(f, "{} has no fallback", component)
+            }
+            TemplateIssue::UnresolvedFallback { component, path } => {
+                write!(f, "The fallback of {} doesn't exist: {}", component, path)
+            }
+            TemplateIssue::SizeMismatch { path, size, mask } => write!(
+                f,
+                "{} is {}x{}, the mask is {}x{}",
+                path, size.0, size.1, mask.0, mask.1
+            ),
+            TemplateIssue::UnreadableImage { path, error } => {
+                write!(f, "Failed to read {}: {}", path, error)
+            }
+            TemplateIssue::UnknownComponent { path, component } => {
+                write!(f, "{} is for {}, which isn't a component", path, component)
+            }
+        }
+    }
+}
+
+fn attribute_uuids(template: &ItemTemplate) -> Vec<Uuid> {
+    template
+        .get_attributes()
+        .values()
+        .flatten()
+        .map(|attribute| attribute.uuid)
+        .collect()
+}
+
+/// Check a template for duplicate uuids, a mask missing component colors, unresolvable
+/// fallbacks and component images that don't match the mask size
+pub fn validate_template(template: &ItemTemplate) -> Vec<TemplateIssue> {
+    let mut issues = Vec::new();
+
+    let mut seen = HashSet::new();
+    for uuid in attribute_uuids(template) {
+        if !seen.insert(uuid) {
+            issues.push(TemplateIssue::DuplicateUuid(uuid));
+        }
+    }
+
+    let mask = match template.load_mask() {
+        Ok(mask) => Some(mask),
+        Err(e) => {
+            issues.push(TemplateIssue::MissingMask(e.to_string()));
+            None
+        }
+    };
+
+    let components = template.get_component_names();
+
+    if let Some(mask) = &mask {
+        let colors: HashSet<HexColor> = mask
+            .pixels()
+            .map(|(_, _, pixel)| HexColor::rgba(pixel[0], pixel[1], pixel[2], pixel[3]))
+            .collect();
+
+        for component in components.iter() {
+            if let Some(color) = template.get_component_color(component) {
+                if !colors.contains(&color) {
+                    issues.push(TemplateIssue::MissingMaskColor {
+                        component: component.clone(),
+                        color,
+                    });
+                }
+            }
+        }
+    }
+
+    let source = template.get_source();
+
+    for component in components.iter() {
+        match template.get_fallback_path(component) {
+            None => issues.push(TemplateIssue::MissingFallback(component.clone())),
+            Some(path) if !source.exists(path) => issues.push(TemplateIssue::UnresolvedFallback {
+                component: component.clone(),
+                path: path.to_owned(),
+            }),
+            Some(_) => {}
+        }
+    }
+
+    // Component images are named `{component}-{item}.png`, anything else is left alone
+    let folder = template.get_folder();
+    let images = source.list(folder).unwrap_or_default();
+
+    for name in images.iter().filter(|name| name.ends_with(".png")) {
+        let Some((component, _)) = name.trim_end_matches(".png").split_once('-') else {
+            continue;
+        };
+        let path = format!("{}/{}", folder, name);
+
+        if !components.iter().any(|c| c == component) {
+            issues.push(TemplateIssue::UnknownComponent {
+                path,
+                component: component.to_owned(),
+            });
+            continue;
+        }
+
+        let image = source
+            .read(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|data| image::load_from_memory(&data).map_err(|e| e.to_string()));
+
+        match (image, &mask) {
+            (Err(error), _) => issues.push(TemplateIssue::UnreadableImage { path, error }),
+            (Ok(image), Some(mask)) if image.dimensions() != mask.dimensions() => {
+                issues.push(TemplateIssue::SizeMismatch {
+                    path,
+                    size: image.dimensions(),
+                    mask: mask.dimensions(),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    issues
+}
+
+/// Get the folders in the root of the source that contain a `template.toml`
+pub fn list_templates(source: &dyn AssetSource) -> std::io::Result<Vec<String>> {
+    Ok(source
+        .list("")?
+        .into_iter()
+        .filter(|name| source.exists(&format!("{}/template.toml", name)))
+        .collect())
+}
+
+/// Validate every template of the source, by template name. Unlike `validate_template` this
+/// also finds attribute uuids shared between templates
+pub fn validate_templates(
+    source: Arc<dyn AssetSource>,
+) -> std::io::Result<BTreeMap<String, Vec<TemplateIssue>>> {
+    let mut issues = BTreeMap::new();
+    let mut users: HashMap<Uuid, Vec<String>> = HashMap::new();
+
+    for name in list_templates(&*source)? {
+        let template_issues = match ItemTemplate::load_template(source.clone(), &name) {
+            Ok(template) => {
+                let uuids: HashSet<Uuid> = attribute_uuids(&template).into_iter().collect();
+                for uuid in uuids {
+                    users.entry(uuid).or_default().push(name.clone());
+                }
+
+                validate_template(&template)
+            }
+            Err(e) => vec![TemplateIssue::Load(e.to_string())],
+        };
+
+        issues.insert(name, template_issues);
+    }
+
+    for (uuid, templates) in users
+        .into_iter()
+        .filter(|(_, templates)| templates.len() > 1)
+    {
+        for name in templates.iter() {
+            issues
+                .entry(name.clone())
+                .or_default()
+                .push(TemplateIssue::SharedUuid {
+                    uuid,
+                    templates: templates.iter().filter(|t| *t != name).cloned().collect(),
+                });
+        }
+    }
+
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use image::{DynamicImage, GenericImage as _, GenericImageView as _, Rgba};
+
+    use crate::{item::ItemTexture, template::source::MemoryAssetSource};
+
+    use super::*;
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const GRAY: [u8; 4] = [128, 128, 128, 255];
+
+    fn png(pixels: &[[u8; 4]]) -> Vec<u8> {
+        let mut image = DynamicImage::new_rgba8(pixels.len() as u32, 1);
+        for (x, pixel) in pixels.iter().enumerate() {
+            image.put_pixel(x as u32, 0, Rgba(*pixel));
+        }
+
+        let mut buf = Cursor::new(Vec::new());
+        image
+            .write_to(&mut buf, image::ImageFormat::Png)
+            .expect("Failed to write image to buffer");
+        buf.into_inner()
+    }
+
+    fn stick_toml(uuid: &str) -> String {
+        format!(
+            r##"
+            data_name = "stick"
+
+            [attribute.Sharpness]
+            strategy = "Sum"
+
+            [attribute.Sharpness.attr.'{}']
+            priority = 1
+            reason = "🪵"
+            modifier = {{ "Set" = 1 }}
+
+            [components]
+            tip = "#ff0000"
+            shaft = "#00ff00"
+
+            [fallback]
+            tip = "rock"
+            shaft = "rock"
+            "##,
+            uuid
+        )
+    }
+
+    const UUID: &str = "9c1ba4b5-1d4d-4bcb-8d7e-5a6f2a1f4e0b";
+
+    fn stick() -> MemoryAssetSource {
+        MemoryAssetSource::new()
+            .with_file("stick/template.toml", stick_toml(UUID))
+            .with_file("stick/template.png", png(&[RED, GREEN]))
+            .with_file("stick/tip-rock.png", png(&[GRAY, GRAY]))
+            .with_file("stick/shaft-rock.png", png(&[GRAY, GRAY]))
+    }
+
+    #[test]
+    fn valid_template() {
+        let issues = validate_templates(Arc::new(stick())).unwrap();
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues["stick"], Vec::new());
+    }
+
+    #[test]
+    fn broken_template() {
+        let source = stick()
+            .with_file("stick/template.png", png(&[RED, RED]))
+            .with_file("stick/tip-rock.png", png(&[GRAY]))
+            .with_file("stick/blade-rock.png", png(&[GRAY, GRAY]))
+            .with_file("club/template.toml", stick_toml(UUID))
+            .with_file("club/template.png", png(&[RED, GREEN]));
+
+        let issues = validate_templates(Arc::new(source)).unwrap();
+        let stick = &issues["stick"];
+
+        assert!(stick.contains(&TemplateIssue::MissingMaskColor {
+            component: "shaft".to_owned(),
+            color: HexColor::rgb(0, 255, 0),
+        }));
+        assert!(stick.contains(&TemplateIssue::SizeMismatch {
+            path: "stick/tip-rock.png".to_owned(),
+            size: (1, 1),
+            mask: (2, 1),
+        }));
+        assert!(stick.contains(&TemplateIssue::UnknownComponent {
+            path: "stick/blade-rock.png".to_owned(),
+            component: "blade".to_owned(),
+        }));
+        assert!(stick.contains(&TemplateIssue::SharedUuid {
+            uuid: UUID.parse().unwrap(),
+            templates: vec!["club".to_owned()],
+        }));
+
+        assert!(issues["club"].contains(&TemplateIssue::UnresolvedFallback {
+            component: "tip".to_owned(),
+            path: "club/tip-rock.png".to_owned(),
+        }));
+    }
+
+    #[test]
+    fn render_fallbacks() {
+        let source = stick().with_file("stick/shaft-rope.png", png(&[GREEN, GREEN]));
+        let template = ItemTemplate::load_template(Arc::new(source), "stick").unwrap();
+
+        let items = HashMap::from([("shaft".to_owned(), "rope".to_owned())]);
+        let ItemTexture::Image(image) = template.render(&items).unwrap() else {
+            panic!("Expected the stick to render to an image");
+        };
+
+        assert_eq!(image.dimensions(), (2, 1));
+        assert_eq!(image.get_pixel(0, 0), Rgba(GRAY));
+        assert_eq!(image.get_pixel(1, 0), Rgba(GREEN));
+    }
+}
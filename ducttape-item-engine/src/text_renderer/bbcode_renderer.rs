@@ -26,9 +26,9 @@ impl BBCodeRenderer for Text {
                     bbcode_string.push_str("[s]");
                 }
 
-                if text.color.is_some() {
+                if let Some(color) = &text.color {
                     bbcode_string.push_str("[color=");
-                    bbcode_string.push_str(&text.color.as_ref().unwrap().to_string());
+                    bbcode_string.push_str(&color.to_string());
                     bbcode_string.push(']');
                 }
 
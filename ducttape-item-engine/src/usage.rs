@@ -0,0 +1,349 @@
+use godot::builtin::Vector2;
+
+use crate::{
+    attribute::{condition::AttributeContext, AttributeParser, AttributeType},
+    item::{is_air, EngineHook, Item, ItemCollection, ItemMut, ItemStack},
+};
+
+/// Pixels of range per point of `Reach`
+pub const PIXELS_PER_REACH: f32 = 4.0;
+/// Pixels of rope per point of `Strength`
+pub const ROPE_PIXELS_PER_STRENGTH: f32 = 8.0;
+/// How far past the gap a bridge rests on each side
+pub const BRIDGE_OVERHANG: f32 = 16.0;
+/// Throw speed of an item weighing 1, heavier items are thrown slower
+pub const THROW_SPEED: f32 = 400.0;
+pub const MIN_THROW_SPEED: f32 = 80.0;
+/// How much of the throw speed goes upwards
+pub const THROW_ARC: f32 = 0.6;
+
+/// What using an item does in the world
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemAction {
+    /// Hit everything within `reach` in front of the user
+    Swing { reach: f32, damage: f32 },
+    /// Throw one item, heavier items fly slower and lower
+    Throw { velocity: Vector2 },
+    /// Lay one item across the gap in front of the user
+    PlaceBridge { length: f32 },
+    /// Tie one item to the ledge in front of the user so it can be climbed down
+    TieRope { length: f32 },
+}
+
+impl ItemAction {
+    /// Pick what an item does, based on its tags and stats and on what is in front of the user
+    pub fn choose<THook: EngineHook>(
+        stack: &ItemStack<THook>,
+        stats: &AttributeParser,
+        gap_ahead: bool,
+        facing: f32,
+    ) -> Self {
+        let tags = stack.get_tags();
+        let has_tag = |tag: &str| tags.iter().any(|t| t == tag);
+
+        let reach = stats.aggregate_to_value(AttributeType::Reach) as f32 * PIXELS_PER_REACH;
+        let sharpness = stats.aggregate_to_value(AttributeType::Sharpness) as f32;
+
+        if gap_ahead && has_tag("long") {
+            return ItemAction::PlaceBridge {
+                length: reach + BRIDGE_OVERHANG * 2.0,
+            };
+        }
+
+        if gap_ahead && has_tag("binding") {
+            let strength = stats.aggregate_to_value(AttributeType::Strength) as f32;
+            return ItemAction::TieRope {
+                length: strength * ROPE_PIXELS_PER_STRENGTH,
+            };
+        }
+
+        // Stacked items share their wear, so only items that don't stack are swung
+        let swingable = stack.get_max_stack_size() == 1;
+        if swingable && (has_tag("weapon") || (!has_tag("throwable") && sharpness > 0.0)) {
+            return ItemAction::Swing {
+                reach,
+                damage: sharpness,
+            };
+        }
+
+        let weight = stats.aggregate_to_value(AttributeType::Weight).max(1.0) as f32;
+        let speed = (THROW_SPEED / weight.sqrt()).max(MIN_THROW_SPEED);
+
+        ItemAction::Throw {
+            velocity: Vector2::new(facing * speed, -speed * THROW_ARC),
+        }
+    }
+
+    /// Durability the item loses by being used like this
+    pub fn get_wear(&self) -> f64 {
+        match self {
+            ItemAction::Swing { .. } => 1.0,
+            ItemAction::Throw { .. } => 2.0,
+            ItemAction::PlaceBridge { .. } | ItemAction::TieRope { .. } => 5.0,
+        }
+    }
+
+    /// Check if the item leaves the user's hand
+    pub fn consumes_item(&self) -> bool {
+        !matches!(self, ItemAction::Swing { .. })
+    }
+}
+
+/// An item that was used, see `use_item`
+#[derive(Debug, Clone)]
+pub struct UsedItem<THook: EngineHook> {
+    pub action: ItemAction,
+    /// The used item, always a single item
+    pub stack: ItemStack<THook>,
+    /// The item wore down completely, it is gone and the action does not happen
+    pub broken: bool,
+}
+
+/// Use the item in a slot of a collection, returns `None` if the slot is empty.
+/// A single item is split off the stack and takes the wear of the action, the rest of the
+/// stack keeps its own wear. Swung items go back into the slot unless they broke
+pub fn use_item<THook: EngineHook>(
+    collection: &mut impl ItemCollection<THook>,
+    index: usize,
+    context: AttributeContext,
+    gap_ahead: bool,
+    facing: f32,
+) -> Option<UsedItem<THook>> {
+    let stack = collection.get_item(index).ok()?;
+    if is_air(stack) {
+        return None;
+    }
+
+    let stats = AttributeParser::from(stack.get_stats().get_all_attributes()).with_context(context);
+    let action = ItemAction::choose(stack, &stats, gap_ahead, facing);
+
+    let mut used = collection.take_item(index, 1).ok()?;
+    used.get_item_mut().get_stats_mut().consume_use();
+    let broken = used.get_item_mut().damage(action.get_wear());
+
+    if !action.consumes_item() && !broken {
+        // Swung items don't stack, so taking the item emptied the slot
+        collection.get_items_mut()[index] = used.clone();
+        collection.refresh();
+    }
+
+    Some(UsedItem {
+        action,
+        stack: used,
+        broken,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        data_item::DataItem,
+        item::{DummyHook, ItemCollectionSized},
+        prelude_items::{rock, PRELUDE_ASSETS},
+    };
+
+    use super::*;
+
+    fn item(toml: &str) -> Arc<dyn Item<DummyHook>> {
+        Arc::new(DataItem::from_toml(&*PRELUDE_ASSETS, "test.toml", toml).unwrap())
+    }
+
+    fn plank() -> Arc<dyn Item<DummyHook>> {
+        item(
+            r#"
+            ident = "plank"
+            name = "plank"
+            texture = "rock.png"
+            tags = ["long"]
+
+            [attributes]
+            Reach = 10.0
+            Durability = 12.0
+            "#,
+        )
+    }
+
+    fn knife() -> Arc<dyn Item<DummyHook>> {
+        item(
+            r#"
+            ident = "knife"
+            name = "knife"
+            texture = "rock.png"
+            max_stack_size = 1
+
+            [attributes]
+            Sharpness = 3.0
+            Reach = 2.0
+            Durability = 20.0
+            "#,
+        )
+    }
+
+    fn choose(item: Arc<dyn Item<DummyHook>>, gap_ahead: bool) -> ItemAction {
+        let stack = ItemStack::new(item, 1);
+        let stats = AttributeParser::from(stack.get_stats().get_all_attributes());
+        ItemAction::choose(&stack, &stats, gap_ahead, 1.0)
+    }
+
+    #[test]
+    fn choose_by_tags_and_gap() {
+        assert_eq!(
+            choose(plank(), true),
+            ItemAction::PlaceBridge {
+                length: 10.0 * PIXELS_PER_REACH + BRIDGE_OVERHANG * 2.0
+            }
+        );
+        // Without a gap a blunt plank is thrown
+        assert!(matches!(choose(plank(), false), ItemAction::Throw { .. }));
+
+        let vine = item(
+            r#"
+            ident = "vine"
+            name = "vine"
+            texture = "rock.png"
+            tags = ["binding"]
+
+            [attributes]
+            Strength = 4.0
+            "#,
+        );
+        assert_eq!(
+            choose(vine, true),
+            ItemAction::TieRope {
+                length: 4.0 * ROPE_PIXELS_PER_STRENGTH
+            }
+        );
+
+        // The knife is sharp, so it is swung unless tagged throwable
+        assert_eq!(
+            choose(knife(), false),
+            ItemAction::Swing {
+                reach: 2.0 * PIXELS_PER_REACH,
+                damage: 3.0
+            }
+        );
+        // Rocks are sharp too, but they stack and would share their wear
+        assert!(matches!(
+            choose(Arc::new(rock()), false),
+            ItemAction::Throw { .. }
+        ));
+        let pebble = item(
+            r#"
+            ident = "pebble"
+            name = "pebble"
+            texture = "rock.png"
+            tags = ["throwable"]
+
+            [attributes]
+            Sharpness = 1.0
+            Weight = 4.0
+            "#,
+        );
+        assert_eq!(
+            choose(pebble, false),
+            ItemAction::Throw {
+                velocity: Vector2::new(200.0, -200.0 * THROW_ARC)
+            }
+        );
+    }
+
+    #[test]
+    fn heavy_throws_keep_a_minimum_speed() {
+        let boulder = item(
+            r#"
+            ident = "boulder"
+            name = "boulder"
+            texture = "rock.png"
+
+            [attributes]
+            Weight = 10000.0
+            "#,
+        );
+
+        let stack = ItemStack::new(boulder, 1);
+        let stats = AttributeParser::from(stack.get_stats().get_all_attributes());
+
+        assert_eq!(
+            ItemAction::choose(&stack, &stats, false, -1.0),
+            ItemAction::Throw {
+                velocity: Vector2::new(-MIN_THROW_SPEED, -MIN_THROW_SPEED * THROW_ARC)
+            }
+        );
+    }
+
+    #[test]
+    fn swinging_wears_the_held_item() {
+        let mut hand = ItemCollectionSized::new(1);
+        let mut worn = ItemStack::new(knife(), 1);
+        worn.get_item_mut().damage(3.0);
+        hand.add_item(worn).unwrap();
+
+        let used = use_item(&mut hand, 0, AttributeContext::new(), false, 1.0).unwrap();
+
+        assert!(matches!(used.action, ItemAction::Swing { .. }));
+        assert!(!used.broken);
+        assert_eq!(used.stack.get_count(), 1);
+
+        // The knife stays in hand and keeps the wear it had
+        let held = hand.get_item(0).unwrap();
+        assert_eq!(held.get_count(), 1);
+        assert_eq!(held.get_item().get_damage(), 4.0);
+    }
+
+    #[test]
+    fn throwing_keeps_the_wear_of_the_rest() {
+        let mut hand = ItemCollectionSized::new(1);
+        let mut worn = ItemStack::new(Arc::new(rock()), 3);
+        worn.get_item_mut().damage(10.0);
+        hand.add_item(worn).unwrap();
+
+        let used = use_item(&mut hand, 0, AttributeContext::new(), false, 1.0).unwrap();
+
+        assert!(matches!(used.action, ItemAction::Throw { .. }));
+        assert_eq!(used.stack.get_count(), 1);
+        assert_eq!(used.stack.get_item().get_damage(), 12.0);
+
+        let rest = hand.get_item(0).unwrap();
+        assert_eq!(rest.get_count(), 2);
+        assert_eq!(rest.get_item().get_damage(), 10.0);
+    }
+
+    #[test]
+    fn bridging_takes_one_item() {
+        let mut hand = ItemCollectionSized::new(1);
+        hand.add_item(ItemStack::new(plank(), 3)).unwrap();
+
+        let used = use_item(&mut hand, 0, AttributeContext::new(), true, 1.0).unwrap();
+
+        assert!(matches!(used.action, ItemAction::PlaceBridge { .. }));
+        assert_eq!(used.stack.get_count(), 1);
+        assert_eq!(used.stack.get_item().get_damage(), 5.0);
+
+        let rest = hand.get_item(0).unwrap();
+        assert_eq!(rest.get_count(), 2);
+        assert_eq!(rest.get_item().get_damage(), 0.0);
+    }
+
+    #[test]
+    fn broken_items_leave_the_hand() {
+        let mut hand = ItemCollectionSized::new(1);
+        hand.add_item(ItemStack::new(plank(), 1)).unwrap();
+
+        // The last plank is laid down, leaving nothing to use
+        let used = use_item(&mut hand, 0, AttributeContext::new(), true, 1.0).unwrap();
+        assert!(!used.broken);
+        assert!(use_item(&mut hand, 0, AttributeContext::new(), true, 1.0).is_none());
+
+        let mut hand = ItemCollectionSized::new(1);
+        let mut worn = ItemStack::new(knife(), 1);
+        worn.get_item_mut().damage(19.0);
+        hand.add_item(worn).unwrap();
+
+        let used = use_item(&mut hand, 0, AttributeContext::new(), false, 1.0).unwrap();
+        assert!(matches!(used.action, ItemAction::Swing { .. }));
+        assert!(used.broken);
+        assert!(hand.get_item(0).is_err());
+    }
+}
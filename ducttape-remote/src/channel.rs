@@ -0,0 +1,136 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use crate::{
+    client::RemoteClient,
+    command::{RemoteCommand, RemoteResponse},
+    error::{RemoteError, RemoteResult},
+};
+
+/// How long a call waits for the game to answer, the game answers on its next physics frame
+pub const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// A command waiting for the game, answered with `respond`
+#[derive(Debug)]
+pub struct RemoteRequest {
+    pub command: RemoteCommand,
+    reply: Sender<RemoteResult<RemoteResponse>>,
+}
+
+impl RemoteRequest {
+    pub fn respond(self, result: RemoteResult<RemoteResponse>) {
+        // The caller may have timed out already, then nobody wants the answer
+        let _ = self.reply.send(result);
+    }
+}
+
+/// The in-process transport, every other transport forwards its commands through one of these
+#[derive(Debug, Clone)]
+pub struct ChannelClient {
+    sender: Sender<RemoteRequest>,
+    timeout: Duration,
+}
+
+impl ChannelClient {
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl RemoteClient for ChannelClient {
+    fn call(&self, command: RemoteCommand) -> RemoteResult<RemoteResponse> {
+        let (reply, answer) = mpsc::channel();
+
+        self.sender
+            .send(RemoteRequest { command, reply })
+            .map_err(|_| RemoteError::Transport("The game is not running".to_owned()))?;
+
+        match answer.recv_timeout(self.timeout) {
+            Ok(result) => result,
+            Err(RecvTimeoutError::Timeout) => {
+                Err(RemoteError::TimedOut("The game did not answer".to_owned()))
+            }
+            Err(RecvTimeoutError::Disconnected) => Err(RemoteError::Transport(
+                "The game dropped the command".to_owned(),
+            )),
+        }
+    }
+}
+
+/// The game's end of the channel, the receiver is locked so it can be shared between threads
+#[derive(Debug)]
+pub struct RemoteReceiver(Mutex<Receiver<RemoteRequest>>);
+
+impl RemoteReceiver {
+    /// Take every command that is waiting
+    pub fn drain(&self) -> Vec<RemoteRequest> {
+        self.0.lock().unwrap().try_iter().collect()
+    }
+}
+
+/// Create a connected client and receiver
+pub fn channel() -> (ChannelClient, RemoteReceiver) {
+    let (sender, receiver) = mpsc::channel();
+
+    (
+        ChannelClient {
+            sender,
+            timeout: REPLY_TIMEOUT,
+        },
+        RemoteReceiver(Mutex::new(receiver)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn call_and_respond() {
+        let (client, receiver) = channel();
+
+        let game = thread::spawn(move || loop {
+            for request in receiver.drain() {
+                let result = match request.command {
+                    RemoteCommand::ListRecipes => Ok(RemoteResponse::Names(vec!["spear".into()])),
+                    RemoteCommand::AddItem { ref item, .. } => {
+                        Err(RemoteError::ItemNotFound(item.clone()))
+                    }
+                    _ => return,
+                };
+                request.respond(result);
+            }
+            thread::yield_now();
+        });
+
+        assert_eq!(client.list_recipes().unwrap(), vec!["spear".to_owned()]);
+        assert_eq!(
+            client.add_item("pebble", 1),
+            Err(RemoteError::ItemNotFound("pebble".to_owned()))
+        );
+
+        // Anything else stops the game, which drops the command unanswered
+        let client = client.with_timeout(Duration::from_secs(5));
+        assert!(matches!(
+            client.list_entities(),
+            Err(RemoteError::Transport(_))
+        ));
+        game.join().unwrap();
+    }
+
+    #[test]
+    fn timeout() {
+        let (client, _receiver) = channel();
+        let client = client.with_timeout(Duration::from_millis(10));
+
+        assert!(matches!(client.get_game_state(), Err(RemoteError::TimedOut(_))));
+    }
+}
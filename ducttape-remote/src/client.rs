@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use crate::{
+    command::{RemoteCommand, RemoteResponse},
+    error::{RemoteError, RemoteResult},
+    info::{EntityInfo, GameStateInfo, ItemInfo, RegistryItemInfo},
+};
+
+/// Unwrap the expected kind of answer, anything else means the transport mixed up answers
+macro_rules! expect_response {
+    ($response:expr, $variant:ident) => {
+        match $response {
+            RemoteResponse::$variant(value) => Ok(value),
+            other => Err(unexpected(other)),
+        }
+    };
+}
+
+fn unexpected(response: RemoteResponse) -> RemoteError {
+    RemoteError::Transport(format!("Unexpected answer: {:?}", response))
+}
+
+fn expect_done(response: RemoteResponse) -> RemoteResult<()> {
+    match response {
+        RemoteResponse::Done => Ok(()),
+        other => Err(unexpected(other)),
+    }
+}
+
+/// Something that sends commands to the game, implemented by every transport's client.
+///
+/// Only `call` has to be implemented, the typed methods are built on it
+pub trait RemoteClient {
+    fn call(&self, command: RemoteCommand) -> RemoteResult<RemoteResponse>;
+
+    fn spawn_entity(&self, kind: &str, position: (f32, f32)) -> RemoteResult<u64> {
+        expect_response!(
+            self.call(RemoteCommand::SpawnEntity {
+                kind: kind.to_owned(),
+                position,
+            })?,
+            EntityId
+        )
+    }
+
+    fn list_entities(&self) -> RemoteResult<Vec<EntityInfo>> {
+        expect_response!(self.call(RemoteCommand::ListEntities)?, Entities)
+    }
+
+    fn get_entity(&self, id: u64) -> RemoteResult<EntityInfo> {
+        expect_response!(self.call(RemoteCommand::GetEntity { id })?, Entity)
+    }
+
+    fn remove_entity(&self, id: u64) -> RemoteResult<()> {
+        expect_done(self.call(RemoteCommand::RemoveEntity { id })?)
+    }
+
+    fn set_position(&self, id: u64, position: (f32, f32)) -> RemoteResult<()> {
+        expect_done(self.call(RemoteCommand::SetPosition { id, position })?)
+    }
+
+    fn set_velocity(&self, id: u64, velocity: (f32, f32)) -> RemoteResult<()> {
+        expect_done(self.call(RemoteCommand::SetVelocity { id, velocity })?)
+    }
+
+    fn get_game_state(&self) -> RemoteResult<GameStateInfo> {
+        expect_response!(self.call(RemoteCommand::GetGameState)?, GameState)
+    }
+
+    fn list_inventory(&self) -> RemoteResult<Vec<ItemInfo>> {
+        expect_response!(self.call(RemoteCommand::ListInventory)?, Items)
+    }
+
+    fn add_item(&self, item: &str, amount: u32) -> RemoteResult<()> {
+        expect_done(self.call(RemoteCommand::AddItem {
+            item: item.to_owned(),
+            amount,
+        })?)
+    }
+
+    fn remove_item(&self, slot: u32, count: u32) -> RemoteResult<ItemInfo> {
+        expect_response!(self.call(RemoteCommand::RemoveItem { slot, count })?, Item)
+    }
+
+    fn move_item(&self, from: u32, to: u32) -> RemoteResult<()> {
+        expect_done(self.call(RemoteCommand::MoveItem { from, to })?)
+    }
+
+    fn list_registry(&self) -> RemoteResult<Vec<RegistryItemInfo>> {
+        expect_response!(self.call(RemoteCommand::ListRegistry)?, Registry)
+    }
+
+    fn list_recipes(&self) -> RemoteResult<Vec<String>> {
+        expect_response!(self.call(RemoteCommand::ListRecipes)?, Names)
+    }
+
+    fn craft(&self, recipe: &str, components: HashMap<String, String>) -> RemoteResult<String> {
+        expect_response!(
+            self.call(RemoteCommand::Craft {
+                recipe: recipe.to_owned(),
+                components,
+            })?,
+            Ident
+        )
+    }
+}
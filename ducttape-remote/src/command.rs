@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::info::{EntityInfo, GameStateInfo, ItemInfo, RegistryItemInfo};
+
+/// Everything a remote can ask the game to do.
+///
+/// Serialized as `{ "method": "spawn_entity", "params": { ... } }`, which is also how the
+/// JSON-RPC transport names its methods
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+pub enum RemoteCommand {
+    /// Spawn an entity of a registered kind, answered with its id
+    SpawnEntity { kind: String, position: (f32, f32) },
+    ListEntities,
+    GetEntity { id: u64 },
+    RemoveEntity { id: u64 },
+    SetPosition { id: u64, position: (f32, f32) },
+    /// Only works for physics bodies
+    SetVelocity { id: u64, velocity: (f32, f32) },
+    GetGameState,
+    /// Every non-empty inventory slot with the stats of its stack
+    ListInventory,
+    AddItem { item: String, amount: u32 },
+    /// Take `count` items out of a slot, answered with what was taken
+    RemoveItem { slot: u32, count: u32 },
+    /// Move a stack onto another slot, merging same items and swapping different ones
+    MoveItem { from: u32, to: u32 },
+    ListRegistry,
+    ListRecipes,
+    /// Craft a recipe from the inventory, components are given as item idents and taken from
+    /// the first slot holding that item. Answered with the ident of the crafted item
+    Craft {
+        recipe: String,
+        components: HashMap<String, String>,
+    },
+}
+
+/// The answer to a `RemoteCommand`, each command has exactly one kind of answer.
+///
+/// Serialized as just the value, so the command has to be known to read it back, see
+/// `RemoteCommand::parse_response`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum RemoteResponse {
+    /// The command has no result
+    Done,
+    EntityId(u64),
+    Entity(EntityInfo),
+    Entities(Vec<EntityInfo>),
+    GameState(GameStateInfo),
+    Item(ItemInfo),
+    Items(Vec<ItemInfo>),
+    Registry(Vec<RegistryItemInfo>),
+    Names(Vec<String>),
+    Ident(String),
+}
+
+impl RemoteCommand {
+    /// Read the answer to this command from its serialized value
+    pub fn parse_response(&self, value: serde_json::Value) -> serde_json::Result<RemoteResponse> {
+        let response = match self {
+            RemoteCommand::SpawnEntity { .. } => {
+                RemoteResponse::EntityId(serde_json::from_value(value)?)
+            }
+            RemoteCommand::ListEntities => RemoteResponse::Entities(serde_json::from_value(value)?),
+            RemoteCommand::GetEntity { .. } => {
+                RemoteResponse::Entity(serde_json::from_value(value)?)
+            }
+            RemoteCommand::RemoveEntity { .. }
+            | RemoteCommand::SetPosition { .. }
+            | RemoteCommand::SetVelocity { .. }
+            | RemoteCommand::AddItem { .. }
+            | RemoteCommand::MoveItem { .. } => RemoteResponse::Done,
+            RemoteCommand::GetGameState => {
+                RemoteResponse::GameState(serde_json::from_value(value)?)
+            }
+            RemoteCommand::ListInventory => RemoteResponse::Items(serde_json::from_value(value)?),
+            RemoteCommand::RemoveItem { .. } => {
+                RemoteResponse::Item(serde_json::from_value(value)?)
+            }
+            RemoteCommand::ListRegistry => RemoteResponse::Registry(serde_json::from_value(value)?),
+            RemoteCommand::ListRecipes => RemoteResponse::Names(serde_json::from_value(value)?),
+            RemoteCommand::Craft { .. } => RemoteResponse::Ident(serde_json::from_value(value)?),
+        };
+
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_format() {
+        let command = RemoteCommand::SpawnEntity {
+            kind: "Player".to_owned(),
+            position: (10.0, 20.0),
+        };
+        let value = serde_json::to_value(&command).unwrap();
+
+        assert_eq!(value["method"], "spawn_entity");
+        assert_eq!(value["params"]["kind"], "Player");
+        assert_eq!(serde_json::from_value::<RemoteCommand>(value).unwrap(), command);
+
+        // Commands without parameters don't need any
+        let command: RemoteCommand =
+            serde_json::from_value(serde_json::json!({ "method": "list_entities" })).unwrap();
+        assert_eq!(command, RemoteCommand::ListEntities);
+    }
+
+    #[test]
+    fn response_round_trip() {
+        let command = RemoteCommand::ListRecipes;
+        let response = RemoteResponse::Names(vec!["spear".to_owned()]);
+
+        let value = serde_json::to_value(&response).unwrap();
+        assert_eq!(value, serde_json::json!(["spear"]));
+        assert_eq!(command.parse_response(value).unwrap(), response);
+
+        let value = serde_json::to_value(RemoteResponse::Done).unwrap();
+        assert_eq!(
+            RemoteCommand::RemoveEntity { id: 1 }.parse_response(value).unwrap(),
+            RemoteResponse::Done
+        );
+    }
+}
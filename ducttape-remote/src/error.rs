@@ -0,0 +1,65 @@
+use serde::{Deserialize, Serialize};
+
+/// Why a remote command failed, every transport keeps the variant so callers can match on it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RemoteError {
+    /// The item ident is not registered, or the inventory doesn't hold it
+    ItemNotFound(String),
+    /// The inventory has no room for the items
+    InventoryFull(String),
+    /// The entity id or kind doesn't exist, or the entity can't do what was asked
+    InvalidEntity(String),
+    /// A slot, recipe or component that doesn't exist
+    InvalidArgument(String),
+    /// The game didn't answer in time, usually because it is paused or loading
+    TimedOut(String),
+    /// The command or its answer couldn't be sent
+    Transport(String),
+}
+
+impl RemoteError {
+    /// The name of the variant, used as the error name by transports
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            RemoteError::ItemNotFound(_) => "ItemNotFound",
+            RemoteError::InventoryFull(_) => "InventoryFull",
+            RemoteError::InvalidEntity(_) => "InvalidEntity",
+            RemoteError::InvalidArgument(_) => "InvalidArgument",
+            RemoteError::TimedOut(_) => "TimedOut",
+            RemoteError::Transport(_) => "Transport",
+        }
+    }
+
+    pub fn get_message(&self) -> &str {
+        match self {
+            RemoteError::ItemNotFound(message)
+            | RemoteError::InventoryFull(message)
+            | RemoteError::InvalidEntity(message)
+            | RemoteError::InvalidArgument(message)
+            | RemoteError::TimedOut(message)
+            | RemoteError::Transport(message) => message,
+        }
+    }
+}
+
+impl std::fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.get_name(), self.get_message())
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
+impl From<std::io::Error> for RemoteError {
+    fn from(e: std::io::Error) -> Self {
+        RemoteError::Transport(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for RemoteError {
+    fn from(e: serde_json::Error) -> Self {
+        RemoteError::Transport(e.to_string())
+    }
+}
+
+pub type RemoteResult<T> = Result<T, RemoteError>;
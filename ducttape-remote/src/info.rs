@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use zvariant::Type;
+
+/// An entity as seen from a remote
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct EntityInfo {
+    /// Bevy entity bits, stable for as long as the entity lives
+    pub id: u64,
+    pub kind: String,
+    pub position: (f32, f32),
+    /// Zero for entities that aren't physics bodies
+    pub velocity: (f32, f32),
+    pub tags: Vec<String>,
+}
+
+/// A stack in a collection as seen from a remote
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct ItemInfo {
+    pub slot: u32,
+    pub ident: String,
+    pub name: String,
+    pub count: u32,
+    pub tags: Vec<String>,
+    /// Aggregated value of every attribute, by attribute name
+    pub stats: HashMap<String, f64>,
+}
+
+/// An item of the item registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RegistryItemInfo {
+    pub ident: String,
+    pub name: String,
+    pub tags: Vec<String>,
+    /// Compound items are generated from a template
+    pub compound: bool,
+}
+
+/// The state of the running game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct GameStateInfo {
+    pub state: String,
+    /// Scene file of the current level, empty outside of a level
+    pub level: String,
+    /// Bevy entity bits of the player, zero if there is no player
+    pub player: u64,
+    pub player_position: (f32, f32),
+    pub entities: u32,
+    pub inventory_used: u32,
+    pub inventory_size: u32,
+    pub encumbrance: String,
+}
+
+/// A change of the inventory, sent to remotes as it happens
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct InventoryEvent {
+    /// `Add`, `Remove`, `Update`, `Clear` or `ManualRefresh`
+    pub kind: String,
+    /// The changed stack, empty for `Clear` and `ManualRefresh` which can change every slot
+    pub items: Vec<ItemInfo>,
+}
@@ -0,0 +1,403 @@
+//! JSON-RPC 2.0 over a Unix domain socket, one message per line.
+//!
+//! The method and params of a request are those of the serialized `RemoteCommand`, e.g.
+//! `{"jsonrpc": "2.0", "id": 1, "method": "add_item", "params": {"item": "rope", "amount": 3}}`.
+//! Failed commands are answered with an error whose `data` is the serialized `RemoteError`.
+//! After a `subscribe` request the server also sends `inventory_changed` notifications with an
+//! `InventoryEvent`
+
+use std::{
+    collections::VecDeque,
+    io::{self, BufRead, BufReader, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    sync::{mpsc::Receiver, Arc, Mutex},
+    thread,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    channel::ChannelClient,
+    client::RemoteClient,
+    command::{RemoteCommand, RemoteResponse},
+    error::{RemoteError, RemoteResult},
+    info::InventoryEvent,
+};
+
+/// Overrides where the socket is created
+pub const SOCKET_ENV: &str = "DUCTTAPE_REMOTE_SOCKET";
+const SOCKET_NAME: &str = "ducttape-remote.sock";
+
+/// Start sending `inventory_changed` notifications on this connection
+pub const SUBSCRIBE_METHOD: &str = "subscribe";
+pub const INVENTORY_CHANGED_METHOD: &str = "inventory_changed";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const INVALID_PARAMS: i64 = -32602;
+/// Any `RemoteError`, the error's `data` tells which one
+const COMMAND_ERROR: i64 = -32000;
+
+/// Gives every subscribed connection its own stream of inventory changes
+pub type Subscribe = Arc<dyn Fn() -> Receiver<InventoryEvent> + Send + Sync>;
+
+/// The socket path shared by the game and the tools, from `DUCTTAPE_REMOTE_SOCKET` or in the
+/// runtime directory
+pub fn default_socket_path() -> PathBuf {
+    if let Some(path) = std::env::var_os(SOCKET_ENV) {
+        return path.into();
+    }
+
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir)
+        .join(SOCKET_NAME)
+}
+
+fn write_message(writer: &Mutex<UnixStream>, message: &Value) -> io::Result<()> {
+    let mut writer = writer.lock().unwrap();
+
+    serde_json::to_writer(&mut *writer, message)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+fn error_response(id: Value, code: i64, message: String, data: Option<RemoteError>) -> Value {
+    let mut error = json!({ "code": code, "message": message });
+    if let Some(data) = data {
+        error["data"] = json!(data);
+    }
+
+    json!({ "jsonrpc": "2.0", "id": id, "error": error })
+}
+
+/// Accept connections until the listener fails, every connection is served on its own thread
+pub fn serve(
+    listener: UnixListener,
+    client: ChannelClient,
+    subscribe: Subscribe,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let client = client.clone();
+        let subscribe = subscribe.clone();
+
+        thread::spawn(move || serve_connection(stream, client, subscribe));
+    }
+
+    Ok(())
+}
+
+/// Answer the requests of one connection until it is closed
+pub fn serve_connection(
+    stream: UnixStream,
+    client: ChannelClient,
+    subscribe: Subscribe,
+) -> io::Result<()> {
+    let writer = Arc::new(Mutex::new(stream.try_clone()?));
+
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        if let Some(response) = handle_message(&line, &client, &writer, &subscribe) {
+            write_message(&writer, &response)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Run one request, returns the response unless the request is a notification
+fn handle_message(
+    line: &str,
+    client: &ChannelClient,
+    writer: &Arc<Mutex<UnixStream>>,
+    subscribe: &Subscribe,
+) -> Option<Value> {
+    let mut request: Value = match serde_json::from_str(line) {
+        Ok(request) => request,
+        Err(e) => return Some(error_response(Value::Null, PARSE_ERROR, e.to_string(), None)),
+    };
+
+    let Some(request) = request.as_object_mut() else {
+        let message = "A request has to be an object".to_owned();
+        return Some(error_response(Value::Null, INVALID_REQUEST, message, None));
+    };
+
+    let id = request.get("id").cloned();
+    let method = request.remove("method").unwrap_or_default();
+    let params = request.remove("params").unwrap_or_default();
+
+    let result = if method == SUBSCRIBE_METHOD {
+        forward_events(subscribe(), writer.clone());
+        Ok(RemoteResponse::Done)
+    } else {
+        let mut command = json!({ "method": method });
+        if !params.is_null() {
+            command["params"] = params;
+        }
+
+        match serde_json::from_value::<RemoteCommand>(command) {
+            Ok(command) => client.call(command),
+            Err(e) => {
+                let id = id.unwrap_or(Value::Null);
+                return Some(error_response(id, INVALID_PARAMS, e.to_string(), None));
+            }
+        }
+    };
+
+    let id = id?;
+    Some(match result {
+        Ok(response) => json!({ "jsonrpc": "2.0", "id": id, "result": response }),
+        Err(e) => error_response(id, COMMAND_ERROR, e.to_string(), Some(e)),
+    })
+}
+
+/// Send every event as a notification until the connection is closed
+fn forward_events(events: Receiver<InventoryEvent>, writer: Arc<Mutex<UnixStream>>) {
+    thread::spawn(move || {
+        for event in events.iter() {
+            let notification = json!({
+                "jsonrpc": "2.0",
+                "method": INVENTORY_CHANGED_METHOD,
+                "params": event,
+            });
+
+            if write_message(&writer, &notification).is_err() {
+                break;
+            }
+        }
+    });
+}
+
+struct Connection {
+    reader: BufReader<UnixStream>,
+    writer: UnixStream,
+    next_id: u64,
+    /// Notifications read while waiting for a response
+    events: VecDeque<InventoryEvent>,
+}
+
+impl Connection {
+    fn read_message(&mut self) -> RemoteResult<Value> {
+        let mut line = String::new();
+        if self.reader.read_line(&mut line)? == 0 {
+            return Err(RemoteError::Transport("The game closed the connection".to_owned()));
+        }
+
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    /// Keep a notification for `next_event`, returns false if the message isn't one
+    fn queue_event(&mut self, message: &mut Value) -> bool {
+        if message["method"] != INVENTORY_CHANGED_METHOD {
+            return false;
+        }
+
+        let params = message.get_mut("params").map(Value::take);
+        if let Some(Ok(event)) = params.map(serde_json::from_value) {
+            self.events.push_back(event);
+        }
+        true
+    }
+}
+
+/// Connects to the game's JSON-RPC socket
+pub struct JsonRpcClient {
+    connection: Mutex<Connection>,
+}
+
+impl JsonRpcClient {
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_stream(UnixStream::connect(path)?)
+    }
+
+    pub fn from_stream(stream: UnixStream) -> io::Result<Self> {
+        Ok(Self {
+            connection: Mutex::new(Connection {
+                reader: BufReader::new(stream.try_clone()?),
+                writer: stream,
+                next_id: 1,
+                events: VecDeque::new(),
+            }),
+        })
+    }
+
+    /// Send a request and wait for its response, notifications read meanwhile are queued
+    fn request(&self, method: Value, params: Value) -> RemoteResult<Value> {
+        let mut connection = self.connection.lock().unwrap();
+
+        let id = connection.next_id;
+        connection.next_id += 1;
+
+        let request = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        serde_json::to_writer(&mut connection.writer, &request)?;
+        connection.writer.write_all(b"\n")?;
+
+        loop {
+            let mut message = connection.read_message()?;
+            if connection.queue_event(&mut message) || message["id"] != id {
+                continue;
+            }
+
+            if let Some(error) = message.get_mut("error") {
+                let data = error.get_mut("data").map(Value::take).unwrap_or_default();
+                return Err(match serde_json::from_value(data) {
+                    Ok(error) => error,
+                    Err(_) => RemoteError::Transport(
+                        error["message"].as_str().unwrap_or_default().to_owned(),
+                    ),
+                });
+            }
+
+            return Ok(message
+                .get_mut("result")
+                .map(Value::take)
+                .unwrap_or_default());
+        }
+    }
+
+    /// Ask the game to send inventory changes, read them with `next_event`
+    pub fn subscribe(&self) -> RemoteResult<()> {
+        self.request(json!(SUBSCRIBE_METHOD), Value::Null).map(|_| ())
+    }
+
+    /// Wait for the next inventory change, `subscribe` has to be called first
+    pub fn next_event(&self) -> RemoteResult<InventoryEvent> {
+        let mut connection = self.connection.lock().unwrap();
+
+        loop {
+            if let Some(event) = connection.events.pop_front() {
+                return Ok(event);
+            }
+
+            let mut message = connection.read_message()?;
+            connection.queue_event(&mut message);
+        }
+    }
+}
+
+impl RemoteClient for JsonRpcClient {
+    fn call(&self, command: RemoteCommand) -> RemoteResult<RemoteResponse> {
+        let mut value = serde_json::to_value(&command)?;
+        let result = self.request(value["method"].take(), value["params"].take())?;
+
+        Ok(command.parse_response(result)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::{channel::channel, info::ItemInfo};
+
+    use super::*;
+
+    #[test]
+    fn call_over_socket() {
+        let (client, receiver) = channel();
+        let (game_events, events) = mpsc::channel();
+        let events = Mutex::new(Some(events));
+        let subscribe: Subscribe = Arc::new(move || events.lock().unwrap().take().unwrap());
+
+        let (server_end, client_end) = UnixStream::pair().unwrap();
+        thread::spawn(move || serve_connection(server_end, client, subscribe));
+
+        thread::spawn(move || loop {
+            for request in receiver.drain() {
+                let result = match request.command {
+                    RemoteCommand::SpawnEntity { .. } => Ok(RemoteResponse::EntityId(7)),
+                    RemoteCommand::AddItem { .. } => {
+                        Err(RemoteError::InventoryFull("No room".to_owned()))
+                    }
+                    _ => Ok(RemoteResponse::Done),
+                };
+                request.respond(result);
+            }
+            thread::yield_now();
+        });
+
+        let remote = JsonRpcClient::from_stream(client_end).unwrap();
+
+        assert_eq!(remote.spawn_entity("Player", (10.0, 20.0)).unwrap(), 7);
+        assert_eq!(
+            remote.add_item("rope", 3),
+            Err(RemoteError::InventoryFull("No room".to_owned()))
+        );
+        remote.move_item(0, 1).unwrap();
+
+        remote.subscribe().unwrap();
+        let event = InventoryEvent {
+            kind: "Add".to_owned(),
+            items: vec![ItemInfo {
+                slot: 0,
+                ident: "rope".to_owned(),
+                name: "Rope".to_owned(),
+                count: 3,
+                tags: Vec::new(),
+                stats: Default::default(),
+            }],
+        };
+        game_events.send(event.clone()).unwrap();
+        assert_eq!(remote.next_event().unwrap(), event);
+    }
+
+    #[test]
+    fn invalid_request() {
+        let (client, _receiver) = channel();
+        let (server_end, client_end) = UnixStream::pair().unwrap();
+        let subscribe: Subscribe = Arc::new(|| mpsc::channel().1);
+        thread::spawn(move || serve_connection(server_end, client, subscribe));
+
+        let remote = JsonRpcClient::from_stream(client_end).unwrap();
+        let error = remote.request(json!("fly_to_the_moon"), Value::Null);
+
+        assert!(matches!(error, Err(RemoteError::Transport(_))));
+    }
+
+    #[test]
+    fn non_object_requests() {
+        let (client, _receiver) = channel();
+        let (server_end, mut client_end) = UnixStream::pair().unwrap();
+        let subscribe: Subscribe = Arc::new(|| mpsc::channel().1);
+        thread::spawn(move || serve_connection(server_end, client, subscribe));
+
+        let mut reader = BufReader::new(client_end.try_clone().unwrap());
+
+        // The connection keeps answering after every one of them
+        for request in ["[1,2]", "5", "\"subscribe\"", "null"] {
+            writeln!(client_end, "{}", request).unwrap();
+
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            let response: Value = serde_json::from_str(&line).unwrap();
+
+            assert_eq!(response["error"]["code"], INVALID_REQUEST);
+            assert_eq!(response["id"], Value::Null);
+        }
+    }
+
+    #[test]
+    fn malformed_error_response() {
+        let (server_end, client_end) = UnixStream::pair().unwrap();
+        thread::spawn(move || {
+            let mut writer = server_end.try_clone().unwrap();
+            let mut line = String::new();
+            BufReader::new(server_end).read_line(&mut line).unwrap();
+            let response = json!({ "jsonrpc": "2.0", "id": 1, "error": "boom" });
+            writeln!(writer, "{}", response).unwrap();
+        });
+
+        let remote = JsonRpcClient::from_stream(client_end).unwrap();
+
+        assert!(matches!(
+            remote.request(json!("list_entities"), Value::Null),
+            Err(RemoteError::Transport(_))
+        ));
+    }
+}
@@ -0,0 +1,13 @@
+//! The remote control protocol of the game, shared by the game and the tools that control it.
+//!
+//! Every command is a `RemoteCommand`, the game answers each one with a `RemoteResponse` or a
+//! `RemoteError`. Transports only move commands and answers around, so every command works the
+//! same over D-Bus, the JSON-RPC socket and the in-process channel
+
+pub mod channel;
+pub mod client;
+pub mod command;
+pub mod error;
+pub mod info;
+#[cfg(unix)]
+pub mod json_rpc;
@@ -0,0 +1,129 @@
+use std::sync::{Arc, Mutex};
+
+use bevy::ecs::{
+    component::Component,
+    entity::Entity,
+    query::Added,
+    system::{Commands, Query},
+};
+use bevy_godot4::prelude::ErasedGd;
+use ducttape_item_engine::{
+    item::{Item, ItemStack},
+    pickup::DroppedStack,
+};
+use godot::{
+    classes::{Area2D, RigidBody2D, TextureRect},
+    prelude::*,
+};
+use lazy_static::lazy_static;
+
+use crate::{
+    game_entities::GameEntity, player::Player, singletons::inventory::INVENTORY,
+    template::loader::set_item_texture,
+};
+
+/// An item lying in the world, picked up into the inventory when the player touches it
+#[derive(Component, Debug, Clone)]
+pub struct DroppedItem {
+    /// Shared with the pickup callback
+    stack: Arc<Mutex<DroppedStack>>,
+    velocity: Vector2,
+}
+
+impl DroppedItem {
+    pub fn new(stack: ItemStack, velocity: Vector2) -> Self {
+        Self {
+            stack: Arc::new(Mutex::new(DroppedStack::new(stack))),
+            velocity,
+        }
+    }
+
+    /// Get what is left of the dropped stack
+    pub fn get_stack(&self) -> Option<ItemStack> {
+        self.stack.lock().unwrap().get_stack().cloned()
+    }
+
+    pub fn is_picked_up(&self) -> bool {
+        self.stack.lock().unwrap().is_picked_up()
+    }
+
+    fn setup(&self, node: Gd<Node2D>) {
+        let mut body = node.cast::<RigidBody2D>();
+        body.set_linear_velocity(self.velocity);
+
+        if let Some(stack) = self.get_stack() {
+            if let Some(mut texture_rect) = body.try_get_node_as::<TextureRect>("Texture") {
+                if let Some(timer) = set_item_texture(&mut texture_rect, stack.get_texture()) {
+                    body.add_child(&timer);
+                }
+            }
+        }
+
+        let Some(mut pickup) = body.try_get_node_as::<Area2D>("Pickup") else {
+            return;
+        };
+
+        let stack = self.stack.clone();
+        pickup.connect(
+            "body_entered",
+            &Callable::from_local_fn("_on_pickup", move |args| {
+                let is_player = args
+                    .first()
+                    .is_some_and(|arg| arg.try_to::<Gd<Player>>().is_ok());
+
+                if is_player {
+                    let mut inventory = INVENTORY.lock().unwrap();
+                    if stack.lock().unwrap().pick_up(&mut *inventory) {
+                        body.queue_free();
+                    }
+                }
+                Ok(Variant::nil())
+            }),
+        );
+    }
+}
+
+lazy_static! {
+    /// Items waiting for their entity, drained by `dropped_item_system`
+    static ref DROP_QUEUE: Mutex<Vec<(DroppedItem, Vector2)>> = Mutex::new(Vec::new());
+}
+
+/// Put a stack into the world at `position`
+pub fn drop_item(stack: ItemStack, position: Vector2, velocity: Vector2) {
+    let dropped = DroppedItem::new(stack, velocity);
+    if dropped.get_stack().is_none() {
+        return;
+    }
+
+    DROP_QUEUE.lock().unwrap().push((dropped, position));
+}
+
+/// Spawn the entities of the dropped items
+pub fn dropped_item_system(mut commands: Commands) {
+    for (dropped, position) in DROP_QUEUE.lock().unwrap().drain(..) {
+        match GameEntity::DroppedItem.spawn_with_position(&mut commands, position) {
+            Ok(entity) => {
+                commands.entity(entity).insert(dropped);
+            }
+            Err(e) => godot_print!("Failed to spawn a dropped item: {}", e),
+        }
+    }
+}
+
+/// Despawn the entities of dropped items that were picked up, their nodes free themselves
+pub fn despawn_picked_up_system(mut commands: Commands, query: Query<(Entity, &DroppedItem)>) {
+    for (entity, dropped) in query.iter() {
+        if dropped.is_picked_up() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Place the nodes of dropped items once their scene is in the tree
+pub fn setup_dropped_item_system(
+    mut query: Query<(&mut ErasedGd, &DroppedItem), Added<ErasedGd>>,
+) {
+    for (mut node, dropped) in query.iter_mut() {
+        dropped.setup(node.get::<Node2D>());
+    }
+}
@@ -1,10 +1,36 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Mutex, RwLock},
+};
+
 use bevy::{
-    ecs::{entity::Entity, system::Commands},
+    ecs::{
+        component::Component,
+        entity::Entity,
+        system::{Commands, EntityCommands},
+    },
     math::Vec3,
     transform::components::Transform,
 };
-use bevy_godot4::prelude::{ErasedGdResource, GodotScene};
-use godot::{classes::Resource, prelude::*, tools::load};
+use bevy_godot4::prelude::{ErasedGd, ErasedGdResource, GodotScene};
+use ducttape_item_engine::{item::ItemStack, template::source::AssetSource};
+use godot::{
+    classes::{IMarker2D, Marker2D, Resource},
+    prelude::*,
+    tools::try_load,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dropped_item::DroppedItem,
+    singletons::inventory::ITEM_REGISTRY,
+    template::loader::asset_source,
+    transform_sync::TransformSync,
+};
+
+/// Extra entity kinds, relative to `res://assets/`
+const ENTITIES_FILE: &str = "entities.toml";
 
 macro_rules! spawnable {
     ($name:ident) => {
@@ -13,54 +39,375 @@ macro_rules! spawnable {
                 use super::*;
 
                 pub fn spawn(mut commands: Commands) {
-                    super::GameEntity::$name.spawn(&mut commands);
+                    if let Err(e) = super::GameEntity::$name.spawn(&mut commands) {
+                        godot_print!("Failed to spawn {}: {}", super::GameEntity::$name, e);
+                    }
                 }
             }
         }
     };
 }
 
-#[derive(
-    Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize, zbus::zvariant::Type,
-)]
-pub enum GameEntity {
-    Player,
+/// The kind of an entity, any kind registered in the `ENTITY_REGISTRY` can be spawned.
+///
+/// Spawned entities keep their kind as a component
+#[derive(Component, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GameEntity(&'static str);
+
+#[allow(non_upper_case_globals)]
+impl GameEntity {
+    pub const Player: GameEntity = GameEntity("Player");
+    /// The hitbox of a melee swing, frees itself after a moment
+    pub const Swing: GameEntity = GameEntity("Swing");
+    pub const ThrownItem: GameEntity = GameEntity("ThrownItem");
+    pub const Bridge: GameEntity = GameEntity("Bridge");
+    pub const Rope: GameEntity = GameEntity("Rope");
+    /// An item stack lying in the world, see `dropped_item`
+    pub const DroppedItem: GameEntity = GameEntity("DroppedItem");
 }
 
 spawnable!(Player);
 
+lazy_static! {
+    static ref INTERNED_KINDS: Mutex<HashSet<&'static str>> = Mutex::new(HashSet::new());
+}
+
 impl GameEntity {
-    fn get_scene(&self) -> Gd<Resource> {
+    /// Get the entity for a kind, the kind doesn't have to be registered
+    pub fn new(kind: &str) -> Self {
+        let mut interned = INTERNED_KINDS.lock().unwrap();
+
+        match interned.get(kind) {
+            Some(kind) => GameEntity(*kind),
+            None => {
+                let kind: &'static str = Box::leak(kind.to_owned().into_boxed_str());
+                interned.insert(kind);
+                GameEntity(kind)
+            }
+        }
+    }
+
+    pub fn get_kind(&self) -> &'static str {
+        self.0
+    }
+
+    /// Get the registered definition of this kind
+    pub fn get_definition(&self) -> EntityResult<EntityDefinition> {
+        ENTITY_REGISTRY
+            .read()
+            .unwrap()
+            .get(*self)
+            .cloned()
+            .ok_or(EntityError::UnknownKind(*self))
+    }
+
+    pub fn spawn(&self, commands: &mut Commands) -> EntityResult<Entity> {
+        self.spawn_with_transform(commands, Transform::IDENTITY)
+    }
+
+    pub fn spawn_with_position(
+        &self,
+        commands: &mut Commands,
+        position: Vector2,
+    ) -> EntityResult<Entity> {
+        let transform = Transform::from_translation(Vec3::new(position.x, position.y, 0.0));
+        self.spawn_with_transform(commands, transform)
+    }
+
+    /// Spawn the entity with its node placed at `transform`, see `TransformSync`
+    pub fn spawn_with_transform(
+        &self,
+        commands: &mut Commands,
+        transform: Transform,
+    ) -> EntityResult<Entity> {
+        let definition = self.get_definition()?;
+        let scene = try_load::<Resource>(definition.scene.as_str())
+            .map_err(|_| EntityError::MissingScene(definition.scene.clone()))?;
+
+        let mut entity = commands.spawn((
+            GodotScene::from_resource(ErasedGdResource::new(scene)),
+            *self,
+            transform,
+            definition.transform,
+        ));
+
+        for component in definition.components.iter() {
+            component.insert(&mut entity);
+        }
+
+        Ok(entity.id())
+    }
+}
+
+impl std::fmt::Debug for GameEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::fmt::Display for GameEntity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Serialize for GameEntity {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameEntity {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let kind = String::deserialize(deserializer)?;
+        Ok(GameEntity::new(&kind))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EntityError {
+    /// No definition is registered for the kind
+    UnknownKind(GameEntity),
+    /// The scene of the definition can't be loaded
+    MissingScene(String),
+}
+
+impl std::fmt::Display for EntityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            GameEntity::Player => load::<Resource>("res://Player.tscn"),
+            EntityError::UnknownKind(kind) => write!(f, "Unknown entity kind: {}", kind),
+            EntityError::MissingScene(scene) => write!(f, "Failed to load entity scene: {}", scene),
         }
     }
+}
+
+impl std::error::Error for EntityError {}
+
+pub type EntityResult<T> = Result<T, EntityError>;
+
+/// Get the node of an entity, `None` once the node was freed on the Godot side
+pub fn try_get_node(node: &ErasedGd) -> Option<Gd<Node2D>> {
+    Gd::try_from_instance_id(node.instance_id()).ok()
+}
 
-    pub fn spawn(&self, commands: &mut Commands) -> Entity {
-        let entity = commands.spawn(GodotScene::from_resource(ErasedGdResource::new(
-            self.get_scene(),
-        )));
+/// Tags of an entity, e.g. `"enemy"` or `"puzzle"`
+#[derive(Component, Debug, Clone, Default, PartialEq)]
+pub struct EntityTags(pub Vec<String>);
 
-        entity.id()
+/// A component every entity of a kind starts with
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum EntityComponent {
+    Tags(Vec<String>),
+    /// Makes the entity an item pickup, the scene should be set up like a dropped item
+    Item { ident: String, count: u32 },
+}
+
+impl EntityComponent {
+    fn insert(&self, entity: &mut EntityCommands) {
+        match self {
+            EntityComponent::Tags(tags) => {
+                entity.insert(EntityTags(tags.clone()));
+            }
+            EntityComponent::Item { ident, count } => {
+                let Some(item) = ITEM_REGISTRY.lock().unwrap().get(ident).cloned() else {
+                    godot_print!("Unknown item in entity components: {}", ident);
+                    return;
+                };
+
+                entity.insert(DroppedItem::new(ItemStack::new(item, *count), Vector2::ZERO));
+            }
+        }
+    }
+}
+
+/// How an entity kind is spawned
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityDefinition {
+    pub kind: GameEntity,
+    /// Path of the scene instanced for the entity, e.g. `res://entities/Rope.tscn`
+    pub scene: String,
+    /// Physics bodies moved by Godot should use `TransformSync::Godot`
+    #[serde(default)]
+    pub transform: TransformSync,
+    #[serde(default)]
+    pub components: Vec<EntityComponent>,
+}
+
+impl EntityDefinition {
+    pub fn new(kind: GameEntity, scene: &str) -> Self {
+        Self {
+            kind,
+            scene: scene.to_owned(),
+            transform: TransformSync::default(),
+            components: Vec::new(),
+        }
+    }
+
+    pub fn with_transform_sync(mut self, transform: TransformSync) -> Self {
+        self.transform = transform;
+        self
+    }
+
+    pub fn with_component(mut self, component: EntityComponent) -> Self {
+        self.components.push(component);
+        self
+    }
+}
+
+/// The contents of an entity file, one `[[entity]]` table per kind
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EntityDefinitionFile {
+    #[serde(default)]
+    pub entity: Vec<EntityDefinition>,
+}
+
+/// Every entity kind that can be spawned by name
+#[derive(Debug, Clone, Default)]
+pub struct EntityRegistry {
+    definitions: HashMap<GameEntity, EntityDefinition>,
+}
+
+impl EntityRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry holding the entities the game code spawns itself
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+
+        registry.register(
+            EntityDefinition::new(GameEntity::Player, "res://Player.tscn")
+                .with_transform_sync(TransformSync::Godot),
+        );
+        registry.register(EntityDefinition::new(
+            GameEntity::Swing,
+            "res://entities/Swing.tscn",
+        ));
+        registry.register(
+            EntityDefinition::new(GameEntity::ThrownItem, "res://entities/ThrownItem.tscn")
+                .with_transform_sync(TransformSync::Godot),
+        );
+        registry.register(EntityDefinition::new(
+            GameEntity::Bridge,
+            "res://entities/Bridge.tscn",
+        ));
+        registry.register(EntityDefinition::new(
+            GameEntity::Rope,
+            "res://entities/Rope.tscn",
+        ));
+        registry.register(
+            EntityDefinition::new(GameEntity::DroppedItem, "res://entities/DroppedItem.tscn")
+                .with_transform_sync(TransformSync::Godot),
+        );
+
+        registry
     }
 
-    pub fn spawn_with_position(&self, commands: &mut Commands, position: Vector2) -> Entity {
-        let mut entity = commands.spawn(GodotScene::from_resource(ErasedGdResource::new(
-            self.get_scene(),
-        )));
+    /// Add a kind, registering a kind again replaces its definition
+    pub fn register(&mut self, definition: EntityDefinition) {
+        self.definitions.insert(definition.kind, definition);
+    }
+
+    pub fn get(&self, kind: GameEntity) -> Option<&EntityDefinition> {
+        self.definitions.get(&kind)
+    }
+
+    pub fn contains(&self, kind: GameEntity) -> bool {
+        self.definitions.contains_key(&kind)
+    }
+
+    /// Get every registered kind, sorted by name
+    pub fn get_kinds(&self) -> Vec<GameEntity> {
+        let mut kinds: Vec<GameEntity> = self.definitions.keys().copied().collect();
+        kinds.sort();
+        kinds
+    }
 
-        entity
-            .commands()
-            .spawn(Transform::from_translation(Vec3::new(
-                position.x, position.y, 0.0,
-            )));
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    /// Register every kind of an entity file, returns the registered kinds
+    pub fn load_toml(&mut self, toml: &str) -> Result<Vec<GameEntity>, toml::de::Error> {
+        let file: EntityDefinitionFile = toml::from_str(toml)?;
+
+        Ok(file
+            .entity
+            .into_iter()
+            .map(|definition| {
+                let kind = definition.kind;
+                self.register(definition);
+                kind
+            })
+            .collect())
+    }
+
+    pub fn load(
+        &mut self,
+        source: &dyn AssetSource,
+        path: &str,
+    ) -> Result<Vec<GameEntity>, Box<dyn std::error::Error>> {
+        Ok(self.load_toml(&source.read_to_string(path)?)?)
+    }
+}
+
+fn create_entity_registry() -> EntityRegistry {
+    let mut registry = EntityRegistry::with_builtins();
+
+    match registry.load(&*asset_source(), ENTITIES_FILE) {
+        Ok(kinds) => godot_print!("Registered entities: {:?}", kinds),
+        Err(e) => godot_print!("Failed to load entities: {}", e),
+    }
+
+    registry
+}
+
+lazy_static! {
+    /// The entity kinds known to the game, the built-ins and the ones in `entities.toml`
+    pub static ref ENTITY_REGISTRY: RwLock<EntityRegistry> =
+        RwLock::new(create_entity_registry());
+    /// Entities requested outside of bevy systems, drained by `spawn_request_system`
+    static ref SPAWN_REQUESTS: Mutex<Vec<(GameEntity, Vector2)>> = Mutex::new(Vec::new());
+}
+
+/// Spawn an entity from anywhere, e.g. from a Godot node or a remote, on the next physics frame
+pub fn request_spawn(kind: GameEntity, position: Vector2) {
+    SPAWN_REQUESTS.lock().unwrap().push((kind, position));
+}
+
+pub fn spawn_request_system(mut commands: Commands) {
+    for (kind, position) in SPAWN_REQUESTS.lock().unwrap().drain(..) {
+        if let Err(e) = kind.spawn_with_position(&mut commands, position) {
+            godot_print!("Failed to spawn {}: {}", kind, e);
+        }
+    }
+}
+
+/// Place in a level to spawn an entity of a registered kind where the marker is
+#[derive(GodotClass)]
+#[class(base = Marker2D)]
+pub struct EntitySpawner {
+    base: Base<Marker2D>,
+    #[export]
+    kind: GString,
+}
+
+#[godot_api]
+impl IMarker2D for EntitySpawner {
+    fn init(base: Base<Marker2D>) -> Self {
+        Self {
+            base,
+            kind: GString::new(),
+        }
+    }
 
-        entity.id()
-        // .insert_resource(Transform::from_translation(Vec3::new(
-        //     position.0, position.1, 0.0,
-        // )));
-        // .insert_bundle((Transform::from_translation(Vec3::new(
-        //     position.0, position.1, 0.0,
-        // )),));
+    fn ready(&mut self) {
+        let kind = GameEntity::new(&self.kind.to_string());
+        request_spawn(kind, self.base().get_global_position());
     }
 }
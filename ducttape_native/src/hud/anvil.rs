@@ -0,0 +1,392 @@
+use std::{collections::BTreeMap, sync::mpsc};
+
+use ducttape_item_engine::{
+    crafting::Recipe,
+    item::{Item, ItemCollectionEvent, ItemStack},
+};
+use godot::{
+    classes::{
+        control::SizeFlags, texture_rect::{ExpandMode, StretchMode}, Button, Control, HBoxContainer, IControl, IPanel, InputEvent, Label, Panel, ResourceLoader, StyleBoxFlat, Texture2D, TextureRect, VBoxContainer
+    }, global::HorizontalAlignment, obj::NewAlloc, prelude::*
+};
+
+use crate::{
+    hud::inventory::{CenterAnchor as _, InventoryItem, InventoryItemSlot, StatDisplay, StatsContainer, ITEM_DROP_TARGET_GROUP},
+    singletons::{crafting::RECIPE_BOOK, inventory::{INVENTORY, ITEM_REGISTRY}},
+    template::loader::set_item_texture,
+};
+
+const ANVIL_TEXTURE: &str = "res://assets/gui/anvil/anvil.png";
+const EMPTY_OVERLAY_TEXTURE: &str = "res://assets/gui/anvil/overlay_empty.png";
+const SLOT_TEXTURE: &str = "res://assets/gui/slot.png";
+const ANVIL_SIZE: Vector2 = Vector2::new(160.0, 160.0);
+
+fn load_texture(path: &str) -> Option<Gd<Texture2D>> {
+    let mut loader = ResourceLoader::singleton();
+
+    if !loader.exists(path) {
+        return None;
+    }
+
+    loader.load(path).and_then(|resource| resource.try_cast::<Texture2D>().ok())
+}
+
+/// A single component of the anvil's template, accepts items dragged out of the inventory
+#[derive(GodotClass)]
+#[class(base = Control)]
+pub struct AnvilComponentSlot {
+    base: Base<Control>,
+    component: GString,
+    texture_rect: Option<Gd<TextureRect>>,
+}
+
+#[godot_api]
+impl AnvilComponentSlot {
+    #[signal]
+    fn component_changed(&self, component: GString, index: i32);
+
+    #[func]
+    pub fn set_component(&mut self, component: GString) {
+        self.component = component;
+    }
+
+    #[func]
+    pub fn get_component(&self) -> GString {
+        self.component.clone()
+    }
+
+    /// Called for every item dropped from the inventory, see `ITEM_DROP_TARGET_GROUP`
+    #[func]
+    fn _on_item_drop(&mut self, item: Gd<InventoryItem>, pos: Vector2) {
+        if !self.base().is_visible_in_tree() || !self.base().get_global_rect().contains_point(pos) {
+            return;
+        }
+
+        let Some(slot) = item.get_parent().and_then(|parent| parent.try_cast::<InventoryItemSlot>().ok()) else {
+            return;
+        };
+
+        let index = slot.bind().get_slot_index();
+        let component = self.component.clone();
+
+        self.set_stack(Some(item.bind().get_stack()));
+        self.base_mut().emit_signal("component_changed", &[component.to_variant(), index.to_variant()]);
+    }
+
+    /// Show the item assigned to this component, or nothing
+    pub fn set_stack(&mut self, stack: Option<ItemStack>) {
+        let Some(mut texture_rect) = self.texture_rect.clone() else {
+            return;
+        };
+
+        texture_rect.get_children().iter_shared().for_each(|mut child| {
+            child.queue_free();
+        });
+
+        match stack {
+            Some(stack) => {
+                if let Some(timer) = set_item_texture(&mut texture_rect, stack.get_texture()) {
+                    texture_rect.add_child(&timer);
+                }
+            }
+            None => texture_rect.set_texture(Gd::<Texture2D>::null_arg()),
+        }
+    }
+}
+
+#[godot_api]
+impl IControl for AnvilComponentSlot {
+    fn init(base: Base<Control>) -> Self {
+        Self {
+            base,
+            component: GString::new(),
+            texture_rect: None,
+        }
+    }
+
+    fn ready(&mut self) {
+        let mut base = self.base_mut();
+        base.set_custom_minimum_size(Vector2::new(32.0, 32.0));
+        base.add_to_group(ITEM_DROP_TARGET_GROUP);
+
+        if let Some(slot_texture) = load_texture(SLOT_TEXTURE) {
+            let mut background = TextureRect::new_alloc();
+            background.set_texture(&slot_texture);
+            background.set_custom_minimum_size(Vector2::new(32.0, 32.0));
+            base.add_child(&background);
+        }
+
+        let mut texture_rect = TextureRect::new_alloc();
+        texture_rect.set_custom_minimum_size(Vector2::new(32.0, 32.0));
+        base.add_child(&texture_rect);
+
+        drop(base);
+
+        self.texture_rect = Some(texture_rect);
+    }
+}
+
+/// The crafting HUD, lets the player fill the components of a template with items from the
+/// inventory and previews the result before committing the craft
+#[derive(GodotClass)]
+#[class(base = Panel)]
+pub struct Anvil {
+    base: Base<Panel>,
+    /// The template (and recipe) this anvil crafts
+    #[export]
+    template: GString,
+    /// Inventory slot used for each component
+    inputs: BTreeMap<String, usize>,
+    slots: Vec<Gd<AnvilComponentSlot>>,
+    preview: Option<Gd<TextureRect>>,
+    stats: Option<Gd<StatsContainer>>,
+    status: Option<Gd<Label>>,
+    receiver: Option<mpsc::Receiver<ItemCollectionEvent>>,
+}
+
+#[godot_api]
+impl Anvil {
+    #[func]
+    fn _on_component_changed(&mut self, component: GString, index: i32) {
+        self.inputs.insert(component.to_string(), index as usize);
+        self.update_preview();
+    }
+
+    /// Take the inputs out of the inventory and put the crafted item back in
+    #[func]
+    fn craft(&mut self) {
+        let Some(recipe) = self.get_recipe() else {
+            return;
+        };
+
+        let result = {
+            let mut registry = ITEM_REGISTRY.lock().unwrap();
+            let mut inventory = INVENTORY.lock().unwrap();
+
+            recipe.craft_into(&mut *inventory, &mut registry, &self.inputs)
+        };
+
+        match result {
+            Ok(_) => {
+                self.clear();
+                self.set_status(&format!("Crafted {}", recipe.get_name()));
+            }
+            Err(e) => self.set_status(&e.to_string()),
+        }
+    }
+
+    /// Forget every assigned component
+    #[func]
+    pub fn clear(&mut self) {
+        self.inputs.clear();
+
+        for slot in self.slots.iter_mut() {
+            slot.bind_mut().set_stack(None);
+        }
+
+        self.update_preview();
+    }
+
+    fn get_recipe(&self) -> Option<&'static Recipe> {
+        RECIPE_BOOK.get(&self.template.to_string())
+    }
+
+    fn set_status(&mut self, text: &str) {
+        if let Some(status) = self.status.as_mut() {
+            status.set_text(text);
+        }
+    }
+
+    /// Render the composited texture and stats of whatever the current inputs would craft
+    fn update_preview(&mut self) {
+        let Some(recipe) = self.get_recipe() else {
+            return;
+        };
+
+        let result = {
+            let registry = ITEM_REGISTRY.lock().unwrap();
+            let inventory = INVENTORY.lock().unwrap();
+
+            recipe.preview(&*inventory, &registry, &self.inputs)
+        };
+
+        let (Some(mut preview), Some(mut stats)) = (self.preview.clone(), self.stats.clone()) else {
+            return;
+        };
+
+        preview.get_children().iter_shared().for_each(|mut child| {
+            child.queue_free();
+        });
+
+        match result {
+            Ok(item) => {
+                if let Some(timer) = set_item_texture(&mut preview, item.get_texture()) {
+                    preview.add_child(&timer);
+                }
+
+                stats.bind_mut().set_item(ItemStack::new(item, 1));
+                stats.bind_mut().set_display(StatDisplay::Summary);
+                self.set_status("");
+            }
+            Err(e) => {
+                preview.set_texture(Gd::<Texture2D>::null_arg());
+                stats.bind_mut().clear();
+
+                // Nothing assigned yet isn't worth complaining about
+                let status = if self.inputs.is_empty() { String::new() } else { e.to_string() };
+                self.set_status(&status);
+            }
+        }
+
+        stats.emit_signal("request_rerender", &[]);
+    }
+
+    /// Build one component slot per component of the recipe, named by their tooltips
+    fn build_component_slots(&mut self, container: &mut Gd<VBoxContainer>) {
+        let Some(recipe) = self.get_recipe() else {
+            godot_print!("No recipe for anvil template {}", self.template);
+            return;
+        };
+
+        let self_gd = self.to_gd();
+
+        self.slots = recipe.get_components().keys().map(|component| {
+            let mut slot = AnvilComponentSlot::new_alloc();
+            slot.bind_mut().set_component(component.into());
+            slot.set_tooltip_text(component.as_str());
+            slot.connect("component_changed", &Callable::from_object_method(&self_gd, "_on_component_changed"));
+
+            container.add_child(&slot);
+
+            slot
+        }).collect();
+    }
+}
+
+#[godot_api]
+impl IPanel for Anvil {
+    fn init(base: Base<Panel>) -> Self {
+        Self {
+            base,
+            template: "spear".into(),
+            inputs: BTreeMap::new(),
+            slots: vec![],
+            preview: None,
+            stats: None,
+            status: None,
+            receiver: None,
+        }
+    }
+
+    fn ready(&mut self) {
+        let template = self.template.clone();
+        let self_gd = self.to_gd();
+        let mut base = self.base_mut();
+        base.set_visible(false);
+        // Sits to the left of the inventory so items can be dragged across
+        base.center_anchor(ANVIL_SIZE);
+        base.set_offset(Side::LEFT, -ANVIL_SIZE.x * 1.5);
+        base.set_offset(Side::RIGHT, -ANVIL_SIZE.x * 0.5);
+
+        let mut style_box = StyleBoxFlat::new_gd();
+        style_box.set_bg_color(Color::from_rgba(0.0, 0.0, 0.0, 0.0));
+        base.add_theme_stylebox_override("panel", &style_box);
+
+        // The anvil itself, with the outline of the template's components drawn over it
+        let overlay_path = format!("res://assets/gui/anvil/overlay_{}.png", template);
+        let overlay_texture = load_texture(&overlay_path).or_else(|| load_texture(EMPTY_OVERLAY_TEXTURE));
+
+        for texture in [load_texture(ANVIL_TEXTURE), overlay_texture].into_iter().flatten() {
+            let mut gui = TextureRect::new_alloc();
+            gui.set_texture(&texture);
+            gui.center_anchor(ANVIL_SIZE);
+            base.add_child(&gui);
+        }
+
+        let mut content_box = VBoxContainer::new_alloc();
+        content_box.set_anchor_and_offset(Side::TOP, 0.0, 8.0);
+        content_box.set_anchor_and_offset(Side::LEFT, 0.0, 8.0);
+        content_box.set_anchor_and_offset(Side::RIGHT, 1.0, -8.0);
+        content_box.set_anchor_and_offset(Side::BOTTOM, 1.0, -8.0);
+        content_box.add_theme_constant_override("separation", 2);
+
+        base.add_child(&content_box);
+
+        let mut input_box = HBoxContainer::new_alloc();
+        input_box.add_theme_constant_override("separation", 4);
+
+        content_box.add_child(&input_box);
+
+        let mut components = VBoxContainer::new_alloc();
+        components.add_theme_constant_override("separation", 4);
+
+        input_box.add_child(&components);
+
+        let mut preview = TextureRect::new_alloc();
+        preview.set_custom_minimum_size(Vector2::new(64.0, 64.0));
+        preview.set_h_size_flags(SizeFlags::EXPAND_FILL);
+        preview.set_expand_mode(ExpandMode::IGNORE_SIZE);
+        preview.set_stretch_mode(StretchMode::KEEP_ASPECT_CENTERED);
+
+        input_box.add_child(&preview);
+
+        let stats = StatsContainer::new_alloc();
+        content_box.add_child(&stats);
+
+        let mut status = Label::new_alloc();
+        status.add_theme_font_size_override("font_size", 8);
+        status.set_horizontal_alignment(HorizontalAlignment::CENTER);
+        content_box.add_child(&status);
+
+        let mut button_box = HBoxContainer::new_alloc();
+        button_box.add_theme_constant_override("separation", 4);
+
+        content_box.add_child(&button_box);
+
+        for (text, method) in [("Craft", "craft"), ("Clear", "clear")] {
+            let mut button = Button::new_alloc();
+            button.set_text(text);
+            button.set_h_size_flags(SizeFlags::EXPAND_FILL);
+            button.add_theme_font_size_override("font_size", 8);
+            button.connect("pressed", &Callable::from_object_method(&self_gd, method));
+            button_box.add_child(&button);
+        }
+
+        drop(base);
+
+        self.build_component_slots(&mut components);
+
+        self.preview = Some(preview);
+        self.stats = Some(stats);
+        self.status = Some(status);
+
+        // Inputs are inventory slots, so the preview has to follow the inventory
+        let (sender, receiver) = mpsc::channel();
+
+        INVENTORY.lock().unwrap().listen(Box::new(move |event| {
+            let _ = sender.send(event);
+        }));
+
+        self.receiver = Some(receiver);
+    }
+
+    fn process(&mut self, _delta: f64) {
+        if let Some(receiver) = self.receiver.take() {
+            let changed = receiver.try_iter().count() > 0;
+            self.receiver = Some(receiver);
+
+            if changed && !self.inputs.is_empty() {
+                self.update_preview();
+            }
+        }
+    }
+
+    fn input(&mut self, evt: Gd<InputEvent>) {
+        if evt.is_action_pressed("ui_anvil") {
+            let mut base = self.base_mut();
+
+            let visible = base.is_visible();
+            base.set_visible(!visible);
+        }
+    }
+}
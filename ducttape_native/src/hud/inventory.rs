@@ -1,6 +1,6 @@
 use std::sync::{mpsc, Arc, Mutex};
 
-use ducttape_item_engine::{attribute::{AttributeParser, AttributeType, ALL_ATTRIBUTE_TYPES}, item::{Item, ItemCollection, ItemCollectionEvent, ItemStack, ItemTexture}, prelude_items::{air::Air, MISSING_TEXTURE}, text_renderer::bbcode_renderer::BBCodeRenderer as _};
+use ducttape_item_engine::{attribute::{schema::all_attribute_types, AttributeParser, AttributeType}, item::{Item, ItemCollection, ItemCollectionEvent, ItemStack}, prelude_items::air::Air, text_renderer::bbcode_renderer::BBCodeRenderer as _};
 use godot::{
     classes::{
         control::{MouseFilter, SizeFlags}, Control, GridContainer, HBoxContainer, IControl, IGridContainer, IPanel, IRichTextLabel, IScrollContainer, InputEvent, InputEventMouseButton, InputEventMouseMotion, Label, MarginContainer, Panel, ResourceLoader, RichTextLabel, ScrollContainer, StyleBoxFlat, Texture2D, TextureRect, VBoxContainer
@@ -8,7 +8,13 @@ use godot::{
 };
 use valence_text::IntoText;
 
-use crate::{singletons::inventory::INVENTORY, template::loader::{anim_to_texture, image_to_texture}};
+use crate::{dropped_item::drop_item, player::{Player, PLAYER_GROUP}, singletons::{context::attribute_context, equipment::EQUIPMENT, inventory::INVENTORY}, template::loader::set_item_texture};
+
+/// Nodes in this group get `_on_item_drop(item, pos)` called whenever an inventory item is dropped
+pub const ITEM_DROP_TARGET_GROUP: &str = "item_drop_targets";
+/// How far in front of the player items dropped out of the inventory land
+const DROP_DISTANCE: f32 = 16.0;
+const DROP_SPEED: f32 = 60.0;
 
 #[derive(GodotClass)]
 #[class(base = GridContainer)]
@@ -50,6 +56,15 @@ impl InventoryContainer {
         for slot in self.slots.iter_mut() {
             slot.emit_signal("item_slot_drag_end", &[item.to_variant(), pos.to_variant()]);
         }
+
+        // Let other parts of the HUD (like the anvil) accept items dragged out of the grid
+        if let Some(mut tree) = self.base().get_tree() {
+            tree.call_group(ITEM_DROP_TARGET_GROUP, "_on_item_drop", &[item.to_variant(), pos.to_variant()]);
+
+            if !self.is_over_inventory(pos) && !is_over_drop_target(&mut tree, pos) {
+                self.drop_into_world(&mut tree, item);
+            }
+        }
     }
 
     #[func]
@@ -97,6 +112,49 @@ impl InventoryContainer {
     fn request_rerender(&self);
 }
 
+impl InventoryContainer {
+    /// Check if a position is on the inventory panel this grid is in
+    fn is_over_inventory(&self, pos: Vector2) -> bool {
+        let mut node = self.base().get_parent();
+
+        while let Some(parent) = node {
+            if let Ok(panel) = parent.clone().try_cast::<Inventory>() {
+                return panel.get_global_rect().contains_point(pos);
+            }
+            node = parent.get_parent();
+        }
+
+        self.base().get_global_rect().contains_point(pos)
+    }
+
+    /// Take the dragged stack out of the inventory and drop it in front of the player
+    fn drop_into_world(&self, tree: &mut Gd<SceneTree>, item: Gd<InventoryItem>) {
+        let Some(slot) = item.get_parent().and_then(|parent| parent.try_cast::<InventoryItemSlot>().ok()) else {
+            return;
+        };
+        let Some(player) = tree.get_first_node_in_group(PLAYER_GROUP).and_then(|node| node.try_cast::<Player>().ok()) else {
+            return;
+        };
+
+        let Ok(stack) = INVENTORY.lock().unwrap().remove_item(slot.bind().get_slot_index() as usize) else {
+            return;
+        };
+
+        let facing = player.bind().get_facing();
+        let position = player.get_global_position() + Vector2::new(facing * DROP_DISTANCE, 0.0);
+
+        drop_item(stack, position, Vector2::new(facing * DROP_SPEED, -DROP_SPEED));
+    }
+}
+
+/// Check if a visible drop target, like the anvil, is under a position
+fn is_over_drop_target(tree: &mut Gd<SceneTree>, pos: Vector2) -> bool {
+    tree.get_nodes_in_group(ITEM_DROP_TARGET_GROUP)
+        .iter_shared()
+        .filter_map(|node| node.try_cast::<Control>().ok())
+        .any(|control| control.is_visible_in_tree() && control.get_global_rect().contains_point(pos))
+}
+
 #[godot_api]
 impl IGridContainer for InventoryContainer {
     fn init(base: Base<GridContainer>) -> Self {
@@ -213,6 +271,12 @@ impl InventoryItemSlot {
                     godot_print!("New item slot number: {:?}", new_slot_number);
 
                     let mut inventory = INVENTORY.lock().unwrap();
+
+                    // Dropping onto the same kind of item tops up that stack instead of swapping
+                    if inventory.merge(old_slot_number as usize, new_slot_number as usize).is_ok() {
+                        return;
+                    }
+
                     let items = inventory.get_items_mut();
 
                     let (left, right) = if old_slot_number < new_slot_number {
@@ -279,6 +343,10 @@ impl InventoryItem {
         })
     }
 
+    pub fn get_stack(&self) -> ItemStack {
+        self.item.clone()
+    }
+
     #[signal]
     fn change_active_state(&self, active: bool);
 
@@ -411,20 +479,9 @@ impl IControl for InventoryItem {
             texture_rect.set_h_size_flags(SizeFlags::EXPAND_FILL);
             texture_rect.set_v_size_flags(SizeFlags::EXPAND_FILL);
 
-            match item.get_texture() {
-                ItemTexture::None => {
-                    texture_rect.set_texture(&image_to_texture(MISSING_TEXTURE.clone()).unwrap());
-                },
-                ItemTexture::Image(img) => {
-                    texture_rect.set_texture(&image_to_texture(img).unwrap());
-                }
-                ItemTexture::Animated(atlas, frame_properties, animation_type) => {
-                    let (texture, timer) = anim_to_texture(atlas, frame_properties, animation_type).unwrap();
-                    
-                    texture_rect.set_texture(&texture);
-                    self.base_mut().add_child(&timer);
-                }
-            };
+            if let Some(timer) = set_item_texture(&mut texture_rect, item.get_texture()) {
+                self.base_mut().add_child(&timer);
+            }
     
             self.base_mut().add_child(&texture_rect.clone());
             self.texture_rect = Some(texture_rect.clone());
@@ -470,7 +527,10 @@ impl StatsContainer {
 
     #[func]
     fn _on_request_rerender(&mut self) {
-        let item = self.item.clone().unwrap();
+        let Some(item) = self.item.clone() else {
+            self.base_mut().set_text("");
+            return;
+        };
         let display = self.display.clone();
 
         let mut base = self.base_mut();
@@ -481,12 +541,12 @@ impl StatsContainer {
                 base.set_text("");
             },
             StatDisplay::Summary => {
-                let text = AttributeParser::from(item.get_stats().get_all_attributes()).into_text();
+                let text = AttributeParser::from(item.get_stats().get_all_attributes()).with_context(attribute_context()).into_text();
 
                 base.set_text(&text.to_bbcode_string());
             },
             StatDisplay::Attribute(attr) => {
-                let text = AttributeParser::from(item.get_stats().get_all_attributes()).aggregate_to_component(attr).into_text();
+                let text = AttributeParser::from(item.get_stats().get_all_attributes()).with_context(attribute_context()).aggregate_to_component(attr).into_text();
 
                 base.set_text(&text.to_bbcode_string());
             }
@@ -584,7 +644,7 @@ impl StatsScroller {
         container.add_child(&summary);
 
         // clear the selected display and repopulate it with the the new display options (Options may be grayed out if the item does not have the attribute)
-        ALL_ATTRIBUTE_TYPES.iter().for_each(|attr| {
+        all_attribute_types().iter().for_each(|attr| {
             // if the item has the attribute, add it to the display
             // if the item does not have the attribute, gray it out
 
@@ -595,6 +655,7 @@ impl StatsScroller {
             label.set_vertical_alignment(VerticalAlignment::CENTER);
             label.set_horizontal_alignment(HorizontalAlignment::CENTER);
             label.set_text(&attr.into_text().to_bbcode_string());
+            label.set_tooltip_text(attr.get_schema().description.as_str());
             label.set_mouse_filter(MouseFilter::STOP);
 
             if self.item.clone().unwrap().get_stats().get_all_attributes().contains_key(attr) {
@@ -708,7 +769,7 @@ pub struct Inventory {
     receiver: Option<mpsc::Receiver<ItemCollectionEvent>>,
 }
 
-trait CenterAnchor {
+pub(crate) trait CenterAnchor {
     fn center_anchor(&mut self, size: Vector2);
 }
 
@@ -731,6 +792,35 @@ impl Inventory {
             grid.emit_signal("request_rerender", &[]);
         }
     }
+
+    /// Move the active item into the first equipment slot that accepts it
+    fn equip_active_item(&mut self) {
+        let Some(index) = self.grid.as_ref().map(|grid| grid.bind().get_active_item()) else {
+            return;
+        };
+        if index < 0 {
+            return;
+        }
+
+        let mut inventory = INVENTORY.lock().unwrap();
+        let mut equipment = EQUIPMENT.lock().unwrap();
+
+        let Ok(stack) = inventory.get_item(index as usize) else {
+            return;
+        };
+        if stack.get_ident() == "air" {
+            return;
+        }
+
+        if equipment.get_space_for(stack) < stack.get_count() {
+            godot_print!("No equipment slot accepts {}", stack.get_name());
+            return;
+        }
+
+        if let Ok(stack) = inventory.remove_item(index as usize) {
+            equipment.add_item(stack).expect("Checked that the equipment has space");
+        }
+    }
 }
 
 #[godot_api]
@@ -899,6 +989,8 @@ impl IPanel for Inventory {
 
             let visible = base.is_visible();
             base.set_visible(!visible);
+        } else if evt.is_action_pressed("ui_equip") && self.base().is_visible() {
+            self.equip_active_item();
         }
     }
 }
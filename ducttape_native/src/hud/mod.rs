@@ -1 +1,2 @@
+pub mod anvil;
 pub mod inventory;
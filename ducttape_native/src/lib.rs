@@ -1,17 +1,18 @@
+pub mod dropped_item;
 pub mod game_entities;
 pub mod hud;
-pub mod item;
 pub mod player;
 pub mod remote;
+pub mod save_game;
 pub mod singletons;
 pub mod template;
+pub mod transform_sync;
+pub mod usage;
 
-use std::future::pending;
-
-use bevy::{prelude::*, state::app::StatesPlugin, tasks::futures_lite};
-use bevy_godot4::prelude::{AsPhysicsSystem, ErasedGd};
+use bevy::{prelude::*, state::app::StatesPlugin};
+use bevy_godot4::prelude::AsPhysicsSystem;
 use godot::prelude::*;
-use remote::dbus_remote_provider::{DbusCommandQueue, DbusRemoteProvider};
+use remote::handler::RemoteQueue;
 
 #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, States)]
 pub enum GameState {
@@ -35,56 +36,50 @@ unsafe impl ExtensionLibrary for DuctTapeNative {
 }
 
 fn build_app(app: &mut App) {
-    let queue = DbusCommandQueue::default();
+    let (client, receiver) = ducttape_remote::channel::channel();
 
     app.add_plugins(StatesPlugin)
-        .insert_resource(queue.clone())
+        .insert_resource(RemoteQueue(receiver))
         .init_state::<GameState>()
+        .add_systems(Startup, singletons::inventory::setup_inventory_system)
         .add_systems(OnEnter(GameState::Playing), game_entities::player::spawn)
+        .add_systems(Update, remote::handler::remote_system.as_physics_system())
         .add_systems(
             Update,
-            remote::dbus_remote_provider::remote_provider_system.as_physics_system(),
+            transform_sync::place_spawned_nodes_system.as_physics_system(),
         )
-        .add_systems(Update, transform_system.as_physics_system());
-
-    godot_print!("Starting Remote provider (main)");
-
-    std::thread::spawn(move || {
-        futures_lite::future::block_on(async move {
-            let provider: DbusRemoteProvider = queue.clone().into(); // Clone the Arc to move it into the thread
-
-            let conn = zbus::connection::Builder::session()
-                .unwrap()
-                .name("me.endercass.ducttape.Remote")
-                .unwrap()
-                .serve_at("/me/endercass/ducttape/Remote", provider)
-                .unwrap()
-                .build()
-                .await;
-
-            match conn {
-                Ok(conn) => {
-                    println!("Remote provider started: {:?}", conn);
-                    pending::<()>().await;
-                }
-                Err(e) => println!("Error starting Remote provider: {:?}", e),
-            }
-        });
-    });
-}
-
-// #[derive(Resource)]
-// struct EntityManipulationSystems(HashMap<String, SystemId>);
+        // Sync once new nodes are placed where their entity was spawned
+        .add_systems(
+            Update,
+            transform_sync::push_transforms_system
+                .as_physics_system()
+                .after(transform_sync::place_spawned_nodes_system),
+        )
+        .add_systems(
+            Update,
+            transform_sync::pull_transforms_system
+                .as_physics_system()
+                .after(transform_sync::place_spawned_nodes_system),
+        )
+        .add_systems(Update, game_entities::spawn_request_system.as_physics_system())
+        .add_systems(Update, usage::item_use_system.as_physics_system())
+        .add_systems(Update, usage::setup_item_use_system.as_physics_system())
+        .add_systems(Update, dropped_item::dropped_item_system.as_physics_system())
+        .add_systems(
+            Update,
+            dropped_item::setup_dropped_item_system.as_physics_system(),
+        )
+        .add_systems(
+            Update,
+            dropped_item::despawn_picked_up_system.as_physics_system(),
+        )
+        // After the spawn requests, so the spawners of a new level have spawned their pickups
+        .add_systems(
+            Update,
+            save_game::save_game_system
+                .as_physics_system()
+                .after(game_entities::spawn_request_system),
+        );
 
-// This just straight up doesn't work, but it doesn't matter right now.
-fn transform_system(mut query: Query<(&mut ErasedGd, &Transform)>) {
-    for (mut node, transform) in query.iter_mut() {
-        let mut node = node.get::<Node2D>();
-        node.set_position(Vector2::new(
-            transform.translation.x,
-            transform.translation.y,
-        ));
-        node.set_rotation(transform.rotation.to_axis_angle().1);
-        node.set_scale(Vector2::new(transform.scale.x, transform.scale.y));
-    }
+    remote::start_transports(client);
 }
@@ -1,9 +1,28 @@
 use godot::{
-    classes::{CharacterBody2D, ICharacterBody2D},
+    classes::{CharacterBody2D, ICharacterBody2D, PhysicsRayQueryParameters2D, Time},
     global::clampf,
     prelude::*,
 };
 
+use ducttape_item_engine::encumbrance::Encumbrance;
+
+use crate::{
+    singletons::{
+        context::update_attribute_context,
+        equipment::{mobility, player_encumbrance},
+    },
+    save_game::{request_load, request_save, QUICKSAVE_SLOT},
+    usage::use_held_item,
+};
+
+/// The player node is in this group, so the HUD can find where items are dropped
+pub const PLAYER_GROUP: &str = "player";
+
+/// How far in front of the player the ground is checked for a gap
+const GAP_PROBE_DISTANCE: f32 = 24.0;
+/// How deep the ground in front of the player has to drop to count as a gap
+const GAP_PROBE_DEPTH: f32 = 48.0;
+
 #[derive(GodotClass)]
 #[class(base=CharacterBody2D)]
 pub struct Player {
@@ -33,6 +52,8 @@ pub struct Player {
     #[export]
     wall_jump_timer: f32,
     #[export]
+    climb_speed: f32,
+    #[export]
     zoom: f32,
 
     state: PlayerState,
@@ -45,6 +66,15 @@ pub struct PlayerState {
     pub should_jump: bool,
     pub wall_jump: bool,
     pub jumping: bool,
+    /// Speed factor from the equipped items, updated every physics frame
+    pub mobility: f32,
+    /// How burdened the player is by everything carried, updated every physics frame
+    pub encumbrance: Encumbrance,
+    /// 1 when facing right, -1 when facing left
+    pub facing: f32,
+    /// How many climbable areas, like tied ropes, the player is in
+    pub climbables: u32,
+    pub climbing: bool,
 }
 
 impl Default for PlayerState {
@@ -55,11 +85,44 @@ impl Default for PlayerState {
             should_jump: false,
             wall_jump: false,
             jumping: false,
+            mobility: 1.0,
+            encumbrance: Encumbrance::Unburdened,
+            facing: 1.0,
+            climbables: 0,
+            climbing: false,
         }
     }
 }
 
 impl Player {
+    /// The max speed after the equipment is taken into account
+    fn effective_max_speed(&self) -> f32 {
+        self.max_speed * self.state.mobility
+    }
+
+    fn effective_acceleration(&self) -> f32 {
+        self.acceleration * self.state.mobility * self.state.encumbrance.get_acceleration_factor()
+    }
+
+    /// Jumps scale less than speed so heavy loads still clear small ledges
+    fn effective_jump_force(&self) -> f32 {
+        self.jump_force * self.state.mobility.sqrt() * self.state.encumbrance.get_jump_factor()
+    }
+
+    /// 1 when the player faces right, -1 when facing left
+    pub fn get_facing(&self) -> f32 {
+        self.state.facing
+    }
+
+    /// Called by climbable areas when the player enters or leaves them
+    pub fn set_on_climbable(&mut self, on_climbable: bool) {
+        if on_climbable {
+            self.state.climbables += 1;
+        } else {
+            self.state.climbables = self.state.climbables.saturating_sub(1);
+        }
+    }
+
     fn get_input_direction(&self) -> Vector2 {
         let input = Input::singleton();
         let x_dir =
@@ -71,19 +134,21 @@ impl Player {
 
     fn jump(&mut self, direction: Vector2) {
         let mut velocity = self.base().get_velocity();
+        let jump_force = self.effective_jump_force();
         let state = &mut self.state;
 
         state.can_jump = false;
         state.should_jump = false;
         state.jumping = true;
+        state.climbing = false;
 
         if state.wall_jump {
-            velocity.x += self.jump_force * -direction.x;
+            velocity.x += jump_force * -direction.x;
             state.wall_jump = false;
             velocity.y = 0.0;
         }
 
-        velocity.y = -self.jump_force;
+        velocity.y = -jump_force;
 
         self.base_mut().set_velocity(velocity);
     }
@@ -124,7 +189,10 @@ impl Player {
             self.buffer_jump();
         } else if jump_strength == 0.0 && velocity.y < 0.0 {
             self.cancel_jump(delta);
-        } else if !self.base().is_on_floor() && self.base().is_on_wall_only() {
+        } else if !self.base().is_on_floor()
+            && self.base().is_on_wall_only()
+            && self.state.encumbrance.can_wall_jump()
+        {
             self.state.can_jump = true;
             self.state.wall_jump = true;
             self.state.jumping = false;
@@ -135,10 +203,76 @@ impl Player {
         self.state.sprinting = Input::singleton().get_action_strength("move_sprint") > 0.0;
     }
 
+    /// Share what the player is doing with the item attributes
+    fn share_attribute_context(&self) {
+        let sprinting = self.state.sprinting;
+        let time = Time::singleton().get_ticks_msec() as f64 / 1000.0;
+
+        update_attribute_context(|context| {
+            context.sprinting = sprinting;
+            context.time = time;
+        });
+    }
+
+    /// Check if the ground drops away in front of the player
+    fn is_gap_ahead(&self) -> bool {
+        if !self.base().is_on_floor() {
+            return false;
+        }
+
+        let Some(mut space) = self
+            .base()
+            .get_world_2d()
+            .and_then(|world| world.get_direct_space_state())
+        else {
+            return false;
+        };
+
+        let from = self.base().get_global_position()
+            + Vector2::new(self.state.facing * GAP_PROBE_DISTANCE, 0.0);
+        let to = from + Vector2::new(0.0, GAP_PROBE_DEPTH);
+
+        PhysicsRayQueryParameters2D::create(from, to)
+            .is_some_and(|query| space.intersect_ray(&query).is_empty())
+    }
+
+    fn handle_use(&mut self) {
+        if !Input::singleton().is_action_just_pressed("use_item") {
+            return;
+        }
+
+        let origin = self.base().get_global_position();
+
+        if let Some(action) = use_held_item(origin, self.state.facing, self.is_gap_ahead()) {
+            godot_print!("Used item: {:?}", action);
+        }
+    }
+
+    fn handle_save(&self) {
+        let input = Input::singleton();
+
+        if input.is_action_just_pressed("quicksave") {
+            request_save(QUICKSAVE_SLOT);
+        } else if input.is_action_just_pressed("quickload") {
+            request_load(QUICKSAVE_SLOT);
+        }
+    }
+
+    fn handle_climb(&mut self) {
+        let direction = self.get_input_direction();
+
+        if self.state.climbables == 0 {
+            self.state.climbing = false;
+        } else if direction.y != 0.0 {
+            self.state.climbing = true;
+        }
+    }
+
     fn handle_velocity(&mut self, delta: f64) {
         let direction = self.get_input_direction();
 
         if direction.x != 0.0 {
+            self.state.facing = direction.x.signum();
             self.apply_velocity(delta, direction);
         } else {
             self.apply_friction(delta);
@@ -153,9 +287,10 @@ impl Player {
         };
 
         let mut velocity = self.base().get_velocity();
+        let max_speed = self.effective_max_speed();
 
         velocity.x += direction.x
-            * self.acceleration
+            * self.effective_acceleration()
             * delta as f32
             * (if self.base().is_on_floor() {
                 sprint_multiplier
@@ -164,8 +299,8 @@ impl Player {
             });
         velocity.x = velocity
             .x
-            .max(-self.max_speed * direction.x.abs() * sprint_multiplier)
-            .min(self.max_speed * direction.x.abs() * sprint_multiplier);
+            .max(-max_speed * direction.x.abs() * sprint_multiplier)
+            .min(max_speed * direction.x.abs() * sprint_multiplier);
 
         self.base_mut().set_velocity(velocity);
     }
@@ -191,9 +326,18 @@ impl Player {
     fn handle_gravity(&mut self, delta: f64) {
         let mut velocity = self.base().get_velocity();
 
+        // Climbing players hold onto the rope instead of falling
+        if self.state.climbing {
+            velocity.y = self.get_input_direction().y * self.climb_speed;
+            self.base_mut().set_velocity(velocity);
+            return;
+        }
+
         velocity.y += self.gravity * delta as f32;
 
+        // Overloaded players are too heavy to hold onto walls
         if !self.state.jumping
+            && self.state.encumbrance.can_wall_jump()
             && self.base().is_on_wall_only()
             && self.get_input_direction().x != 0.0
         {
@@ -221,12 +365,15 @@ impl ICharacterBody2D for Player {
             jump_buffer_timer: 0.1,
             wall_jump_timer: 0.1,
             jump_height: 100.0,
+            climb_speed: 80.0,
             zoom: 1.5,
             state: PlayerState::default(),
         }
     }
 
     fn ready(&mut self) {
+        self.base_mut().add_to_group(PLAYER_GROUP);
+
         // Add a camera to the player
         let mut cam = Camera2D::new_alloc();
         cam.set_zoom(Vector2::new(self.zoom, self.zoom));
@@ -237,7 +384,13 @@ impl ICharacterBody2D for Player {
     fn physics_process(&mut self, delta: f64) {
         self.handle_jump(delta);
         self.handle_sprint();
+        self.share_attribute_context();
+        self.state.mobility = mobility();
+        self.state.encumbrance = player_encumbrance();
         self.handle_velocity(delta);
+        self.handle_climb();
+        self.handle_use();
+        self.handle_save();
 
         self.handle_gravity(delta);
 
@@ -1,78 +1,156 @@
-use std::sync::{Arc, Mutex};
+use std::collections::HashMap;
 
-use bevy::ecs::prelude::*;
-use ducttape_item_engine::item::{ItemCollection as _, ItemStack};
-use godot::prelude::*;
-use zbus::interface;
-
-use crate::{
-    game_entities::GameEntity,
-    singletons::inventory::{INVENTORY, ITEM_REGISTRY},
+use ducttape_remote::{
+    channel::ChannelClient,
+    client::RemoteClient,
+    error::RemoteError,
+    info::{EntityInfo, GameStateInfo, InventoryEvent, ItemInfo, RegistryItemInfo},
 };
+use godot::prelude::godot_print;
+use zbus::{interface, object_server::SignalEmitter, DBusError};
 
-pub fn remote_provider_system(mut commands: Commands, queue: Res<DbusCommandQueue>) {
-    let mut queue = queue.0.lock().unwrap();
-    for command in queue.drain(..) {
-        match command {
-            DbusCommand::SpawnEntity(entity, position) => {
-                entity.spawn_with_position(&mut commands, position);
-            }
-        }
-    }
+use crate::remote::subscribe_inventory;
+
+/// Well-known name of the remote on the session bus
+pub const REMOTE_NAME: &str = "me.endercass.ducttape.Remote";
+pub const REMOTE_PATH: &str = "/me/endercass/ducttape/Remote";
+
+/// Errors returned to D-Bus callers, sent as `me.endercass.ducttape.Remote.Error.<Variant>`
+/// with the variants of `RemoteError`
+#[derive(Debug, DBusError)]
+#[zbus(prefix = "me.endercass.ducttape.Remote.Error")]
+pub enum DbusRemoteError {
+    #[zbus(error)]
+    ZBus(zbus::Error),
+    ItemNotFound(String),
+    InventoryFull(String),
+    InvalidEntity(String),
+    InvalidArgument(String),
+    TimedOut(String),
+    Transport(String),
 }
 
-// Define possible commands that can be queued
-#[derive(Debug)]
-enum DbusCommand {
-    SpawnEntity(GameEntity, Vector2),
+impl From<RemoteError> for DbusRemoteError {
+    fn from(e: RemoteError) -> Self {
+        match e {
+            RemoteError::ItemNotFound(message) => DbusRemoteError::ItemNotFound(message),
+            RemoteError::InventoryFull(message) => DbusRemoteError::InventoryFull(message),
+            RemoteError::InvalidEntity(message) => DbusRemoteError::InvalidEntity(message),
+            RemoteError::InvalidArgument(message) => DbusRemoteError::InvalidArgument(message),
+            RemoteError::TimedOut(message) => DbusRemoteError::TimedOut(message),
+            RemoteError::Transport(message) => DbusRemoteError::Transport(message),
+        }
+    }
 }
 
-/// Shared queue to store DBus commands
-#[derive(Resource, Clone, Default)]
-pub struct DbusCommandQueue(Arc<Mutex<Vec<DbusCommand>>>);
+pub type DbusRemoteResult<T> = Result<T, DbusRemoteError>;
 
+/// Forwards D-Bus calls to the game as `RemoteCommand`s
 pub struct DbusRemoteProvider {
-    command_queue: DbusCommandQueue,
+    client: ChannelClient,
 }
 
-impl From<DbusCommandQueue> for DbusRemoteProvider {
-    fn from(command_queue: DbusCommandQueue) -> Self {
-        Self { command_queue }
+impl From<ChannelClient> for DbusRemoteProvider {
+    fn from(client: ChannelClient) -> Self {
+        Self { client }
     }
 }
 
 #[interface(name = "me.endercass.ducttape.RemoteProvider")]
 impl DbusRemoteProvider {
-    fn spawn_entity(&self, entity: GameEntity, position: (f32, f32)) {
-        let mut queue = self.command_queue.0.lock().unwrap();
-        queue.push(DbusCommand::SpawnEntity(
-            entity,
-            Vector2::new(position.0, position.1),
-        ));
+    /// Spawn an entity of a registered kind, returns its id
+    fn spawn_entity(&self, kind: String, position: (f32, f32)) -> DbusRemoteResult<u64> {
+        Ok(self.client.spawn_entity(&kind, position)?)
+    }
+
+    fn list_entities(&self) -> DbusRemoteResult<Vec<EntityInfo>> {
+        Ok(self.client.list_entities()?)
     }
 
-    fn add_item(&self, item: String, amount: u32) {
-        println!("Adding item: {}", item);
+    fn get_entity(&self, id: u64) -> DbusRemoteResult<EntityInfo> {
+        Ok(self.client.get_entity(id)?)
+    }
 
-        let registry = ITEM_REGISTRY.lock().unwrap();
-        let mut inventory = INVENTORY.lock().unwrap();
+    fn remove_entity(&self, id: u64) -> DbusRemoteResult<()> {
+        Ok(self.client.remove_entity(id)?)
+    }
 
-        if let Some(item) = registry.get(&item) {
-            println!("Found item: {:?}", item);
+    fn set_position(&self, id: u64, position: (f32, f32)) -> DbusRemoteResult<()> {
+        Ok(self.client.set_position(id, position)?)
+    }
 
-            inventory
-                .add_item(ItemStack::new(item.clone(), amount))
-                .unwrap();
-        } else {
-            println!("Item not found: {}", item);
-        }
+    fn set_velocity(&self, id: u64, velocity: (f32, f32)) -> DbusRemoteResult<()> {
+        Ok(self.client.set_velocity(id, velocity)?)
+    }
+
+    fn get_game_state(&self) -> DbusRemoteResult<GameStateInfo> {
+        Ok(self.client.get_game_state()?)
+    }
+
+    /// Every non-empty inventory slot with the stats of its stack
+    fn list_inventory(&self) -> DbusRemoteResult<Vec<ItemInfo>> {
+        Ok(self.client.list_inventory()?)
+    }
+
+    fn add_item(&self, item: String, amount: u32) -> DbusRemoteResult<()> {
+        Ok(self.client.add_item(&item, amount)?)
+    }
+
+    /// Take `count` items out of a slot, returns what was taken
+    fn remove_item(&self, slot: u32, count: u32) -> DbusRemoteResult<ItemInfo> {
+        Ok(self.client.remove_item(slot, count)?)
+    }
+
+    /// Move a stack onto another slot, merging same items and swapping different ones
+    fn move_item(&self, from: u32, to: u32) -> DbusRemoteResult<()> {
+        Ok(self.client.move_item(from, to)?)
+    }
+
+    fn list_registry(&self) -> DbusRemoteResult<Vec<RegistryItemInfo>> {
+        Ok(self.client.list_registry()?)
+    }
+
+    fn list_recipes(&self) -> DbusRemoteResult<Vec<String>> {
+        Ok(self.client.list_recipes()?)
+    }
+
+    /// Craft a recipe from the inventory, components are given as item idents and taken from
+    /// the first slot holding that item. Returns the ident of the crafted item
+    fn craft(
+        &self,
+        recipe: String,
+        components: HashMap<String, String>,
+    ) -> DbusRemoteResult<String> {
+        Ok(self.client.craft(&recipe, components)?)
+    }
+
+    /// Emitted for every change of the inventory, see `InventoryEvent`
+    #[zbus(signal)]
+    pub async fn inventory_changed(
+        emitter: &SignalEmitter<'_>,
+        event: InventoryEvent,
+    ) -> zbus::Result<()>;
+}
+
+/// Serve the remote on the session bus, then forward inventory changes as signals until the
+/// game exits. Blocks the calling thread
+pub async fn serve(client: ChannelClient) -> zbus::Result<()> {
+    let provider: DbusRemoteProvider = client.into();
+
+    let conn = zbus::connection::Builder::session()?
+        .name(REMOTE_NAME)?
+        .serve_at(REMOTE_PATH, provider)?
+        .build()
+        .await?;
+    godot_print!("Remote provider started: {:?}", conn);
+
+    let events = subscribe_inventory();
+    let emitter = SignalEmitter::new(&conn, REMOTE_PATH)?;
+
+    // The connection runs on its own executor, so waiting for events here doesn't stall calls
+    for event in events.iter() {
+        DbusRemoteProvider::inventory_changed(&emitter, event).await?;
     }
 
-    // fn remove_entity(&self, entity: Entity) -> Result<(), String>;
-    // fn set_position(&self, entity: Entity, position: Vec2) -> Result<(), String>;
-    // fn set_velocity(&self, entity: Entity, velocity: Vec2) -> Result<(), String>;
-    // fn set_rotation(&self, entity: Entity, rotation: f32) -> Result<(), String>;
-    // fn set_scale(&self, entity: Entity, scale: Vec2) -> Result<(), String>;
-    // fn add_item(&self, item: Item) -> Result<(), String>;
-    // fn remove_item(&self, item: Item) -> Result<(), String>;
+    Ok(())
 }
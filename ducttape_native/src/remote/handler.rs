@@ -0,0 +1,345 @@
+use std::collections::HashMap;
+
+use bevy::{ecs::prelude::*, state::state::State, transform::components::Transform};
+use bevy_godot4::prelude::{ErasedGd, SceneTreeRef};
+use ducttape_item_engine::{
+    attribute::AttributeParser,
+    crafting::CraftingError,
+    item::{Item, ItemCollection as _, ItemCollectionEvent, ItemStack},
+};
+use ducttape_remote::{
+    channel::RemoteReceiver,
+    command::{RemoteCommand, RemoteResponse},
+    error::{RemoteError, RemoteResult},
+    info::{EntityInfo, GameStateInfo, InventoryEvent, ItemInfo, RegistryItemInfo},
+};
+use godot::{
+    classes::{CharacterBody2D, RigidBody2D},
+    prelude::*,
+};
+
+use crate::{
+    game_entities::{try_get_node, EntityTags, GameEntity},
+    singletons::{
+        context::attribute_context,
+        crafting::RECIPE_BOOK,
+        equipment::player_encumbrance,
+        inventory::{INVENTORY, ITEM_REGISTRY},
+    },
+    GameState,
+};
+
+/// Commands of every transport, answered by `remote_system`
+#[derive(Resource)]
+pub struct RemoteQueue(pub RemoteReceiver);
+
+type EntityQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GameEntity,
+        &'static mut Transform,
+        Option<&'static EntityTags>,
+        Option<&'static ErasedGd>,
+    ),
+>;
+
+/// Answer the remote commands, whichever transport they came from
+pub fn remote_system(
+    mut commands: Commands,
+    queue: Res<RemoteQueue>,
+    mut entities: EntityQuery,
+    state: Res<State<GameState>>,
+    mut scene_tree: SceneTreeRef,
+) {
+    for request in queue.0.drain() {
+        let result = match &request.command {
+            RemoteCommand::SpawnEntity { kind, position } => GameEntity::new(kind)
+                .spawn_with_position(&mut commands, Vector2::new(position.0, position.1))
+                .map(|entity| RemoteResponse::EntityId(entity.to_bits()))
+                .map_err(|e| RemoteError::InvalidEntity(e.to_string())),
+            RemoteCommand::ListEntities => Ok(RemoteResponse::Entities(
+                entities
+                    .iter()
+                    .map(|(entity, kind, transform, tags, node)| {
+                        entity_info(entity, kind, &transform, tags, node)
+                    })
+                    .collect(),
+            )),
+            RemoteCommand::GetEntity { id } => find_entity(*id).and_then(|entity| {
+                let (entity, kind, transform, tags, node) =
+                    entities.get(entity).map_err(|_| no_entity(*id))?;
+                Ok(RemoteResponse::Entity(entity_info(
+                    entity, kind, &transform, tags, node,
+                )))
+            }),
+            RemoteCommand::RemoveEntity { id } => find_entity(*id).and_then(|entity| {
+                let (entity, _, _, _, node) = entities.get(entity).map_err(|_| no_entity(*id))?;
+
+                if let Some(mut node) = node.and_then(try_get_node) {
+                    node.queue_free();
+                }
+                commands.entity(entity).despawn();
+                Ok(RemoteResponse::Done)
+            }),
+            RemoteCommand::SetPosition { id, position } => find_entity(*id).and_then(|entity| {
+                let (_, _, mut transform, _, node) =
+                    entities.get_mut(entity).map_err(|_| no_entity(*id))?;
+
+                // Both sides are moved, whichever side owns the transform keeps it there
+                transform.translation.x = position.0;
+                transform.translation.y = position.1;
+                if let Some(mut node) = node.and_then(try_get_node) {
+                    node.set_global_position(Vector2::new(position.0, position.1));
+                }
+                Ok(RemoteResponse::Done)
+            }),
+            RemoteCommand::SetVelocity { id, velocity } => find_entity(*id).and_then(|entity| {
+                let (_, kind, _, _, node) = entities.get(entity).map_err(|_| no_entity(*id))?;
+                let velocity = Vector2::new(velocity.0, velocity.1);
+
+                match node.and_then(try_get_node) {
+                    Some(node) if set_velocity(node, velocity) => Ok(RemoteResponse::Done),
+                    _ => Err(RemoteError::InvalidEntity(format!(
+                        "{} {} is not a physics body",
+                        kind, id
+                    ))),
+                }
+            }),
+            RemoteCommand::GetGameState => {
+                let level = scene_tree
+                    .get()
+                    .get_current_scene()
+                    .map(|scene| scene.get_scene_file_path().to_string())
+                    .unwrap_or_default();
+
+                Ok(RemoteResponse::GameState(game_state(
+                    format!("{:?}", state.get()),
+                    level,
+                    &entities,
+                )))
+            }
+            command => handle_item_command(command),
+        };
+
+        request.respond(result);
+    }
+}
+
+fn find_entity(id: u64) -> RemoteResult<Entity> {
+    Entity::try_from_bits(id)
+        .map_err(|_| RemoteError::InvalidEntity(format!("Invalid entity: {}", id)))
+}
+
+fn no_entity(id: u64) -> RemoteError {
+    RemoteError::InvalidEntity(format!("No entity {}", id))
+}
+
+fn entity_info(
+    entity: Entity,
+    kind: &GameEntity,
+    transform: &Transform,
+    tags: Option<&EntityTags>,
+    node: Option<&ErasedGd>,
+) -> EntityInfo {
+    let velocity = node
+        .and_then(try_get_node)
+        .map_or(Vector2::ZERO, get_velocity);
+
+    EntityInfo {
+        id: entity.to_bits(),
+        kind: kind.to_string(),
+        position: (transform.translation.x, transform.translation.y),
+        velocity: (velocity.x, velocity.y),
+        tags: tags.map(|tags| tags.0.clone()).unwrap_or_default(),
+    }
+}
+
+fn get_velocity(node: Gd<Node2D>) -> Vector2 {
+    match node.try_cast::<CharacterBody2D>() {
+        Ok(body) => body.get_velocity(),
+        Err(node) => node
+            .try_cast::<RigidBody2D>()
+            .map_or(Vector2::ZERO, |body| body.get_linear_velocity()),
+    }
+}
+
+/// Set the velocity of a physics body, returns false if the node isn't one
+fn set_velocity(node: Gd<Node2D>, velocity: Vector2) -> bool {
+    match node.try_cast::<CharacterBody2D>() {
+        Ok(mut body) => body.set_velocity(velocity),
+        Err(node) => match node.try_cast::<RigidBody2D>() {
+            Ok(mut body) => body.set_linear_velocity(velocity),
+            Err(_) => return false,
+        },
+    }
+
+    true
+}
+
+fn game_state(state: String, level: String, entities: &EntityQuery) -> GameStateInfo {
+    let player = entities
+        .iter()
+        .find(|(_, kind, _, _, _)| **kind == GameEntity::Player);
+
+    let inventory = INVENTORY.lock().unwrap();
+    let inventory_used = inventory
+        .iter()
+        .filter(|stack| stack.get_ident() != "air")
+        .count();
+    let inventory_size = inventory.len();
+    drop(inventory);
+
+    GameStateInfo {
+        state,
+        level,
+        player: player.as_ref().map_or(0, |(entity, ..)| entity.to_bits()),
+        player_position: player.map_or((0.0, 0.0), |(_, _, transform, _, _)| {
+            (transform.translation.x, transform.translation.y)
+        }),
+        entities: entities.iter().count() as u32,
+        inventory_used: inventory_used as u32,
+        inventory_size: inventory_size as u32,
+        encumbrance: player_encumbrance().to_string(),
+    }
+}
+
+/// A stack as seen from a remote, with the stats of the current attribute context
+pub fn item_info(slot: usize, stack: &ItemStack) -> ItemInfo {
+    let stats = AttributeParser::from(stack.get_stats().get_all_attributes())
+        .with_context(attribute_context())
+        .aggregate_to_values()
+        .into_iter()
+        .map(|(at, value)| (at.to_string(), value))
+        .collect();
+
+    ItemInfo {
+        slot: slot as u32,
+        ident: stack.get_ident(),
+        name: stack.get_name(),
+        count: stack.get_count(),
+        tags: stack.get_tags(),
+        stats,
+    }
+}
+
+pub fn inventory_event(event: ItemCollectionEvent) -> InventoryEvent {
+    let (kind, items) = match event {
+        ItemCollectionEvent::Add { index, item } => ("Add", vec![item_info(index, &item)]),
+        ItemCollectionEvent::Remove { index, item } => ("Remove", vec![item_info(index, &item)]),
+        ItemCollectionEvent::Update { index, item } => ("Update", vec![item_info(index, &item)]),
+        ItemCollectionEvent::Clear => ("Clear", Vec::new()),
+        ItemCollectionEvent::ManualRefresh => ("ManualRefresh", Vec::new()),
+    };
+
+    InventoryEvent {
+        kind: kind.to_owned(),
+        items,
+    }
+}
+
+fn check_slot(slot: u32, len: usize) -> RemoteResult<usize> {
+    if (slot as usize) < len {
+        Ok(slot as usize)
+    } else {
+        Err(RemoteError::InvalidArgument(format!("No inventory slot {}", slot)))
+    }
+}
+
+/// Commands that only touch the inventory and the registries
+fn handle_item_command(command: &RemoteCommand) -> RemoteResult<RemoteResponse> {
+    match command {
+        RemoteCommand::ListInventory => Ok(RemoteResponse::Items(
+            INVENTORY
+                .lock()
+                .unwrap()
+                .iter()
+                .enumerate()
+                .filter(|(_, stack)| stack.get_ident() != "air")
+                .map(|(slot, stack)| item_info(slot, stack))
+                .collect(),
+        )),
+        RemoteCommand::AddItem { item, amount } => {
+            let item = ITEM_REGISTRY
+                .lock()
+                .unwrap()
+                .get(item)
+                .cloned()
+                .ok_or_else(|| RemoteError::ItemNotFound(format!("Unknown item: {}", item)))?;
+
+            INVENTORY
+                .lock()
+                .unwrap()
+                .add_item(ItemStack::new(item, *amount))
+                .map(|_| RemoteResponse::Done)
+                .map_err(|e| RemoteError::InventoryFull(e.to_string()))
+        }
+        RemoteCommand::RemoveItem { slot, count } => {
+            let mut inventory = INVENTORY.lock().unwrap();
+            let slot = check_slot(*slot, inventory.len())?;
+
+            inventory
+                .take_item(slot, *count)
+                .map(|taken| RemoteResponse::Item(item_info(slot, &taken)))
+                .map_err(|e| RemoteError::ItemNotFound(e.to_string()))
+        }
+        RemoteCommand::MoveItem { from, to } => {
+            let mut inventory = INVENTORY.lock().unwrap();
+            let from = check_slot(*from, inventory.len())?;
+            let to = check_slot(*to, inventory.len())?;
+
+            if inventory.merge(from, to).is_err() {
+                inventory.get_items_mut().swap(from, to);
+                inventory.notify(ItemCollectionEvent::ManualRefresh);
+            }
+
+            Ok(RemoteResponse::Done)
+        }
+        RemoteCommand::ListRegistry => {
+            let registry = ITEM_REGISTRY.lock().unwrap();
+
+            let mut items: Vec<RegistryItemInfo> = registry
+                .iter()
+                .map(|(ident, item)| RegistryItemInfo {
+                    ident: ident.clone(),
+                    name: item.get_name(),
+                    tags: item.get_tags(),
+                    compound: !item.get_components().is_empty(),
+                })
+                .collect();
+            items.sort_by(|a, b| a.ident.cmp(&b.ident));
+
+            Ok(RemoteResponse::Registry(items))
+        }
+        RemoteCommand::ListRecipes => Ok(RemoteResponse::Names(
+            RECIPE_BOOK.iter().map(|(name, _)| name.clone()).collect(),
+        )),
+        RemoteCommand::Craft { recipe, components } => {
+            craft(recipe, components).map(RemoteResponse::Ident)
+        }
+        command => Err(RemoteError::InvalidArgument(format!(
+            "Not an item command: {:?}",
+            command
+        ))),
+    }
+}
+
+fn craft(recipe: &str, components: &HashMap<String, String>) -> RemoteResult<String> {
+    let recipe = RECIPE_BOOK
+        .get(recipe)
+        .ok_or_else(|| RemoteError::InvalidArgument(format!("Unknown recipe: {}", recipe)))?;
+
+    let mut registry = ITEM_REGISTRY.lock().unwrap();
+    let mut inventory = INVENTORY.lock().unwrap();
+
+    recipe
+        .find_inputs(&*inventory, components)
+        .and_then(|inputs| recipe.craft_into(&mut *inventory, &mut registry, &inputs))
+        .map(|crafted| crafted.get_ident())
+        .map_err(|e| match e {
+            CraftingError::ItemNotFound(_) => RemoteError::ItemNotFound(e.to_string()),
+            CraftingError::NoRoom => RemoteError::InventoryFull(e.to_string()),
+            e => RemoteError::InvalidArgument(e.to_string()),
+        })
+}
@@ -1 +1,85 @@
 pub mod dbus_remote_provider;
+pub mod handler;
+
+use std::{
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Mutex, Once,
+    },
+    thread,
+};
+
+use bevy::tasks::futures_lite;
+use ducttape_item_engine::item::ItemCollection as _;
+use ducttape_remote::{channel::ChannelClient, info::InventoryEvent};
+use godot::prelude::{godot_error, godot_print};
+use lazy_static::lazy_static;
+
+use crate::singletons::inventory::INVENTORY;
+
+lazy_static! {
+    static ref INVENTORY_SUBSCRIBERS: Mutex<Vec<Sender<InventoryEvent>>> = Mutex::new(Vec::new());
+}
+
+static LISTEN_INVENTORY: Once = Once::new();
+
+/// Get every change of the inventory from now on, for as long as the receiver is kept
+pub fn subscribe_inventory() -> Receiver<InventoryEvent> {
+    LISTEN_INVENTORY.call_once(|| {
+        INVENTORY.lock().unwrap().listen(Box::new(|event| {
+            let event = handler::inventory_event(event);
+            INVENTORY_SUBSCRIBERS
+                .lock()
+                .unwrap()
+                .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }));
+    });
+
+    let (sender, receiver) = mpsc::channel();
+    INVENTORY_SUBSCRIBERS.lock().unwrap().push(sender);
+    receiver
+}
+
+/// Serve the remote on D-Bus and the JSON-RPC socket, each on its own thread so one failing
+/// doesn't take the other down
+pub fn start_transports(client: ChannelClient) {
+    godot_print!("Starting Remote provider (main)");
+
+    let dbus_client = client.clone();
+    thread::spawn(move || {
+        futures_lite::future::block_on(async move {
+            if let Err(e) = dbus_remote_provider::serve(dbus_client).await {
+                godot_error!("Error starting D-Bus remote: {:?}", e);
+            }
+        });
+    });
+
+    #[cfg(unix)]
+    thread::spawn(move || {
+        if let Err(e) = serve_socket(client) {
+            godot_error!("Error starting socket remote: {:?}", e);
+        }
+    });
+}
+
+#[cfg(unix)]
+fn serve_socket(client: ChannelClient) -> std::io::Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    use ducttape_remote::json_rpc;
+
+    let path = json_rpc::default_socket_path();
+    if path.exists() {
+        // A socket that still accepts connections belongs to another running game
+        if UnixStream::connect(&path).is_ok() {
+            godot_error!("Socket remote already served at {}", path.display());
+            return Ok(());
+        }
+        std::fs::remove_file(&path)?;
+    }
+
+    let listener = UnixListener::bind(&path)?;
+    godot_print!("Socket remote started at {}", path.display());
+
+    json_rpc::serve(listener, client, std::sync::Arc::new(subscribe_inventory))
+}
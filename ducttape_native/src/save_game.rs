@@ -0,0 +1,532 @@
+use std::{collections::BTreeMap, fs, path::PathBuf, sync::Mutex};
+
+use bevy::{
+    ecs::{
+        entity::Entity,
+        system::{Commands, Query},
+    },
+    transform::components::Transform,
+};
+use bevy_godot4::prelude::{ErasedGd, SceneTreeRef};
+use ducttape_item_engine::{
+    item::{Item, ItemCollection},
+    prelude_items::air::Air,
+    save::{
+        get_renames, SaveError, SaveMigrationFile, SavedItem, SavedItemCollection, SavedItemStack,
+    },
+};
+use godot::{
+    classes::{Area2D, IArea2D, ProjectSettings, SceneTree},
+    prelude::*,
+};
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dropped_item::{drop_item, DroppedItem},
+    game_entities::try_get_node,
+    player::{Player, PLAYER_GROUP},
+    singletons::{
+        equipment::EQUIPMENT,
+        inventory::{INVENTORY, ITEM_REGISTRY},
+    },
+    template::loader::asset_source,
+};
+
+/// Version of the save format written by this build, older saves are migrated when loaded
+pub const SAVE_VERSION: u32 = 1;
+/// Written when the player moves to another level
+pub const AUTOSAVE_SLOT: &str = "autosave";
+/// Used by the quicksave and quickload inputs
+pub const QUICKSAVE_SLOT: &str = "quicksave";
+
+const SAVES_FOLDER: &str = "user://saves";
+const SAVE_EXTENSION: &str = "json";
+/// Idents renamed between save versions, relative to `res://assets/`
+const MIGRATIONS_FILE: &str = "migrations.toml";
+/// Where the player is put when entering a level, if the level has one
+const PLAYER_START_NODE: &str = "PlayerStart";
+
+/// Everything needed to continue a game, stored as JSON in `user://saves/<slot>.json`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    /// Scene file of the level the player was in
+    pub level: String,
+    #[serde(default)]
+    pub player_position: Option<(f32, f32)>,
+    pub inventory: SavedItemCollection,
+    /// Equipped stacks by slot name, empty slots are left out
+    #[serde(default)]
+    pub equipment: BTreeMap<String, SavedItemStack>,
+    /// Items generated from templates while playing, like crafted tools, by registry ident
+    #[serde(default)]
+    pub generated_items: BTreeMap<String, SavedItem>,
+    /// Items lying around in the level
+    #[serde(default)]
+    pub pickups: Vec<SavedPickup>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPickup {
+    pub item: SavedItemStack,
+    pub position: (f32, f32),
+}
+
+impl SaveGame {
+    /// Replace the idents renamed since the save was written
+    fn migrate(&mut self, migrations: &SaveMigrationFile) {
+        let renames = get_renames(&migrations.migration, self.version);
+        if renames.is_empty() {
+            return;
+        }
+
+        self.inventory.rename_idents(&renames);
+        for stack in self.equipment.values_mut() {
+            stack.rename_idents(&renames);
+        }
+        for pickup in self.pickups.iter_mut() {
+            pickup.item.rename_idents(&renames);
+        }
+
+        self.generated_items = std::mem::take(&mut self.generated_items)
+            .into_iter()
+            .map(|(ident, mut item)| {
+                item.rename_idents(&renames);
+                (renames.get(&ident).cloned().unwrap_or(ident), item)
+            })
+            .collect();
+
+        self.version = SAVE_VERSION;
+    }
+}
+
+#[derive(Debug)]
+pub enum SaveGameError {
+    Io(std::io::Error),
+    Parse(serde_json::Error),
+    /// The save was written by a newer build
+    UnsupportedVersion(u32),
+    /// The save references items that can't be loaded
+    Items(SaveError),
+}
+
+impl std::fmt::Display for SaveGameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SaveGameError::Io(e) => write!(f, "IO error: {}", e),
+            SaveGameError::Parse(e) => write!(f, "Invalid save: {}", e),
+            SaveGameError::UnsupportedVersion(version) => {
+                write!(f, "Save version {} is newer than {}", version, SAVE_VERSION)
+            }
+            SaveGameError::Items(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveGameError {}
+
+impl From<std::io::Error> for SaveGameError {
+    fn from(e: std::io::Error) -> Self {
+        SaveGameError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveGameError {
+    fn from(e: serde_json::Error) -> Self {
+        SaveGameError::Parse(e)
+    }
+}
+
+impl From<SaveError> for SaveGameError {
+    fn from(e: SaveError) -> Self {
+        SaveGameError::Items(e)
+    }
+}
+
+pub type SaveGameResult<T> = Result<T, SaveGameError>;
+
+fn saves_folder() -> PathBuf {
+    ProjectSettings::singleton()
+        .globalize_path(SAVES_FOLDER)
+        .to_string()
+        .into()
+}
+
+fn slot_path(slot: &str) -> PathBuf {
+    saves_folder().join(format!("{}.{}", slot, SAVE_EXTENSION))
+}
+
+/// Names of every save slot on disk
+pub fn list_slots() -> Vec<String> {
+    let Ok(entries) = fs::read_dir(saves_folder()) else {
+        return Vec::new();
+    };
+
+    let mut slots: Vec<String> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == SAVE_EXTENSION))
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().into_owned()))
+        .collect();
+    slots.sort();
+
+    slots
+}
+
+fn write_save(slot: &str, save: &SaveGame) -> SaveGameResult<()> {
+    fs::create_dir_all(saves_folder())?;
+    fs::write(slot_path(slot), serde_json::to_string_pretty(save)?)?;
+
+    Ok(())
+}
+
+/// Read a save and bring it up to the current version
+fn read_save(slot: &str) -> SaveGameResult<SaveGame> {
+    let mut save: SaveGame = serde_json::from_str(&fs::read_to_string(slot_path(slot))?)?;
+
+    if save.version > SAVE_VERSION {
+        return Err(SaveGameError::UnsupportedVersion(save.version));
+    }
+
+    save.migrate(&load_migrations());
+
+    Ok(save)
+}
+
+fn load_migrations() -> SaveMigrationFile {
+    let source = asset_source();
+    if !source.exists(MIGRATIONS_FILE) {
+        return SaveMigrationFile::default();
+    }
+
+    SaveMigrationFile::load(&*source, MIGRATIONS_FILE).unwrap_or_else(|e| {
+        godot_print!("Failed to load save migrations: {}", e);
+        SaveMigrationFile::default()
+    })
+}
+
+/// Save the items of the inventory, the equipment and the item registry
+fn save_items(save: &mut SaveGame) {
+    let registry = ITEM_REGISTRY.lock().unwrap();
+
+    save.inventory = registry.save_collection_sized(&INVENTORY.lock().unwrap());
+
+    let equipment = EQUIPMENT.lock().unwrap();
+    for (slot, stack) in equipment.get_slots().iter().zip(equipment.iter()) {
+        if stack.get_ident() != "air" {
+            save.equipment
+                .insert(slot.name.clone(), registry.save_stack(stack));
+        }
+    }
+
+    save.generated_items = registry
+        .iter()
+        .filter(|(_, item)| !item.get_components().is_empty())
+        .map(|(ident, item)| (ident.clone(), registry.save_item(item.as_ref())))
+        .collect();
+}
+
+/// Put the saved items back into the item registry, the inventory and the equipment.
+///
+/// The inventory and the equipment are only replaced once all of their items loaded
+fn load_items(save: &SaveGame) -> SaveGameResult<()> {
+    let mut registry = ITEM_REGISTRY.lock().unwrap();
+
+    // Generated items have to be registered first, the other items may be made of them
+    let generated = save
+        .generated_items
+        .iter()
+        .map(|(ident, item)| Ok((ident.clone(), registry.load_item(item)?)))
+        .collect::<SaveGameResult<Vec<_>>>()?;
+    for (ident, item) in generated {
+        registry.register(ident, item);
+    }
+
+    let loaded_inventory = registry.load_collection_sized(&save.inventory)?;
+    let equipped = save
+        .equipment
+        .iter()
+        .map(|(slot, stack)| Ok((slot.clone(), registry.load_stack(stack)?)))
+        .collect::<SaveGameResult<Vec<_>>>()?;
+    drop(registry);
+
+    // The slots are replaced one by one, the collection keeps its size and its listeners
+    let mut loaded = loaded_inventory.into_iter();
+    let mut inventory = INVENTORY.lock().unwrap();
+    for slot in inventory.get_items_mut().iter_mut() {
+        *slot = loaded.next().unwrap_or_else(Air::new_itemstack);
+    }
+    inventory.refresh();
+    drop(inventory);
+
+    let mut equipment = EQUIPMENT.lock().unwrap();
+    equipment.clear();
+    for (slot, stack) in equipped {
+        if let Err(e) = equipment.equip(&slot, stack) {
+            godot_print!("Failed to equip saved item in {}: {}", slot, e);
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug)]
+enum SaveRequest {
+    Save(String),
+    Load(String),
+    ChangeLevel(String),
+}
+
+/// Waiting for a new level to be the current scene
+#[derive(Debug)]
+enum PendingLevel {
+    /// Entered through a level exit, autosaved once the level is set up
+    Entered(String),
+    /// Changed to by loading a save, the world part of the save is applied once it is set up
+    Loaded(Box<SaveGame>),
+}
+
+lazy_static! {
+    /// Drained by `save_game_system`, saving and loading touch the world so they happen in Bevy
+    static ref SAVE_REQUESTS: Mutex<Vec<SaveRequest>> = Mutex::new(Vec::new());
+    static ref PENDING_LEVEL: Mutex<Option<PendingLevel>> = Mutex::new(None);
+}
+
+pub fn request_save(slot: &str) {
+    SAVE_REQUESTS
+        .lock()
+        .unwrap()
+        .push(SaveRequest::Save(slot.to_owned()));
+}
+
+pub fn request_load(slot: &str) {
+    SAVE_REQUESTS
+        .lock()
+        .unwrap()
+        .push(SaveRequest::Load(slot.to_owned()));
+}
+
+/// Move the player to another level and autosave once it is there
+pub fn change_level(level: &str) {
+    SAVE_REQUESTS
+        .lock()
+        .unwrap()
+        .push(SaveRequest::ChangeLevel(level.to_owned()));
+}
+
+fn current_level(tree: &Gd<SceneTree>) -> Option<String> {
+    tree.get_current_scene()
+        .map(|scene| scene.get_scene_file_path().to_string())
+}
+
+fn find_player(tree: &mut Gd<SceneTree>) -> Option<Gd<Player>> {
+    tree.get_first_node_in_group(PLAYER_GROUP)
+        .and_then(|node| node.try_cast::<Player>().ok())
+}
+
+/// Pickups left in the level, picked up ones are despawned by `despawn_picked_up_system`
+type PickupQuery<'w, 's> =
+    Query<'w, 's, (Entity, Option<&'static ErasedGd>, &'static DroppedItem, &'static Transform)>;
+
+/// Remove every pickup of the current level, both the entities and their nodes
+fn clear_pickups(commands: &mut Commands, pickups: &PickupQuery) {
+    for (entity, node, _, _) in pickups.iter() {
+        if let Some(mut node) = node.and_then(try_get_node) {
+            node.queue_free();
+        }
+
+        commands.entity(entity).despawn();
+    }
+}
+
+fn save_world(tree: &mut Gd<SceneTree>, pickups: &PickupQuery) -> SaveGame {
+    let mut save = SaveGame {
+        version: SAVE_VERSION,
+        level: current_level(tree).unwrap_or_default(),
+        player_position: find_player(tree).map(|player| {
+            let position = player.get_global_position();
+            (position.x, position.y)
+        }),
+        inventory: SavedItemCollection {
+            size: None,
+            items: Vec::new(),
+        },
+        equipment: BTreeMap::new(),
+        generated_items: BTreeMap::new(),
+        pickups: Vec::new(),
+    };
+
+    save_items(&mut save);
+
+    let registry = ITEM_REGISTRY.lock().unwrap();
+    save.pickups = pickups
+        .iter()
+        .filter_map(|(_, _, pickup, transform)| {
+            Some(SavedPickup {
+                item: registry.save_stack(&pickup.get_stack()?),
+                position: (transform.translation.x, transform.translation.y),
+            })
+        })
+        .collect();
+
+    save
+}
+
+/// Put back the pickups and the player, the level has to be the current scene already
+fn load_world(
+    commands: &mut Commands,
+    tree: &mut Gd<SceneTree>,
+    pickups: &PickupQuery,
+    save: &SaveGame,
+) {
+    clear_pickups(commands, pickups);
+
+    let registry = ITEM_REGISTRY.lock().unwrap();
+    for pickup in save.pickups.iter() {
+        match registry.load_stack(&pickup.item) {
+            Ok(stack) => drop_item(
+                stack,
+                Vector2::new(pickup.position.0, pickup.position.1),
+                Vector2::ZERO,
+            ),
+            Err(e) => godot_print!("Failed to load saved pickup: {}", e),
+        }
+    }
+
+    if let (Some(mut player), Some((x, y))) = (find_player(tree), save.player_position) {
+        player.set_global_position(Vector2::new(x, y));
+    }
+}
+
+fn save(tree: &mut Gd<SceneTree>, pickups: &PickupQuery, slot: &str) {
+    match write_save(slot, &save_world(tree, pickups)) {
+        Ok(()) => godot_print!("Saved game to {}", slot),
+        Err(e) => godot_print!("Failed to save game to {}: {}", slot, e),
+    }
+}
+
+fn load(
+    commands: &mut Commands,
+    tree: &mut Gd<SceneTree>,
+    pickups: &PickupQuery,
+    slot: &str,
+) -> SaveGameResult<()> {
+    let save = read_save(slot)?;
+    load_items(&save)?;
+
+    if current_level(tree).as_deref() == Some(save.level.as_str()) {
+        load_world(commands, tree, pickups, &save);
+    } else {
+        clear_pickups(commands, pickups);
+        tree.change_scene_to_file(&save.level);
+        *PENDING_LEVEL.lock().unwrap() = Some(PendingLevel::Loaded(Box::new(save)));
+    }
+
+    Ok(())
+}
+
+/// Finish a level change once the new level is the current scene.
+///
+/// Runs after the spawn requests of the level's `EntitySpawner`s, so its pickups already exist
+fn finish_level_change(
+    commands: &mut Commands,
+    tree: &mut Gd<SceneTree>,
+    pickups: &PickupQuery,
+) {
+    let mut pending = PENDING_LEVEL.lock().unwrap();
+
+    let level = match pending.as_ref() {
+        Some(PendingLevel::Entered(level)) => level.as_str(),
+        Some(PendingLevel::Loaded(save)) => save.level.as_str(),
+        None => return,
+    };
+    if current_level(tree).as_deref() != Some(level) {
+        return;
+    }
+
+    match pending.take() {
+        Some(PendingLevel::Entered(_)) => {
+            let start = tree
+                .get_current_scene()
+                .and_then(|scene| scene.try_get_node_as::<Node2D>(PLAYER_START_NODE));
+
+            if let (Some(mut player), Some(start)) = (find_player(tree), start) {
+                player.set_global_position(start.get_global_position());
+            }
+
+            save(tree, pickups, AUTOSAVE_SLOT);
+        }
+        Some(PendingLevel::Loaded(save)) => load_world(commands, tree, pickups, &save),
+        None => {}
+    }
+}
+
+pub fn save_game_system(
+    mut commands: Commands,
+    mut scene_tree: SceneTreeRef,
+    pickups: PickupQuery,
+) {
+    let mut tree = scene_tree.get();
+
+    finish_level_change(&mut commands, &mut tree, &pickups);
+
+    for request in SAVE_REQUESTS.lock().unwrap().drain(..) {
+        match request {
+            SaveRequest::Save(slot) => save(&mut tree, &pickups, &slot),
+            SaveRequest::Load(slot) => {
+                match load(&mut commands, &mut tree, &pickups, &slot) {
+                    Ok(()) => godot_print!("Loaded game from {}", slot),
+                    Err(e) => godot_print!("Failed to load game from {}: {}", slot, e),
+                }
+            }
+            SaveRequest::ChangeLevel(level) => {
+                clear_pickups(&mut commands, &pickups);
+                tree.change_scene_to_file(&level);
+                *PENDING_LEVEL.lock().unwrap() = Some(PendingLevel::Entered(level));
+            }
+        }
+    }
+}
+
+/// Place in a level to send the player to `next_level` when it walks in, the game is
+/// autosaved once the next level is set up
+#[derive(GodotClass)]
+#[class(base = Area2D)]
+pub struct LevelExit {
+    base: Base<Area2D>,
+    /// Scene file of the level, like `res://levels/level2.tscn`
+    #[export(file = "*.tscn")]
+    next_level: GString,
+}
+
+#[godot_api]
+impl IArea2D for LevelExit {
+    fn init(base: Base<Area2D>) -> Self {
+        Self {
+            base,
+            next_level: GString::new(),
+        }
+    }
+
+    fn ready(&mut self) {
+        let self_gd = self.to_gd();
+        self.base_mut().connect(
+            "body_entered",
+            &Callable::from_object_method(&self_gd, "_on_body_entered"),
+        );
+    }
+}
+
+#[godot_api]
+impl LevelExit {
+    #[func]
+    fn _on_body_entered(&mut self, body: Gd<Node2D>) {
+        if body.try_cast::<Player>().is_err() || self.next_level.is_empty() {
+            return;
+        }
+
+        // Only leave once, the body may enter again before the level is gone
+        self.base_mut().set_deferred("monitoring", &false.to_variant());
+        change_level(&self.next_level.to_string());
+    }
+}
@@ -0,0 +1,19 @@
+use std::sync::RwLock;
+
+use ducttape_item_engine::attribute::condition::AttributeContext;
+use lazy_static::lazy_static;
+
+lazy_static! {
+    /// What the player is doing right now, conditional and temporary item attributes are checked against it
+    pub static ref ATTRIBUTE_CONTEXT: RwLock<AttributeContext> =
+        RwLock::new(AttributeContext::new());
+}
+
+pub fn attribute_context() -> AttributeContext {
+    ATTRIBUTE_CONTEXT.read().unwrap().clone()
+}
+
+/// Change the shared context, e.g. when the player starts sprinting or enters water
+pub fn update_attribute_context(update: impl FnOnce(&mut AttributeContext)) {
+    update(&mut ATTRIBUTE_CONTEXT.write().unwrap());
+}
@@ -0,0 +1,22 @@
+use ducttape_item_engine::crafting::RecipeBook;
+use godot::global::godot_print;
+use lazy_static::lazy_static;
+
+use crate::template::loader::generated_asset_source;
+
+lazy_static! {
+    pub static ref RECIPE_BOOK: RecipeBook = load_recipe_book();
+}
+
+fn load_recipe_book() -> RecipeBook {
+    match RecipeBook::load_all(generated_asset_source()) {
+        Ok(book) => {
+            godot_print!("Loaded {} recipes", book.len());
+            book
+        }
+        Err(e) => {
+            godot_print!("Failed to load recipes: {}", e);
+            RecipeBook::new()
+        }
+    }
+}
@@ -0,0 +1,67 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use ducttape_item_engine::{
+    attribute::{Attribute, AttributeModifier, AttributeParser, AttributeReason, AttributeType},
+    encumbrance::{get_carried_weight, Encumbrance},
+    equipment::EquipmentCollection,
+};
+use lazy_static::lazy_static;
+
+use super::{context::attribute_context, inventory::INVENTORY};
+
+/// Agility of the player without any equipment
+pub const BASE_AGILITY: f64 = 10.0;
+/// Strength of the player without any equipment
+pub const BASE_STRENGTH: f64 = 20.0;
+
+lazy_static! {
+    /// What the player is holding and wearing
+    pub static ref EQUIPMENT: Mutex<EquipmentCollection> =
+        Mutex::new(EquipmentCollection::player());
+}
+
+fn base_attribute(value: f64) -> Vec<Attribute> {
+    vec![Attribute {
+        uuid: uuid::Uuid::new_v4(),
+        reason: AttributeReason::Display("🧍".into()),
+        priority: 0,
+        modifier: AttributeModifier::Set(value),
+        condition: None,
+        lifetime: None,
+    }]
+}
+
+/// The stats of the player with the current equipment and attribute context
+pub fn player_stats() -> AttributeParser {
+    let base = HashMap::from([
+        (AttributeType::Agility, base_attribute(BASE_AGILITY)),
+        (AttributeType::Strength, base_attribute(BASE_STRENGTH)),
+    ]);
+
+    EQUIPMENT
+        .lock()
+        .unwrap()
+        .get_effective_stats(base, &attribute_context())
+}
+
+/// How fast the player moves compared to an unequipped player, heavy equipment lowers it
+pub fn mobility() -> f32 {
+    EQUIPMENT
+        .lock()
+        .unwrap()
+        .get_mobility(BASE_AGILITY, &attribute_context()) as f32
+}
+
+/// How burdened the player is by the inventory and the equipment.
+///
+/// Equipped items already count towards the weight in the player's stats, so only the
+/// inventory is added on top
+pub fn player_encumbrance() -> Encumbrance {
+    let context = attribute_context();
+    let inventory_weight = get_carried_weight(&*INVENTORY.lock().unwrap(), &context);
+
+    let stats = player_stats();
+    let weight = stats.aggregate_to_value(AttributeType::Weight) + inventory_weight;
+
+    Encumbrance::from_load(weight, stats.aggregate_to_value(AttributeType::Strength))
+}
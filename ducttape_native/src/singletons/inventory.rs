@@ -1,90 +1,113 @@
 use ducttape_item_engine::{
+    attribute::schema::ATTRIBUTE_REGISTRY,
     item::{DummyHook, ItemCollection as _, ItemCollectionSized, ItemRegistry, ItemStack},
-    prelude_items::{air::Air, dev_tablet::DevTablet, rock::Rock},
+    prelude_items::air::Air,
 };
 use godot::global::godot_print;
 use lazy_static::lazy_static;
 use maplit::hashmap;
 use std::sync::{Arc, Mutex};
 
-use crate::{item::rope::Rope, template::loader::ItemTemplate};
+use crate::template::loader::{asset_source, load_template};
 
-// static INVENTORY: Mutex<Option<ItemCollectionSized>> = Mutex::new(None);
+/// Folder of the data item definitions, relative to `res://assets/`
+const ITEMS_FOLDER: &str = "items";
+/// Attributes added on top of the built-in ones, relative to `res://assets/`
+const ATTRIBUTES_FILE: &str = "attributes.toml";
+
+/// Slots of the player's inventory
+const INVENTORY_SIZE: usize = 16;
+
+/// Items the inventory starts with, saved games replace them when they're loaded
+const SAMPLE_ITEMS: [(&str, u32); 6] = [
+    ("rock", 3),
+    ("rock", 1),
+    ("rock", 2),
+    ("rope", 1),
+    ("dev_tablet", 1),
+    ("spear", 1),
+];
 
 lazy_static! {
-    pub static ref INVENTORY: Mutex<ItemCollectionSized> = Mutex::new(generate_sample_inventory());
+    /// Starts out empty and is filled by `setup_inventory_system`.
+    ///
+    /// Code that needs both locks takes `ITEM_REGISTRY` before `INVENTORY`
+    pub static ref INVENTORY: Mutex<ItemCollectionSized> =
+        Mutex::new(ItemCollectionSized::new(INVENTORY_SIZE));
     pub static ref ITEM_REGISTRY: Mutex<ItemRegistry<DummyHook>> =
         Mutex::new(create_item_registry());
 }
 
-fn generate_sample_inventory() -> ItemCollectionSized {
-    let mut registry = ITEM_REGISTRY.lock().unwrap();
-
-    let mut inventory = ItemCollectionSized::new(16);
-    inventory
-        .add_item(ItemStack::new(registry.get("rock").unwrap().clone(), 3))
-        .expect("Failed to add item to inventory");
+fn generate_sample_inventory(registry: &ItemRegistry<DummyHook>) -> ItemCollectionSized {
+    let mut inventory = ItemCollectionSized::new(INVENTORY_SIZE);
 
-    inventory
-        .add_item(ItemStack::new(registry.get("rock").unwrap().clone(), 1))
-        .expect("Failed to add item to inventory");
+    for (ident, count) in SAMPLE_ITEMS {
+        let Some(item) = registry.get(ident) else {
+            godot_print!("Sample item {} isn't registered", ident);
+            continue;
+        };
 
-    inventory
-        .add_item(ItemStack::new(registry.get("rock").unwrap().clone(), 2))
-        .expect("Failed to add item to inventory");
+        if let Err(e) = inventory.add_item(ItemStack::new(item.clone(), count)) {
+            godot_print!("Failed to add sample item {}: {}", ident, e);
+        }
+    }
 
     inventory
-        .add_item(ItemStack::new(registry.get("rope").unwrap().clone(), 1))
-        .expect("Failed to add item to inventory");
-
-    inventory
-        .add_item(ItemStack::new(registry.get("dev_tablet").unwrap().clone(), 1))
-        .expect("Failed to add item to inventory");
-
-    let spear_template = ItemTemplate::load_template("spear").unwrap();
-
-    let components = hashmap! {
-        "shaft".to_owned() => registry.get("rock").unwrap().clone(),
-        "tip".to_owned() => registry.get("rock").unwrap().clone(),
-    };
-
-    let spear = spear_template.populate_template(components);
-
-    registry.register("spear".to_owned(), Arc::new(spear));
-
-    inventory
-        .add_item(ItemStack::new(registry.get("spear").unwrap().clone(), 1))
-        .expect("Failed to add item to inventory");
+}
 
-    inventory
+/// Fill the inventory with the sample items
+pub fn setup_inventory_system() {
+    let registry = ITEM_REGISTRY.lock().unwrap();
+    let loaded = generate_sample_inventory(&registry);
+    drop(registry);
+
+    // The slots are replaced one by one, the collection keeps its listeners
+    let mut loaded = loaded.into_iter();
+    let mut inventory = INVENTORY.lock().unwrap();
+    for slot in inventory.get_items_mut().iter_mut() {
+        *slot = loaded.next().unwrap_or_else(Air::new_itemstack);
+    }
+    inventory.refresh();
 }
 
 pub fn create_item_registry() -> ItemRegistry<DummyHook> {
     let mut registry: ItemRegistry = ItemRegistry::new();
 
-    registry.register("rock".to_owned(), {
-        let rock = Rock::new();
-        godot_print!("Registered rock item: {:?}", rock);
-        Arc::new(rock)
-    });
-
     registry.register("air".to_owned(), {
         let air = Air::new();
         godot_print!("Registered air item: {:?}", air);
         Arc::new(air)
     });
 
-    registry.register("rope".to_owned(), {
-        let rope = Rope::new();
-        godot_print!("Registered rope item: {:?}", rope);
-        Arc::new(rope)
-    });
-
-     registry.register("dev_tablet".to_owned(), {
-        let dev_tablet = DevTablet::new();
-        godot_print!("Registered dev_tablet item: {:?}", dev_tablet);
-        Arc::new(dev_tablet)
-    });
+    // Items may use the extra attributes, so they have to be known first
+    match ATTRIBUTE_REGISTRY.write().unwrap().load(&*asset_source(), ATTRIBUTES_FILE) {
+        Ok(attributes) => godot_print!("Registered attributes: {:?}", attributes),
+        Err(e) => godot_print!("Failed to load attributes: {}", e),
+    }
+
+    match registry.load_data_items(&*asset_source(), ITEMS_FOLDER) {
+        Ok(loaded) => {
+            godot_print!("Registered data items: {:?}", loaded.idents);
+            for e in loaded.errors {
+                godot_print!("Failed to load data item: {}", e);
+            }
+        }
+        Err(e) => godot_print!("Failed to load data items: {}", e),
+    }
+
+    match (load_template("spear"), registry.get("rock").cloned()) {
+        (Ok(spear_template), Some(rock)) => {
+            let components = hashmap! {
+                "shaft".to_owned() => rock.clone(),
+                "tip".to_owned() => rock,
+            };
+
+            let spear = spear_template.populate_template(components);
+            registry.register("spear".to_owned(), Arc::new(spear));
+        }
+        (Err(e), _) => godot_print!("Failed to load the spear template: {}", e),
+        (_, None) => godot_print!("The spear needs the rock item"),
+    }
 
     registry
 }
@@ -1 +1,4 @@
+pub mod context;
+pub mod crafting;
+pub mod equipment;
 pub mod inventory;
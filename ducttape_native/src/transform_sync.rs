@@ -0,0 +1,97 @@
+use bevy::{
+    ecs::{
+        change_detection::{DetectChanges, Ref},
+        component::Component,
+        query::{Added, Changed},
+        system::Query,
+    },
+    math::{EulerRot, Quat, Vec3},
+    transform::components::Transform,
+};
+use bevy_godot4::prelude::ErasedGd;
+use godot::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::game_entities::try_get_node;
+
+/// Which side owns the position of an entity, the other side follows it.
+///
+/// Transforms use Godot's 2D coordinates as they are, x to the right and y down, rotated around z
+#[derive(Component, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransformSync {
+    /// Moved by game code through its `Transform`, the node is moved to match
+    #[default]
+    Bevy,
+    /// Moved by Godot physics, like the player or rigid bodies, the `Transform` is updated to match
+    Godot,
+}
+
+fn to_godot(transform: &Transform, node: &mut Gd<Node2D>) {
+    node.set_global_position(Vector2::new(
+        transform.translation.x,
+        transform.translation.y,
+    ));
+    node.set_global_rotation(transform.rotation.to_euler(EulerRot::ZYX).0);
+    node.set_global_scale(Vector2::new(transform.scale.x, transform.scale.y));
+}
+
+fn from_godot(node: &Gd<Node2D>, transform: &Transform) -> Transform {
+    let position = node.get_global_position();
+    let scale = node.get_global_scale();
+
+    Transform {
+        translation: Vec3::new(position.x, position.y, transform.translation.z),
+        rotation: Quat::from_rotation_z(node.get_global_rotation()),
+        scale: Vec3::new(scale.x, scale.y, transform.scale.z),
+    }
+}
+
+/// Put new nodes where their entity was spawned, whichever side owns the transform afterwards.
+///
+/// Entities can outlive their nodes, e.g. a pickup freed on the Godot side, so every sync
+/// system skips entities whose node is gone
+pub fn place_spawned_nodes_system(query: Query<(&ErasedGd, &Transform), Added<ErasedGd>>) {
+    for (node, transform) in query.iter() {
+        if let Some(mut node) = try_get_node(node) {
+            to_godot(transform, &mut node);
+        }
+    }
+}
+
+/// Move the nodes of Bevy owned entities to their transform, runs after
+/// `place_spawned_nodes_system`
+pub fn push_transforms_system(
+    query: Query<(&ErasedGd, &Transform, &TransformSync), Changed<Transform>>,
+) {
+    for (node, transform, sync) in query.iter() {
+        if *sync != TransformSync::Bevy {
+            continue;
+        }
+
+        if let Some(mut node) = try_get_node(node) {
+            to_godot(transform, &mut node);
+        }
+    }
+}
+
+/// Update the transforms of Godot owned entities to where their nodes are, runs after
+/// `place_spawned_nodes_system`
+pub fn pull_transforms_system(mut query: Query<(Ref<ErasedGd>, &mut Transform, &TransformSync)>) {
+    for (node, mut transform, sync) in query.iter_mut() {
+        // New nodes are still waiting to be placed where they were spawned
+        if *sync != TransformSync::Godot || node.is_added() {
+            continue;
+        }
+
+        let Some(node) = try_get_node(&node) else {
+            continue;
+        };
+
+        let pulled = from_godot(&node, &transform);
+
+        // Only write changes, so change detection keeps meaning something
+        if pulled != *transform {
+            *transform = pulled;
+        }
+    }
+}
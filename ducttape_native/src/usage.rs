@@ -0,0 +1,185 @@
+use std::sync::Mutex;
+
+use bevy::{
+    ecs::{
+        component::Component,
+        query::Added,
+        system::{Commands, Query},
+    },
+    math::Vec3,
+    transform::components::Transform,
+};
+use bevy_godot4::prelude::ErasedGd;
+use ducttape_item_engine::{
+    equipment::MAIN_HAND,
+    item::{Item, ItemStack},
+    usage::{use_item, ItemAction, BRIDGE_OVERHANG},
+};
+use godot::{
+    classes::{Area2D, RigidBody2D, TextureRect},
+    prelude::*,
+};
+use lazy_static::lazy_static;
+
+use crate::{
+    game_entities::GameEntity,
+    player::Player,
+    singletons::{context::attribute_context, equipment::EQUIPMENT},
+    template::loader::set_item_texture,
+};
+
+/// Size of the collision shapes in the scenes, spawned entities are scaled to the action
+const SWING_SHAPE_WIDTH: f32 = 32.0;
+const BRIDGE_SHAPE_LENGTH: f32 = 32.0;
+const ROPE_SHAPE_LENGTH: f32 = 32.0;
+
+/// Nodes hit by a swing get `_on_item_hit(damage)` called if they have it
+pub const ITEM_HIT_METHOD: &str = "_on_item_hit";
+
+/// The entity spawned for an action
+pub fn get_action_entity(action: &ItemAction) -> GameEntity {
+    match action {
+        ItemAction::Swing { .. } => GameEntity::Swing,
+        ItemAction::Throw { .. } => GameEntity::ThrownItem,
+        ItemAction::PlaceBridge { .. } => GameEntity::Bridge,
+        ItemAction::TieRope { .. } => GameEntity::Rope,
+    }
+}
+
+/// An item used in the world, attached to the entity spawned for it
+#[derive(Component, Debug, Clone)]
+pub struct ItemUse {
+    pub action: ItemAction,
+    /// The used item, always a single item
+    pub stack: ItemStack,
+    /// Where the player was when using the item
+    pub origin: Vector2,
+    /// 1 when the player faces right, -1 when facing left
+    pub facing: f32,
+}
+
+lazy_static! {
+    /// Item uses waiting for their entity, filled by the player and drained by `item_use_system`
+    static ref ITEM_USE_QUEUE: Mutex<Vec<ItemUse>> = Mutex::new(Vec::new());
+}
+
+/// Use the item in the player's main hand, returns the action that was taken
+pub fn use_held_item(origin: Vector2, facing: f32, gap_ahead: bool) -> Option<ItemAction> {
+    let mut equipment = EQUIPMENT.lock().unwrap();
+    let index = equipment.get_slot_index(MAIN_HAND)?;
+
+    let context = attribute_context().with_equipped(true);
+    let used = use_item(&mut *equipment, index, context, gap_ahead, facing)?;
+
+    if used.broken {
+        godot_print!("{} broke", used.stack.get_name());
+        return None;
+    }
+
+    ITEM_USE_QUEUE.lock().unwrap().push(ItemUse {
+        action: used.action.clone(),
+        stack: used.stack,
+        origin,
+        facing,
+    });
+
+    Some(used.action)
+}
+
+/// Spawn the entities of the queued item uses
+pub fn item_use_system(mut commands: Commands) {
+    for item_use in ITEM_USE_QUEUE.lock().unwrap().drain(..) {
+        let kind = get_action_entity(&item_use.action);
+
+        match kind.spawn_with_transform(&mut commands, item_use.get_transform()) {
+            Ok(entity) => {
+                commands.entity(entity).insert(item_use);
+            }
+            Err(e) => godot_print!("Failed to spawn {}: {}", kind, e),
+        }
+    }
+}
+
+/// Place and configure the nodes of item uses once their scene is in the tree
+pub fn setup_item_use_system(mut query: Query<(&mut ErasedGd, &ItemUse), Added<ErasedGd>>) {
+    for (mut node, item_use) in query.iter_mut() {
+        item_use.setup(node.get::<Node2D>());
+    }
+}
+
+impl ItemUse {
+    /// Where the entity of the action is placed and how it is stretched to the action's size
+    pub fn get_transform(&self) -> Transform {
+        let (offset, scale) = match self.action {
+            ItemAction::Swing { reach, .. } => (
+                Vector2::new(self.facing * reach / 2.0, 0.0),
+                Vector2::new(reach / SWING_SHAPE_WIDTH, 1.0),
+            ),
+            ItemAction::Throw { .. } => (Vector2::ZERO, Vector2::ONE),
+            ItemAction::PlaceBridge { length } => (
+                Vector2::new(self.facing * (length / 2.0 - BRIDGE_OVERHANG), 0.0),
+                Vector2::new(length / BRIDGE_SHAPE_LENGTH, 1.0),
+            ),
+            ItemAction::TieRope { length } => (
+                Vector2::new(self.facing * BRIDGE_OVERHANG, 0.0),
+                Vector2::new(1.0, length / ROPE_SHAPE_LENGTH),
+            ),
+        };
+
+        let position = self.origin + offset;
+        Transform::from_translation(Vec3::new(position.x, position.y, 0.0))
+            .with_scale(Vec3::new(scale.x, scale.y, 1.0))
+    }
+
+    fn setup(&self, mut node: Gd<Node2D>) {
+        if let Some(mut texture_rect) = node.try_get_node_as::<TextureRect>("Texture") {
+            if let Some(timer) = set_item_texture(&mut texture_rect, self.stack.get_texture()) {
+                node.add_child(&timer);
+            }
+        }
+
+        match self.action {
+            ItemAction::Swing { damage, .. } => {
+                node.cast::<Area2D>().connect(
+                    "body_entered",
+                    &Callable::from_local_fn("_on_swing_hit", move |args| {
+                        let body = args.first().and_then(|arg| arg.try_to::<Gd<Node>>().ok());
+                        if let Some(mut body) = body {
+                            if body.has_method(ITEM_HIT_METHOD) {
+                                body.call(ITEM_HIT_METHOD, &[damage.to_variant()]);
+                            }
+                        }
+                        Ok(Variant::nil())
+                    }),
+                );
+            }
+            ItemAction::Throw { velocity } => {
+                node.cast::<RigidBody2D>().set_linear_velocity(velocity);
+            }
+            ItemAction::PlaceBridge { .. } => {}
+            ItemAction::TieRope { .. } => {
+                let mut area = node.cast::<Area2D>();
+                area.connect(
+                    "body_entered",
+                    &Callable::from_local_fn("_on_rope_entered", |args| {
+                        set_player_climbable(args, true);
+                        Ok(Variant::nil())
+                    }),
+                );
+                area.connect(
+                    "body_exited",
+                    &Callable::from_local_fn("_on_rope_exited", |args| {
+                        set_player_climbable(args, false);
+                        Ok(Variant::nil())
+                    }),
+                );
+            }
+        }
+    }
+}
+
+fn set_player_climbable(args: &[&Variant], climbable: bool) {
+    if let Some(mut player) = args.first().and_then(|arg| arg.try_to::<Gd<Player>>().ok()) {
+        player.bind_mut().set_on_climbable(climbable);
+    }
+}